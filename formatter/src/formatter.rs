@@ -100,7 +100,7 @@ impl<'a> Formatter<'a> {
 
     fn space(&mut self) {
         self.write_cur_token();
-        self.string.push_str(" ");
+        self.string.push(' ');
     }
 
     fn next_token(&mut self) {
@@ -28,7 +28,7 @@ fn main() -> Result<(), std::io::Error> {
 
     let lexer = lexer::Lexer::new(&args.path, text, true);
 
-    let mut formatter = formatter::Formatter::new(&text, lexer);
+    let mut formatter = formatter::Formatter::new(text, lexer);
 
     let fmt_str = formatter.format();
 
@@ -0,0 +1,58 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use symboscript_interpreter::Interpreter;
+use symboscript_parser::Parser;
+
+/// System allocator that counts allocations, to see what a run costs besides the work itself
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const PROGRAM: &str = r#"
+let total = 0;
+repeat (1000) {
+    let map = hashmap.new[];
+    map.set["k", total];
+    mut total += map.get["k"] + 1;
+}
+"#;
+
+/// `hashmap.new[]` evaluates the members written in SymboScript, parsed once at startup.
+/// The allocations per map show that their ast is shared instead of copied on every call
+fn run(c: &mut Criterion) {
+    let ast = Parser::new("bench", PROGRAM).parse();
+
+    let mut interpreter = Interpreter::new("bench", PROGRAM, false);
+    interpreter.run(ast.clone());
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    interpreter.eval_ast(&ast);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("interpreter: {} allocations per map", allocations / 1000);
+
+    let mut group = c.benchmark_group("interpreter");
+    group.sample_size(20);
+    group.bench_function("hashmaps", |b| b.iter(|| interpreter.eval_ast(&ast)));
+    group.finish();
+}
+
+criterion_group!(benches, run);
+criterion_main!(benches);
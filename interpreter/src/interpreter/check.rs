@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use symboscript_lexer::LexerOptions;
+use symboscript_parser::{
+    visitor::{walk_ast, walk_expression, walk_statement},
+    Parser, Visitor,
+};
+use symboscript_types::{
+    diagnostic::{Diagnostic, Severity},
+    parser::*,
+};
+
+use super::{DeclarationMode, Interpreter, InterpreterConfig};
+
+impl Interpreter {
+    /// Finds problems in the program without running it, for linting in CI.
+    ///
+    /// Returns the parser's errors and lints and every use of a variable that is declared
+    /// nowhere in the program, the std library, the host functions or the preset globals.
+    /// Names are not resolved through scopes, a name declared anywhere counts as declared.
+    /// In [`DeclarationMode::Lax`] assignments declare their variable.
+    /// A source over the limits of the config is a diagnostic.
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let options = LexerOptions {
+            recover: true,
+            ..self.lexer_options()
+        };
+        let mut parser = Parser::with_lexer_options(&self.paths[0], &self.sources[0], options);
+        let ast = parser.parse();
+
+        let mut diagnostics: Vec<Diagnostic> = parser.diagnostics().iter().cloned().collect();
+
+        let mut declarations = Declarations {
+            names: self.builtin_names(),
+            assignments_declare: self.declaration_mode == DeclarationMode::Lax,
+        };
+        walk_ast(&mut declarations, &ast);
+
+        walk_ast(
+            &mut Uses {
+                declared: &declarations.names,
+                diagnostics: &mut diagnostics,
+            },
+            &ast,
+        );
+
+        diagnostics
+    }
+
+    /// Globals every program starts with
+    fn builtin_names(&self) -> HashSet<String> {
+        let config = InterpreterConfig {
+            natives: self.natives.clone(),
+            ..Default::default()
+        };
+        let mut builtins = Interpreter::with_config(&self.paths[0], "", false, config);
+        builtins.initialize();
+
+        let mut names: HashSet<String> = ["std$0", "global$0"]
+            .iter()
+            .flat_map(|scope| builtins.vault[*scope].values.keys().cloned())
+            .collect();
+
+        names.extend(self.host_functions.keys().cloned());
+        names.extend(self.preset_globals.keys().cloned());
+        names.extend(["this".to_owned(), "self".to_owned(), "global".to_owned()]);
+
+        names
+    }
+}
+
+struct Declarations {
+    names: HashSet<String>,
+    assignments_declare: bool,
+}
+
+impl Visitor for Declarations {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::VariableDeclaration(decl) => match &decl.pattern {
+                Some(names) => self.names.extend(names.iter().cloned()),
+                None => {
+                    self.names.insert(decl.id.clone());
+                }
+            },
+            Statement::FunctionDeclaration(decl) => {
+                self.names.insert(decl.id.clone());
+                self.names.extend(decl.params.iter().cloned());
+            }
+            Statement::ScopeDeclaration(decl) => {
+                self.names.insert(decl.id.clone());
+            }
+            Statement::ContextDeclaration(decl) => {
+                self.names.insert(decl.id.clone());
+            }
+            Statement::WhileStatement(stmt) => {
+                self.names.extend(stmt.binding.iter().cloned());
+            }
+            Statement::WithStatement(stmt) => {
+                self.names.insert(stmt.name.clone());
+            }
+            Statement::ImportStatement(import) => {
+                self.names.insert(import.as_name.name.clone());
+            }
+            Statement::AssignStatement(assign) if self.assignments_declare => {
+                self.names.insert(assign.left.name.clone());
+            }
+            _ => {}
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::FunctionExpression(function) = expression {
+            self.names.extend(function.params.iter().cloned());
+        }
+
+        walk_expression(self, expression);
+    }
+}
+
+struct Uses<'a> {
+    declared: &'a HashSet<String>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Uses<'_> {
+    fn use_name(&mut self, name: &str, node: Node) {
+        if !self.declared.contains(name) {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "undefined_variable",
+                message: format!("Variable `{name}` not found"),
+                span: node.start..node.end,
+                note: None,
+            });
+        }
+    }
+
+    /// Arguments of a call, the callee is resolved elsewhere
+    fn visit_arguments(&mut self, call: &CallExpression) {
+        self.visit_expression(&call.arguments);
+        for arguments in &call.chained {
+            self.visit_expression(arguments);
+        }
+    }
+}
+
+impl Visitor for Uses<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::AssignStatement(assign) = statement {
+            self.use_name(&assign.left.name, assign.left.node);
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(id) => self.use_name(&id.name, id.node),
+            Expression::CallExpression(call) => {
+                self.use_name(&call.callee, call.node);
+                self.visit_arguments(call);
+            }
+            // members are looked up in the object, only computed properties are variables
+            Expression::MemberExpression(member) => {
+                self.visit_expression(&member.object);
+
+                match &member.property {
+                    Expression::Identifier(_) if !member.is_expr => {}
+                    Expression::CallExpression(call) => self.visit_arguments(call),
+                    property => self.visit_expression(property),
+                }
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use super::{DeclarationMode, FileSystemAccess, NumberMode, OutputSink, OverflowMode};
+
+/// Environment a script runs in, for embedders that need more than [`Interpreter::new`].
+///
+/// [`Interpreter::new`]: super::Interpreter::new
+pub struct InterpreterConfig {
+    /// Globals of the std library to register, like `print` or `json`. `None` registers all of them
+    pub natives: Option<HashSet<String>>,
+
+    pub number_mode: NumberMode,
+
+    /// What integer arithmetic does on overflow
+    pub overflow_mode: OverflowMode,
+
+    /// What assigning to an undeclared variable does
+    pub declaration_mode: DeclarationMode,
+
+    /// Whether scripts can read and write files
+    pub fs_access: FileSystemAccess,
+
+    /// Receives lines of `print` and `println` instead of stdout
+    pub output: Option<OutputSink>,
+
+    /// Receives lines of `eprint` and `eprintln` instead of stderr
+    pub error_output: Option<OutputSink>,
+
+    /// Put between the values of one `print` call
+    pub print_separator: String,
+
+    /// Longest source in bytes that is parsed, see [`LexerOptions::max_source_len`].
+    /// Applies to [`Interpreter::check`], imports and `eval_expr`, the program given to `run`
+    /// is parsed by the caller with [`Interpreter::lexer_options`]
+    ///
+    /// [`LexerOptions::max_source_len`]: symboscript_lexer::LexerOptions::max_source_len
+    /// [`Interpreter::check`]: super::Interpreter::check
+    /// [`Interpreter::lexer_options`]: super::Interpreter::lexer_options
+    pub max_source_len: Option<usize>,
+
+    /// Most tokens lexed from one source, see [`LexerOptions::max_tokens`]
+    ///
+    /// [`LexerOptions::max_tokens`]: symboscript_lexer::LexerOptions::max_tokens
+    pub max_tokens: Option<usize>,
+}
+
+impl Default for InterpreterConfig {
+    fn default() -> Self {
+        Self {
+            natives: None,
+            number_mode: NumberMode::default(),
+            overflow_mode: OverflowMode::default(),
+            declaration_mode: DeclarationMode::default(),
+            fs_access: FileSystemAccess::default(),
+            output: None,
+            error_output: None,
+            print_separator: " ".to_owned(),
+            max_source_len: None,
+            max_tokens: None,
+        }
+    }
+}
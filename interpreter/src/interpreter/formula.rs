@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use symboscript_parser::{visitor::walk_expression, Visitor};
+use symboscript_types::{interpreter::Value, lexer::TokenValue, parser::*};
+
+/// Last value of a `:=` formula and the values of the variables it was computed from
+pub struct CachedFormula {
+    pub value: Value,
+
+    /// Names the formula reads and their values
+    pub dependencies: Vec<(String, Value)>,
+}
+
+/// Variables a formula reads: identifiers, callees and member objects.
+/// Member properties are looked up in their object and function expressions
+/// aren't run by the formula, so neither is included
+pub fn dependencies(formula: &Expression) -> Vec<String> {
+    let mut names = Names::default();
+    names.visit_expression(formula);
+    names.order
+}
+
+/// Copy of a dependency to compare later values against. Arrays are copied,
+/// so items pushed or set in place count as a change
+pub fn snapshot(value: &Value) -> Value {
+    match value {
+        Value::Sequence(seq) => {
+            Value::new_sequence(seq.borrow().items.iter().map(snapshot).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
+#[derive(Default)]
+struct Names {
+    seen: HashSet<String>,
+    order: Vec<String>,
+}
+
+impl Names {
+    fn add(&mut self, name: &str) {
+        if self.seen.insert(name.to_owned()) {
+            self.order.push(name.to_owned());
+        }
+    }
+}
+
+impl Visitor for Names {
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(id) => self.add(&id.name),
+            Expression::Literal(Literal {
+                value: TokenValue::Identifier(name),
+                ..
+            }) => self.add(name),
+            Expression::CallExpression(call) => {
+                self.add(&call.callee);
+                walk_expression(self, expression);
+            }
+            Expression::FunctionExpression(_) => {}
+            Expression::MemberExpression(member) => {
+                self.visit_expression(&member.object);
+                match &member.property {
+                    Expression::Identifier(_) if !member.is_expr => {}
+                    Expression::CallExpression(call) => {
+                        self.visit_expression(&call.arguments);
+                        for arguments in &call.chained {
+                            self.visit_expression(arguments);
+                        }
+                    }
+                    property => self.visit_expression(property),
+                }
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+}
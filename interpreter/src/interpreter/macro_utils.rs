@@ -3,7 +3,24 @@ macro_rules! loop_controls {
     ($self:ident, $block: expr) => {
         let control = $self.eval_block(&$block);
         match control {
-            ControlFlow::Break => break,
+            ControlFlow::Break(_) => break,
+            ControlFlow::None(_) | ControlFlow::Continue => {}
+
+            _ => {
+                $self.decrement_scope();
+                return control;
+            }
+        }
+    };
+
+    // sets `$broke` when the loop is left with `break`
+    ($self:ident, $block: expr, $broke: ident) => {
+        let control = $self.eval_block(&$block);
+        match control {
+            ControlFlow::Break(_) => {
+                $broke = true;
+                break;
+            }
             ControlFlow::None(_) | ControlFlow::Continue => {}
 
             _ => {
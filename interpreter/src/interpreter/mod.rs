@@ -1,4 +1,8 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
 
 use rand::{distributions::Alphanumeric, Rng};
 use symboscript_types::{interpreter::*, lexer::*, parser::*};
@@ -6,13 +10,30 @@ use symboscript_utils::report_error;
 
 use colored::Colorize;
 
+mod check;
+mod config;
+mod formula;
 mod macro_utils;
 mod native;
+mod number;
+
+pub use config::InterpreterConfig;
+pub use native::fs::FileSystemAccess;
+pub use native::host::HostFunction;
+use native::io::Output;
+pub use native::io::OutputSink;
+pub use number::{NumberMode, OverflowMode};
+
+#[cfg(test)]
+mod tests;
 
 use crate::loop_controls;
+use symboscript_lexer::LexerOptions;
 use symboscript_parser as parser;
 
+use self::formula::{dependencies, snapshot, CachedFormula};
 use self::native::{get_values, StdLang};
+use self::number::{integer_binary, Number};
 
 pub struct Interpreter {
     /// Path of the source file
@@ -30,6 +51,142 @@ pub struct Interpreter {
     std_lang: StdLang,
 
     repl: bool,
+
+    number_mode: NumberMode,
+
+    /// What integer arithmetic does on overflow
+    overflow_mode: OverflowMode,
+
+    /// Whether scripts can read and write files
+    fs_access: FileSystemAccess,
+
+    /// Functions registered by the embedding program
+    host_functions: HashMap<String, HostFunction>,
+
+    /// Globals set by the embedding program
+    preset_globals: HashMap<String, Value>,
+
+    /// Lines of `print` and `println`
+    stdout: Output,
+
+    /// Lines of `eprint` and `eprintln`
+    stderr: Output,
+
+    /// Put between the values of one `print` call
+    print_separator: String,
+
+    /// Control flow raised inside an expression: the error of a failed `try`, or a `return`,
+    /// `break`, `continue` or `throw` in a block expression. The rest of the statement is skipped,
+    /// then the enclosing block passes it on
+    interrupt: Option<ControlFlow>,
+
+    /// What assigning to an undeclared variable does
+    declaration_mode: DeclarationMode,
+
+    /// Std library globals to register, all of them when `None`
+    natives: Option<HashSet<String>>,
+
+    /// Scope the function called next is a method of, its body sees it as `self` and `this`
+    method_scope: Option<String>,
+
+    /// Longest source and most tokens parsed, for untrusted scripts
+    max_source_len: Option<usize>,
+    max_tokens: Option<usize>,
+
+    /// Values of `:=` formulas computed by `eval`, by the text of the formula
+    formulas: HashMap<String, CachedFormula>,
+
+    /// Formulas being computed, to report ones that depend on themselves
+    evaluating_formulas: HashSet<String>,
+}
+
+/// What `mut x = 5` does when `x` is declared nowhere
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DeclarationMode {
+    /// The assignment is an error, variables must be declared with `let` first
+    #[default]
+    Strict,
+
+    /// The assignment declares a global variable
+    Lax,
+}
+
+/// A method found on a value, bound to it so it can be called later: `let up = "a".upper;`
+fn bind_method(value: Value, receiver: &Option<Value>) -> Value {
+    match (value, receiver) {
+        (Value::NativeFunction(NativeFunction::Method(function)), Some(receiver)) => {
+            Value::BoundMethod(Box::new(BoundMethod {
+                receiver: receiver.clone(),
+                function: *function,
+            }))
+        }
+        (value, _) => value,
+    }
+}
+
+/// `name.close[]` of a `with` statement
+fn close_call(with_stmt: &WithStatement) -> Expression {
+    let node = with_stmt.node;
+
+    Expression::MemberExpression(Box::new(MemberExpression {
+        node,
+        object: Expression::Identifier(Identifier {
+            node,
+            name: with_stmt.name.clone(),
+        }),
+        property: Expression::CallExpression(Box::new(CallExpression {
+            node,
+            callee: "close".to_owned(),
+            arguments: Expression::SequenceExpression(Box::new(SequenceExpression {
+                node,
+                expressions: vec![],
+            })),
+            chained: vec![],
+        })),
+        is_expr: false,
+    }))
+}
+
+/// Puts named arguments at the position of their parameter, after the positional ones.
+/// Parameters left out at the end stay missing, so curried functions still get partially applied.
+fn bind_named_arguments(
+    function: &Value,
+    args: Vec<Value>,
+    named: Vec<(String, Value)>,
+) -> Result<Vec<Value>, String> {
+    let (declarator, bound) = match function {
+        Value::Function(declarator) => (declarator, 0),
+        Value::PartialFunction(partial) => (&partial.function, partial.args.len()),
+        _ => return Err("Named arguments can only be passed to declared functions".to_owned()),
+    };
+
+    let params = &declarator.params[bound.min(declarator.params.len())..];
+
+    let mut slots: Vec<Option<Value>> = args.into_iter().map(Some).collect();
+    if slots.len() < params.len() {
+        slots.resize(params.len(), None);
+    }
+
+    for (name, value) in named {
+        let Some(index) = params.iter().position(|param| *param == name) else {
+            return Err(format!("Unknown argument `{name}`"));
+        };
+
+        if slots[index].is_some() {
+            return Err(format!("Argument `{name}` is given more than once"));
+        }
+
+        slots[index] = Some(value);
+    }
+
+    let filled = slots.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+    slots.truncate(filled);
+
+    slots
+        .into_iter()
+        .enumerate()
+        .map(|(i, slot)| slot.ok_or_else(|| format!("Missing argument `{}`", params[i])))
+        .collect()
 }
 
 fn get_full_path(path: &str) -> String {
@@ -41,6 +198,16 @@ fn get_full_path(path: &str) -> String {
 
 impl Interpreter {
     pub fn new(path: &str, source: &str, print_expr: bool) -> Self {
+        Self::with_config(path, source, print_expr, InterpreterConfig::default())
+    }
+
+    /// Interpreter whose std library, modes and output are set by `config`
+    pub fn with_config(
+        path: &str,
+        source: &str,
+        print_expr: bool,
+        config: InterpreterConfig,
+    ) -> Self {
         let vault = Vault::new();
 
         Self {
@@ -51,22 +218,173 @@ impl Interpreter {
             vault,
             std_lang: get_values(),
             repl: print_expr,
+            number_mode: config.number_mode,
+            overflow_mode: config.overflow_mode,
+            fs_access: config.fs_access,
+            host_functions: HashMap::new(),
+            preset_globals: HashMap::new(),
+            stdout: Output::new(config.output),
+            stderr: Output::new(config.error_output),
+            print_separator: config.print_separator,
+            interrupt: None,
+            declaration_mode: config.declaration_mode,
+            natives: config.natives,
+            method_scope: None,
+            max_source_len: config.max_source_len,
+            max_tokens: config.max_tokens,
+            formulas: HashMap::new(),
+            evaluating_formulas: HashSet::new(),
+        }
+    }
+
+    pub fn with_number_mode(mut self, number_mode: NumberMode) -> Self {
+        self.number_mode = number_mode;
+        self
+    }
+
+    pub fn with_overflow_mode(mut self, overflow_mode: OverflowMode) -> Self {
+        self.overflow_mode = overflow_mode;
+        self
+    }
+
+    pub fn with_declaration_mode(mut self, declaration_mode: DeclarationMode) -> Self {
+        self.declaration_mode = declaration_mode;
+        self
+    }
+
+    pub fn with_fs_access(mut self, fs_access: FileSystemAccess) -> Self {
+        self.fs_access = fs_access;
+        self
+    }
+
+    /// Sends printed lines to `sink` as they are printed instead of writing them to stdout
+    pub fn with_output(mut self, sink: OutputSink) -> Self {
+        self.stdout.sink = Some(sink);
+        self
+    }
+
+    /// Sends lines of `eprint` and `eprintln` to `sink` instead of writing them to stderr
+    pub fn with_error_output(mut self, sink: OutputSink) -> Self {
+        self.stderr.sink = Some(sink);
+        self
+    }
+
+    /// Text between the values printed by one call, a space by default: `print["a", 1]` is `a 1`
+    pub fn with_print_separator(mut self, separator: &str) -> Self {
+        self.print_separator = separator.to_owned();
+        self
+    }
+
+    /// Makes a Rust function callable from scripts as the global `name`, register before `run`
+    pub fn register_fn(&mut self, name: &str, function: HostFunction) {
+        self.host_functions.insert(name.to_owned(), function);
+    }
+
+    /// Declares `name` in the global scope before the script runs, set before `run`
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.preset_globals.insert(name.to_owned(), value);
+    }
+
+    /// Lexer options with the limits of the config, imports and `eval_expr` are parsed with them
+    pub fn lexer_options(&self) -> LexerOptions {
+        LexerOptions {
+            max_source_len: self.max_source_len,
+            max_tokens: self.max_tokens,
+            ..Default::default()
         }
     }
 
+    /// Runs a parsed program. Parse it with [`Interpreter::lexer_options`]
+    /// for `max_source_len` and `max_tokens` to apply to it
     pub fn run(&mut self, ast: Ast) {
         self.initialize();
 
-        self.eval_ast(ast);
+        let control = self.eval_ast(&ast);
+        self.run_defers();
+        native::io::flush(self);
+
+        if let ControlFlow::Throw(thrown) = control {
+            if let Value::Err(err) = &thrown {
+                if let Some(origin) = err.origin {
+                    self.report(
+                        &format!("Uncaught error: {}", err),
+                        origin.start,
+                        origin.end,
+                    );
+                }
+            }
+
+            self.print_uncaught(&thrown);
+            std::process::exit(1);
+        }
+    }
+
+    /// Evaluates a single expression like `2 + 3 * 4`, for calculators and config values.
+    ///
+    /// The std library is set up on the first call, globals of a program that ran before are visible
+    pub fn eval_expr(&mut self, source: &str) -> Value {
+        if !self.vault.contains_key("global$0") {
+            self.initialize();
+        }
+
+        let expression = parser::Parser::with_lexer_options("expr", source, self.lexer_options())
+            .parse_expression();
+
+        self.push_file("expr".to_owned(), source.to_owned());
+        let value = self.eval_expression(&expression);
+        self.paths.pop();
+        self.sources.pop();
+
+        match self.interrupt.take() {
+            Some(
+                ControlFlow::Throw(value) | ControlFlow::Return(value) | ControlFlow::Break(value),
+            ) => value,
+            _ => value,
+        }
+    }
+
+    /// Prints an error that was thrown out of the program
+    pub fn print_uncaught(&self, thrown: &Value) {
+        println!(
+            "{}",
+            format!("Uncaught error: {}", Value::new_error(thrown.clone()))
+                .red()
+                .bold()
+        );
     }
 
-    pub fn eval_ast(&mut self, ast: Ast) -> ControlFlow {
+    pub fn eval_ast(&mut self, ast: &Ast) -> ControlFlow {
+        self.hoist_functions(&ast.program.body);
         self.eval_block(&ast.program.body)
     }
 
+    /// Declares the functions of a program or scope body before it runs,
+    /// so they can be called above their declaration.
+    /// Decorated functions are declared where they are written, their decorators may not exist yet
+    fn hoist_functions(&mut self, body: &BlockStatement) {
+        for statement in body {
+            if let Statement::FunctionDeclaration(decl) = statement {
+                if !decl.decorators.is_empty() {
+                    continue;
+                }
+
+                self.declare_variable(&decl.id, Value::Function(decl.clone()));
+            }
+        }
+    }
+
     fn eval_block(&mut self, body: &BlockStatement) -> ControlFlow {
         for statement in body {
-            let control = self.eval_statement(&statement);
+            // `if (try f[]) { ... }` throws before the body runs
+            if let Some(control) = self.interrupt.take() {
+                return control;
+            }
+
+            let control = self.eval_statement(statement);
+
+            if let Some(control) = self.interrupt.take() {
+                return control;
+            }
 
             match control {
                 ControlFlow::None(v) => {
@@ -78,13 +396,13 @@ impl Interpreter {
             }
         }
 
-        return ControlFlow::None(Value::None);
+        ControlFlow::None(Value::None)
     }
 
     fn eval_statement(&mut self, statement: &Statement) -> ControlFlow {
         match statement {
             Statement::ExpressionStatement(expr) => {
-                return ControlFlow::None(self.eval_expression(&expr));
+                return ControlFlow::None(self.eval_expression(expr));
             }
 
             Statement::ReturnStatement(v) => {
@@ -93,11 +411,25 @@ impl Interpreter {
             Statement::ThrowStatement(v) => {
                 return ControlFlow::Throw(self.eval_expression(&v.argument));
             }
+            Statement::DeferStatement(defer_stmt) => {
+                let scope = self.current_scope.clone();
+
+                self.vault
+                    .get_mut(&scope)
+                    .unwrap()
+                    .defers
+                    .push(defer_stmt.body.clone());
+            }
             Statement::ContinueStatement(_) => {
                 return ControlFlow::Continue;
             }
-            Statement::BreakStatement(_) => {
-                return ControlFlow::Break;
+            Statement::BreakStatement(break_stmt) => {
+                let value = match &break_stmt.argument {
+                    Some(argument) => self.eval_expression(argument),
+                    None => Value::None,
+                };
+
+                return ControlFlow::Break(value);
             }
             Statement::YieldStatement(_) => todo!(),
             Statement::VariableDeclaration(decl) => {
@@ -107,33 +439,59 @@ impl Interpreter {
                     self.eval_expression(&decl.init)
                 };
 
-                self.declare_variable(&decl.id, value);
+                if self.interrupt.is_some() {
+                    return ControlFlow::None(Value::None);
+                }
+
+                match &decl.pattern {
+                    Some(names) => return self.destructure(names, value),
+                    None => self.declare_variable(&decl.id, value),
+                }
             }
             Statement::FunctionDeclaration(decl) => {
-                self.declare_variable(&decl.id, Value::Function(decl.clone()));
+                let function = self.decorate(decl);
+                self.declare_variable(&decl.id, function);
             }
             Statement::ScopeDeclaration(decl) => {
-                let scope = self.start_declaration_of_named_scope(&decl.id);
+                let base = decl
+                    .base
+                    .as_ref()
+                    .map(|base| self.get_base_scope(base, decl));
+
+                let scope = self.reopen_named_scope(&decl.id);
+                if base.is_some() {
+                    self.vault.get_mut(&scope).unwrap().base = base;
+                }
 
+                self.hoist_functions(&decl.body);
                 self.eval_block(&decl.body);
                 self.end_declaration_of_named_scope(&scope);
             }
             Statement::ContextDeclaration(decl) => {
                 let scope = self.start_declaration_of_named_scope(&decl.id);
-                self.declare_variable(&"this".to_owned(), Value::ScopeRef(scope.clone()));
+                self.declare_variable("this", Value::ScopeRef(scope.clone()));
+                self.hoist_functions(&decl.body);
                 self.eval_block(&decl.body);
                 self.end_declaration_of_named_scope(&scope);
             }
             Statement::IfStatement(if_stmt) => {
                 return self.eval_if_statement(if_stmt);
             }
-            Statement::ForStatement(_) => todo!(),
+            Statement::ForStatement(for_stmt) => {
+                return self.eval_for_statement(for_stmt);
+            }
             Statement::WhileStatement(while_stmt) => {
-                self.eval_while_statement(while_stmt);
+                return self.eval_while_statement(while_stmt);
             }
             Statement::LoopStatement(loop_stmt) => {
                 self.eval_loop_statement(loop_stmt);
             }
+            Statement::RepeatStatement(repeat_stmt) => {
+                return self.eval_repeat_statement(repeat_stmt);
+            }
+            Statement::WithStatement(with_stmt) => {
+                return self.eval_with_statement(with_stmt);
+            }
             Statement::BlockStatement(body) => {
                 self.increment_scope();
                 self.eval_block(body);
@@ -149,7 +507,7 @@ impl Interpreter {
             }
         }
 
-        return ControlFlow::None(Value::None);
+        ControlFlow::None(Value::None)
     }
 
     pub fn push_file(&mut self, path: String, source: String) {
@@ -182,7 +540,9 @@ impl Interpreter {
 
         match file_contents {
             Ok(contents) => {
-                let ast = parser::Parser::new(&file_path, &contents).parse();
+                let ast =
+                    parser::Parser::with_lexer_options(&file_path, &contents, self.lexer_options())
+                        .parse();
 
                 {
                     self.push_file(file_path.clone(), contents.clone());
@@ -192,18 +552,15 @@ impl Interpreter {
                             self.start_declaration_of_named_scope(&import_stmt.as_name.name);
 
                         // Declare standard variables
+                        self.declare_variable("__file__", Value::Str(file_path.clone()));
                         self.declare_variable(
-                            &"__file__".to_owned(),
-                            Value::Str(file_path.clone()),
-                        );
-                        self.declare_variable(
-                            &"__name__".to_owned(),
+                            "__name__",
                             Value::Str(import_stmt.as_name.name.clone()),
                         );
-                        self.declare_variable(&"__module__".to_owned(), Value::Bool(true));
+                        self.declare_variable("__module__", Value::Bool(true));
 
                         // Evaluate the AST
-                        self.eval_ast(ast);
+                        self.eval_ast(&ast);
                         self.end_declaration_of_named_scope(&scope);
                     }
 
@@ -220,113 +577,414 @@ impl Interpreter {
     }
 
     fn eval_assign_statement(&mut self, assign_stmt: &AssignStatement) -> ControlFlow {
+        // `??=` must not evaluate the right side once the variable holds a value
+        if assign_stmt.operator == AssignOperator::DefaultAssign
+            && self.is_declared(&assign_stmt.left.name)
+            && !matches!(self.get_variable_value_mut(&assign_stmt.left), Value::None)
+        {
+            return ControlFlow::None(Value::None);
+        }
+
         let right = self.eval_expression(&assign_stmt.right);
 
-        let var_val = self.get_variable_value_mut(&assign_stmt.left);
+        // a failed `try` leaves the variable as it was
+        if self.interrupt.is_some() {
+            return ControlFlow::None(Value::None);
+        }
 
-        match assign_stmt.operator {
-            AssignOperator::Assign => {
-                *var_val = right;
-            }
-            AssignOperator::PlusAssign => {
-                *var_val += right;
-            }
-            AssignOperator::MinusAssign => {
-                *var_val -= right;
-            }
-            AssignOperator::MultiplyAssign => {
-                *var_val *= right;
-            }
-            AssignOperator::DivideAssign => {
-                *var_val /= right;
-            }
-            AssignOperator::PowerAssign => {
-                *var_val = (*var_val).pow(&right);
+        if self.declaration_mode == DeclarationMode::Lax
+            && !self.is_declared(&assign_stmt.left.name)
+        {
+            self.vault
+                .get_mut("global$0")
+                .unwrap()
+                .values
+                .insert(assign_stmt.left.name.clone(), Value::None);
+        }
+
+        let value = match assign_stmt.operator.binary_operator() {
+            Some(operator) => {
+                let left = self.get_variable_value(&assign_stmt.left);
+
+                self.binary_operation(
+                    left,
+                    operator,
+                    right,
+                    assign_stmt.node,
+                    (assign_stmt.left.node, assign_stmt.right.node()),
+                )
             }
-            AssignOperator::ModuloAssign => {
-                *var_val %= right;
+            None => right,
+        };
+
+        *self.get_variable_value_mut(&assign_stmt.left) = value;
+
+        ControlFlow::None(Value::None)
+    }
+
+    /// Runs the body with the value bound to the name, then calls `close` of the value
+    /// however the body ends. Values without a `close` member are only bound
+    fn eval_with_statement(&mut self, with_stmt: &WithStatement) -> ControlFlow {
+        let value = self.eval_expression(&with_stmt.object);
+
+        if self.interrupt.is_some() {
+            return ControlFlow::None(Value::None);
+        }
+
+        self.increment_scope();
+        self.declare_variable(&with_stmt.name, value.clone());
+
+        let control = self.eval_block(&with_stmt.body);
+
+        let mut scope = match &value {
+            Value::ScopeRef(scope) => Some(scope.clone()),
+            _ => None,
+        };
+        while let Some(name) = scope {
+            let declared = &self.vault[&name];
+            if declared.values.contains_key("close") {
+                // the body may have changed the variable, close the value it was bound to
+                self.declare_variable(&with_stmt.name, value);
+                self.eval_expression(&close_call(with_stmt));
+                break;
             }
+            scope = declared.base.clone();
         }
 
-        ControlFlow::None(Value::None)
+        self.decrement_scope();
+
+        control
     }
 
     fn eval_if_statement(&mut self, if_stmt: &IfStatement) -> ControlFlow {
-        if (self.eval_expression(&if_stmt.test)).as_bool() {
-            return self.eval_block(&if_stmt.consequent);
+        if (self.eval_expression(&if_stmt.test)).is_truthy() {
+            self.eval_block(&if_stmt.consequent)
         } else {
-            return self.eval_block(&if_stmt.alternate);
+            self.eval_block(&if_stmt.alternate)
         }
     }
 
     fn eval_while_statement(&mut self, while_stmt: &WhileStatement) -> ControlFlow {
+        if let Some(name) = &while_stmt.binding {
+            return self.eval_while_let(while_stmt, name);
+        }
+
         self.increment_scope();
 
-        while (self.eval_expression(&while_stmt.test)).as_bool() {
-            loop_controls!(self, while_stmt.body);
+        let mut broke = false;
+        while (self.eval_expression(&while_stmt.test)).is_truthy() {
+            loop_controls!(self, while_stmt.body, broke);
         }
 
+        self.eval_loop_else(&while_stmt.alternate, broke)
+    }
+
+    /// `while let x = test`, runs until `test` gives `None`.
+    /// Every iteration has its own scope, so `x` and the body's variables don't outlive it.
+    fn eval_while_let(&mut self, while_stmt: &WhileStatement, name: &str) -> ControlFlow {
+        self.increment_scope();
+
+        let mut broke = false;
+        loop {
+            let value = self.eval_expression(&while_stmt.test);
+            if let Value::None = value {
+                break;
+            }
+
+            self.increment_scope();
+            self.declare_variable(name, value);
+            let control = self.eval_block(&while_stmt.body);
+            self.decrement_scope();
+
+            match control {
+                ControlFlow::Break(_) => {
+                    broke = true;
+                    break;
+                }
+                ControlFlow::None(_) | ControlFlow::Continue => {}
+
+                _ => {
+                    self.decrement_scope();
+                    return control;
+                }
+            }
+        }
+
+        self.eval_loop_else(&while_stmt.alternate, broke)
+    }
+
+    fn eval_for_statement(&mut self, for_stmt: &ForStatement) -> ControlFlow {
+        self.increment_scope();
+
+        self.eval_statement(&for_stmt.init);
+
+        let mut broke = false;
+        while (self.eval_expression(&for_stmt.test)).is_truthy() {
+            loop_controls!(self, for_stmt.body, broke);
+            self.eval_expression(&for_stmt.update);
+
+            if let Some(control) = self.interrupt.take() {
+                self.decrement_scope();
+                return control;
+            }
+        }
+
+        self.eval_loop_else(&for_stmt.alternate, broke)
+    }
+
+    /// Runs the `else` block of a loop that ended without `break` and leaves the loop scope
+    fn eval_loop_else(&mut self, alternate: &BlockStatement, broke: bool) -> ControlFlow {
+        let control = match broke {
+            true => ControlFlow::None(Value::None),
+            false => self.eval_block(alternate),
+        };
+
         self.decrement_scope();
 
-        return ControlFlow::None(Value::None);
+        control
     }
 
+    /// Ends with the value given to `break`
     fn eval_loop_statement(&mut self, loop_stmt: &LoopStatement) -> ControlFlow {
         self.increment_scope();
 
-        loop {
-            loop_controls!(self, loop_stmt.body);
+        let value = loop {
+            match self.eval_block(&loop_stmt.body) {
+                ControlFlow::Break(value) => break value,
+                ControlFlow::None(_) | ControlFlow::Continue => {}
+
+                control => {
+                    self.decrement_scope();
+                    return control;
+                }
+            }
+        };
+
+        self.decrement_scope();
+
+        ControlFlow::None(value)
+    }
+
+    /// `let x = loop { break 5; };`, a throw inside becomes an error value
+    fn eval_loop_expression(&mut self, loop_expr: &LoopStatement) -> Value {
+        match self.eval_loop_statement(loop_expr) {
+            ControlFlow::None(value) => value,
+            ControlFlow::Throw(thrown) => Value::new_error(thrown),
+            _ => Value::None,
+        }
+    }
+
+    /// Throws when the count isn't a non-negative integer
+    fn eval_repeat_statement(&mut self, repeat_stmt: &RepeatStatement) -> ControlFlow {
+        let count = match self.eval_expression(&repeat_stmt.count) {
+            Value::Number(n) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+            Value::Integer(n) if n >= 0 => n as usize,
+            got => {
+                return ControlFlow::Throw(Value::Str(format!(
+                    "repeat count must be a non-negative integer, got {got}"
+                )))
+            }
+        };
+
+        self.increment_scope();
+
+        for _ in 0..count {
+            loop_controls!(self, repeat_stmt.body);
         }
 
         self.decrement_scope();
 
-        return ControlFlow::None(Value::None);
+        ControlFlow::None(Value::None)
+    }
+
+    /// Binds `let [a, b] = value` positionally, the sequence length must match
+    fn destructure(&mut self, names: &[String], value: Value) -> ControlFlow {
+        let items = match &value {
+            Value::Sequence(seq) => seq.borrow().items.clone(),
+            Value::Range(range) => range.iter().collect(),
+            got => {
+                return ControlFlow::Throw(Value::Str(format!(
+                    "Can't destructure {} {got}, expected a sequence",
+                    got.type_name()
+                )))
+            }
+        };
+
+        if items.len() != names.len() {
+            return ControlFlow::Throw(Value::Str(format!(
+                "Can't destructure a sequence of {} items into {} names",
+                items.len(),
+                names.len()
+            )));
+        }
+
+        for (name, item) in names.iter().zip(items) {
+            self.declare_variable(name, item);
+        }
+
+        ControlFlow::None(Value::None)
     }
 
     fn eval_expression(&mut self, expression: &Expression) -> Value {
+        if self.interrupt.is_some() {
+            return Value::None;
+        }
+
         match expression {
             Expression::BinaryExpression(binary_expr) => self.eval_binary_expression(binary_expr),
             Expression::UnaryExpression(unary_expr) => self.eval_unary_expression(unary_expr),
             Expression::ConditionalExpression(_) => todo!(),
             Expression::CallExpression(call_expr) => self.eval_call_expression(call_expr),
             Expression::MemberExpression(member_expr) => self.eval_member_expression(member_expr),
-            Expression::SequenceExpression(_) => todo!(),
-            Expression::WordExpression(_) => todo!(),
+            Expression::SequenceExpression(seq_expr) => Value::new_sequence(
+                seq_expr
+                    .expressions
+                    .iter()
+                    .map(|expr| self.eval_expression(expr))
+                    .collect(),
+            ),
+            Expression::WordExpression(expr) => self.eval_word_expression(expr),
+            Expression::BlockExpression(block_expr) => self.eval_block_expression(block_expr),
+            Expression::LoopExpression(loop_expr) => self.eval_loop_expression(loop_expr),
+            Expression::FunctionExpression(function) => Value::Function((**function).clone()),
+            Expression::NamedArgument(_) => {
+                unreachable!("Named arguments are only parsed in call arguments")
+            }
 
             Expression::Literal(val) => self.match_literal(val),
 
-            Expression::Identifier(id) => self.get_variable_value(id),
+            Expression::Identifier(id) => self.read_variable(id),
 
             Expression::None(_) => Value::None,
         }
     }
 
+    /// `return`, `break`, `continue` and `throw` leave the block and the statement it is in,
+    /// like they would outside of the expression
+    fn eval_block_expression(&mut self, block_expr: &BlockExpression) -> Value {
+        self.increment_scope();
+
+        let value = match self.eval_block(&block_expr.body) {
+            ControlFlow::None(_) => self.eval_expression(&block_expr.value),
+            control => {
+                self.interrupt = Some(control);
+                Value::None
+            }
+        };
+
+        self.decrement_scope();
+
+        value
+    }
+
     fn set_native_value(&mut self, name: &str, value: Value) {
-        self.vault
+        let values = &mut self
+            .vault
             .get_mut(&format!("std$0.&{name}$0"))
             .unwrap()
-            .values
-            .insert("$value".to_owned(), value);
+            .values;
+
+        // error fields are readable as members: `err.message`, `err.code`, `err.start`, `err.end`
+        if let Value::Err(err) = &value {
+            values.insert("message".to_owned(), err.message.clone());
+            values.insert("code".to_owned(), err.code.clone());
+
+            let (start, end) = match err.origin {
+                Some(origin) => (
+                    Value::Number(origin.start as f64),
+                    Value::Number(origin.end as f64),
+                ),
+                None => (Value::None, Value::None),
+            };
+            values.insert("start".to_owned(), start);
+            values.insert("end".to_owned(), end);
+        }
+    }
+
+    /// `delete x` removes the variable from the innermost scope declaring it and gives its value.
+    /// `await x` gives the value of `x`, async functions run to completion when they are called
+    fn eval_word_expression(&mut self, expr: &WordExpression) -> Value {
+        match expr.operator {
+            WordOperator::Await => self.eval_expression(&expr.argument),
+            WordOperator::Try => match self.eval_expression(&expr.argument) {
+                Value::Err(err) => {
+                    self.interrupt = Some(ControlFlow::Throw(Value::Err(err)));
+                    Value::None
+                }
+                value => value,
+            },
+            WordOperator::Delete => match &expr.argument {
+                Expression::Identifier(id) => self.delete_variable(id),
+                argument => Value::new_runtime_error(
+                    "Only variables can be deleted".to_owned(),
+                    argument.node(),
+                ),
+            },
+        }
+    }
+
+    fn delete_variable(&mut self, identifier: &Identifier) -> Value {
+        let scope = self
+            .scope_stack
+            .iter()
+            .rev()
+            .find(|scope| self.vault[*scope].values.contains_key(&identifier.name))
+            .cloned();
+
+        match scope {
+            Some(scope) => self
+                .vault
+                .get_mut(&scope)
+                .unwrap()
+                .values
+                .shift_remove(&identifier.name)
+                .unwrap(),
+            None => {
+                self.report(
+                    &format!("Variable `{identifier}` not found"),
+                    identifier.node.start,
+                    identifier.node.end,
+                );
+                unreachable!("Report ends proccess");
+            }
+        }
     }
 
     fn eval_member_expression(&mut self, member_expr: &MemberExpression) -> Value {
-        let object = self.eval_expression(&member_expr.object);
+        let object = match &member_expr.object {
+            // `global.x` reaches the global scope from any depth, unless `global` is declared
+            Expression::Identifier(id) if id.name == "global" && !self.is_declared(&id.name) => {
+                Value::ScopeRef("global$0".to_owned())
+            }
+            _ => self.eval_expression(&member_expr.object),
+        };
+
+        // methods of values are bound to the value when they are looked up
+        let receiver = match &object {
+            Value::ScopeRef(_) => None,
+            value => Some(value.clone()),
+        };
 
         let object: Identifier = match object {
             Value::ScopeRef(ref_name) => Identifier {
                 name: ref_name.clone(),
-                node: member_expr.node.clone(),
+                node: member_expr.node,
             },
-            Value::Sequence(_) => self.native_id("sequence", object, member_expr.node.clone()),
-            Value::None => self.native_id("none", object, member_expr.node.clone()),
-            Value::Number(_) => self.native_id("number", object, member_expr.node.clone()),
-            Value::Bool(_) => self.native_id("bool", object, member_expr.node.clone()),
-            Value::Str(_) => self.native_id("str", object, member_expr.node.clone()),
-            Value::Ast(_) => self.native_id("ast", object, member_expr.node.clone()),
-            Value::Err(_) => self.native_id("err", object, member_expr.node.clone()),
+            Value::Sequence(_) | Value::Range(_) => {
+                self.native_id("sequence", object, member_expr.node)
+            }
+            Value::Bytes(_) => self.native_id("bytes", object, member_expr.node),
+            Value::None => self.native_id("none", object, member_expr.node),
+            Value::Number(_) | Value::Integer(_) => {
+                self.native_id("number", object, member_expr.node)
+            }
+            Value::Bool(_) => self.native_id("bool", object, member_expr.node),
+            Value::Str(_) => self.native_id("str", object, member_expr.node),
+            Value::Ast(_) => self.native_id("ast", object, member_expr.node),
+            Value::Err(_) => self.native_id("err", object, member_expr.node),
             _ => {
                 self.report(
-                    &format!("is not a scope"),
+                    "is not a scope",
                     member_expr.node.start,
                     member_expr.node.end,
                 );
@@ -334,37 +992,80 @@ impl Interpreter {
             }
         };
 
-        self.enter_named_scope(&object.name);
+        // base scopes are entered first, so own members shadow inherited ones
+        let mut scopes = vec![object.name.clone()];
+        while let Some(base) = self
+            .vault
+            .get(scopes.last().unwrap())
+            .and_then(|s| s.base.clone())
+        {
+            scopes.push(base);
+        }
+
+        for scope in scopes.iter().rev() {
+            self.enter_named_scope(scope);
+        }
 
         let property = match &member_expr.property {
             Expression::Identifier(id) => {
                 if member_expr.is_expr {
                     let property = self.eval_expression(&member_expr.property);
 
-                    self.get_variable_value(&Identifier {
+                    let value = self.read_variable(&Identifier {
                         name: property.to_string(),
-                        node: member_expr.node.clone(),
-                    })
+                        node: member_expr.node,
+                    });
+                    bind_method(value, &receiver)
                 } else {
-                    self.get_variable_value(id)
+                    let value = self.read_variable(id);
+                    bind_method(value, &receiver)
                 }
             }
-            Expression::CallExpression(call_expr) => self.eval_call_expression(call_expr),
+            Expression::CallExpression(call_expr) => match &receiver {
+                None => self.eval_call(call_expr, Some(&object.name)),
+                Some(_) => {
+                    let method = self.get_variable_value(&Identifier {
+                        name: call_expr.callee.clone(),
+                        node: call_expr.node,
+                    });
+                    self.call_chain(call_expr, bind_method(method, &receiver), None)
+                }
+            },
             _ => {
                 let property = self.eval_expression(&member_expr.property);
 
                 self.get_variable_value(&Identifier {
                     name: property.to_string(),
-                    node: member_expr.node.clone(),
+                    node: member_expr.node,
                 })
             }
         };
 
-        self.exit_named_scope();
+        for _ in &scopes {
+            self.exit_named_scope();
+        }
 
         property
     }
 
+    /// Resolves the scope named in `scope B : A`
+    fn get_base_scope(&mut self, base: &str, decl: &ScopeDeclarator) -> String {
+        match self.get_variable_value(&Identifier {
+            name: base.to_owned(),
+            node: decl.node,
+        }) {
+            Value::ScopeRef(scope) => scope,
+            _ => {
+                self.report(
+                    &format!("`{base}` is not a scope"),
+                    decl.node.start,
+                    decl.node.end,
+                );
+                unreachable!("Report ends proccess");
+            }
+        }
+    }
+
     fn native_id(&mut self, name: &str, value: Value, node: Node) -> Identifier {
         self.set_native_value(name, value);
         Identifier {
@@ -374,24 +1075,125 @@ impl Interpreter {
     }
 
     fn eval_call_expression(&mut self, call_expr: &CallExpression) -> Value {
+        self.eval_call(call_expr, None)
+    }
+
+    /// A call, `method_scope` is the scope of `scope.f[]`. Chained calls are not methods
+    fn eval_call(&mut self, call_expr: &CallExpression, method_scope: Option<&str>) -> Value {
         let var = self.get_variable_value(&Identifier {
             name: call_expr.callee.clone(),
-            node: call_expr.node.clone(),
+            node: call_expr.node,
         });
 
-        let args = match &call_expr.arguments {
+        self.call_chain(call_expr, var, method_scope)
+    }
+
+    /// Calls `function` with the arguments of the call, then the result with each chained list
+    fn call_chain(
+        &mut self,
+        call_expr: &CallExpression,
+        function: Value,
+        method_scope: Option<&str>,
+    ) -> Value {
+        let mut result =
+            self.call_with_arguments(call_expr, function, &call_expr.arguments, method_scope);
+
+        for arguments in &call_expr.chained {
+            result = self.call_with_arguments(call_expr, result, arguments, None);
+        }
+
+        result
+    }
+
+    /// Evaluates the arguments of a call, named ones are bound to the parameters of the same name
+    fn call_with_arguments(
+        &mut self,
+        call_expr: &CallExpression,
+        function: Value,
+        arguments: &Expression,
+        method_scope: Option<&str>,
+    ) -> Value {
+        let arguments = match arguments {
             Expression::SequenceExpression(seq_exp) => seq_exp,
             _ => unreachable!("Arguments can only be sequence expressions"),
         };
 
-        let args = args
-            .expressions
-            .iter()
-            .map(|expr| self.eval_expression(expr))
-            .collect::<Vec<Value>>();
+        let mut args = vec![];
+        let mut named = vec![];
+
+        for expr in &arguments.expressions {
+            match expr {
+                Expression::NamedArgument(arg) => {
+                    named.push((arg.name.clone(), self.eval_expression(&arg.value)))
+                }
+                _ => args.push(self.eval_expression(expr)),
+            }
+        }
+
+        if !named.is_empty() {
+            args = match bind_named_arguments(&function, args, named) {
+                Ok(args) => args,
+                Err(message) => return Value::new_error(Value::Str(message)),
+            };
+        }
+
+        // set after the arguments, calls in them are not methods of the scope
+        self.method_scope = method_scope.map(str::to_owned);
+        self.call_function(call_expr, function, &args)
+    }
+
+    /// Passes the declared function through its decorators, the nearest to `fn` first
+    fn decorate(&mut self, decl: &FunctionDeclarator) -> Value {
+        let mut function = Value::Function(decl.clone());
+
+        for decorator in decl.decorators.iter().rev() {
+            let call_expr = CallExpression {
+                node: decorator.node(),
+                callee: decorator.to_string(),
+                arguments: Expression::SequenceExpression(Box::new(SequenceExpression {
+                    node: decorator.node(),
+                    expressions: vec![],
+                })),
+                chained: vec![],
+            };
+
+            let decorator = self.eval_expression(decorator);
+            function = self.call_function(&call_expr, decorator, &[function]);
+        }
+
+        function
+    }
 
-        let result = match var {
-            Value::NativeFunction(name) => native::run_function(self, &call_expr, &name, &args),
+    /// Calls a function value with already evaluated arguments
+    fn call_function(
+        &mut self,
+        call_expr: &CallExpression,
+        function: Value,
+        args: &[Value],
+    ) -> Value {
+        let method_scope = self.method_scope.take();
+
+        // arguments after a failed `try` are not evaluated, neither is the call
+        if self.interrupt.is_some() {
+            return Value::None;
+        }
+
+        match function {
+            Value::NativeFunction(name) => native::run_function(self, call_expr, &name, args),
+            Value::BoundMethod(method) => native::call_method(self, call_expr, &method, args),
+            Value::Function(declarator)
+                if declarator.curried && args.len() < declarator.params.len() =>
+            {
+                Value::PartialFunction(Box::new(PartialFunction {
+                    function: declarator,
+                    args: args.to_vec(),
+                }))
+            }
+            Value::PartialFunction(partial) => {
+                let args = [partial.args.as_slice(), args].concat();
+                self.method_scope = method_scope;
+                self.call_function(call_expr, Value::Function(partial.function), &args)
+            }
             Value::Function(declarator) => {
                 if declarator.params.len() != args.len() {
                     self.report(
@@ -407,8 +1209,13 @@ impl Interpreter {
                 }
                 self.increment_scope();
 
+                if let Some(scope) = method_scope {
+                    self.declare_variable("self", Value::ScopeRef(scope.clone()));
+                    self.declare_variable("this", Value::ScopeRef(scope));
+                }
+
                 for (i, variable) in declarator.params.iter().enumerate() {
-                    self.declare_variable(&variable, args[i].clone());
+                    self.declare_variable(variable, args[i].clone());
                 }
 
                 let control = self.eval_block(&declarator.body);
@@ -416,7 +1223,7 @@ impl Interpreter {
                 self.decrement_scope();
                 match control {
                     ControlFlow::Return(val) => val,
-                    ControlFlow::Throw(val) => Value::Err(format!("{}", val)),
+                    ControlFlow::Throw(val) => Value::new_error(val),
                     _ => Value::None,
                 }
             }
@@ -429,33 +1236,143 @@ impl Interpreter {
                 );
                 unreachable!("Report ends proccess");
             }
-        };
-
-        result
+        }
     }
 
     fn eval_unary_expression(&mut self, expression: &UnaryExpression) -> Value {
+        let updates = matches!(
+            expression.operator,
+            UnaryOperator::PlusPlus | UnaryOperator::MinusMinus
+        );
+        if updates && !matches!(expression.right, Expression::Identifier(_)) {
+            let operand = expression.right.node();
+            self.report(
+                &format!("`{}` can only update a variable", expression.operator),
+                operand.start,
+                operand.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+
         let right = self.eval_expression(&expression.right);
 
         match expression.operator {
             UnaryOperator::Plus => right,
-            UnaryOperator::Minus => -right,
+            UnaryOperator::Minus => match right {
+                Value::Integer(n) => self
+                    .integer_binary(0, BinaryOperator::Substract, n, expression.node)
+                    .unwrap_or(Value::None),
+                _ => -right,
+            },
             UnaryOperator::Not => !right,
             UnaryOperator::BitNot => !right,
-            UnaryOperator::PlusPlus => right + Value::Number(1.0),
-            UnaryOperator::MinusMinus => right - Value::Number(1.0),
+            UnaryOperator::PlusPlus | UnaryOperator::MinusMinus => {
+                let operator = match expression.operator {
+                    UnaryOperator::PlusPlus => BinaryOperator::Add,
+                    _ => BinaryOperator::Substract,
+                };
+                let value = match right {
+                    Value::Integer(n) => self
+                        .integer_binary(n, operator, 1, expression.node)
+                        .unwrap_or(Value::None),
+                    _ if operator == BinaryOperator::Add => right + Value::Number(1.0),
+                    _ => right - Value::Number(1.0),
+                };
+
+                // `++i` and `--i` update the variable, other operands are reported above
+                if let (Expression::Identifier(id), None) = (&expression.right, &self.interrupt) {
+                    *self.get_variable_value_mut(id) = value.clone();
+                }
+
+                value
+            }
         }
     }
 
     fn eval_binary_expression(&mut self, expression: &BinaryExpression) -> Value {
         let left = match &expression.left {
-            Expression::Identifier(id) => self.get_variable_value(id),
+            Expression::Identifier(id) => self.read_variable(id),
             _ => self.eval_expression(&expression.left),
         };
 
         let right = self.eval_expression(&expression.right);
 
-        match expression.operator {
+        self.binary_operation(
+            left,
+            expression.operator,
+            right,
+            expression.node,
+            (expression.left.node(), expression.right.node()),
+        )
+    }
+
+    /// `left operator right` for binary expressions and compound assignments.
+    /// Type errors are raised at the node of the operand to blame, overflows at `node`
+    fn binary_operation(
+        &mut self,
+        left: Value,
+        operator: BinaryOperator,
+        right: Value,
+        node: Node,
+        (left_node, right_node): (Node, Node),
+    ) -> Value {
+        // operators defined only for some operand types, other types are an error
+        let typed = matches!(
+            operator,
+            BinaryOperator::Add
+                | BinaryOperator::Substract
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Power
+                | BinaryOperator::Modulo
+                | BinaryOperator::Less
+                | BinaryOperator::LessEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterEqual
+        );
+
+        if typed {
+            // the first error keeps pointing at where it was raised
+            if let Value::Err(_) = left {
+                return left;
+            }
+            if let Value::Err(_) = right {
+                return right;
+            }
+        }
+
+        // an integer mixed with a number is used as a number
+        let (left, right) = match (left, right) {
+            (Value::Integer(l), right @ Value::Number(_)) => (Value::Number(l as f64), right),
+            (left @ Value::Number(_), Value::Integer(r)) => (left, Value::Number(r as f64)),
+            operands => operands,
+        };
+
+        if let (Value::Integer(l), Value::Integer(r)) = (&left, &right) {
+            if let Some(value) = self.integer_binary(*l, operator, *r, node) {
+                return value;
+            }
+        }
+
+        if let (Value::Number(l), Value::Number(r)) = (&left, &right) {
+            let (l, r) = (
+                Number::new(*l, self.number_mode),
+                Number::new(*r, self.number_mode),
+            );
+
+            if let Some(value) = l.binary(operator, r) {
+                return value;
+            }
+        }
+
+        // an unsupported operand type is blamed on the first operand that isn't a number
+        let types = (left.type_name(), right.type_name());
+        let blamed = match left {
+            Value::Number(_) | Value::Integer(_) => right_node,
+            _ => left_node,
+        };
+
+        let value = match operator {
             BinaryOperator::Add => left + right,
             BinaryOperator::Substract => left - right,
             BinaryOperator::Multiply => left * right,
@@ -478,20 +1395,45 @@ impl Interpreter {
 
             BinaryOperator::Equal => left.equal(&right),
             BinaryOperator::NotEqual => left.not_equal(&right),
+            BinaryOperator::Is => left.identical(&right),
             BinaryOperator::Less => left.less(&right),
             BinaryOperator::LessEqual => left.less_equal(&right),
             BinaryOperator::Greater => left.greater(&right),
             BinaryOperator::GreaterEqual => left.greater_equal(&right),
+        };
+
+        match value {
+            Value::None if typed => Value::new_runtime_error(
+                format!(
+                    "Unsupported operand types for `{}`: {} and {}",
+                    operator, types.0, types.1
+                ),
+                blamed,
+            ),
+            _ => value,
         }
     }
 
+    /// Integer arithmetic under the overflow mode, an overflow error is raised at `node`
+    fn integer_binary(
+        &self,
+        left: i64,
+        operator: BinaryOperator,
+        right: i64,
+        node: Node,
+    ) -> Option<Value> {
+        integer_binary(left, operator, right, self.overflow_mode)
+            .map(|result| result.unwrap_or_else(|message| Value::new_runtime_error(message, node)))
+    }
+
     fn match_literal(&mut self, literal: &Literal) -> Value {
         match &literal.value {
             TokenValue::None => Value::None,
             TokenValue::Number(val) => Value::Number(*val),
+            TokenValue::Integer(val) => Value::Integer(*val),
             TokenValue::Str(val) => Value::Str(val.clone()),
             TokenValue::Bool(val) => Value::Bool(*val),
-            TokenValue::Identifier(id) => self.get_variable_value(&Identifier {
+            TokenValue::Identifier(id) => self.read_variable(&Identifier {
                 node: Node::new(literal.node.start, literal.node.end),
                 name: id.clone(),
             }),
@@ -505,9 +1447,8 @@ impl Interpreter {
         for scope in self.scope_stack.iter().rev() {
             let var = self.vault.get(scope).unwrap().values.get(&id);
 
-            match var {
-                Some(var) => return var.clone(),
-                None => {}
+            if let Some(var) = var {
+                return var.clone();
             }
         }
 
@@ -519,15 +1460,80 @@ impl Interpreter {
         unreachable!("Report ends proccess");
     }
 
-    fn get_cur_value(&mut self, id: &String) -> Value {
-        let (scope_name, _) = self.parse_current_scope();
-        self.vault
-            .get(&format!("{}$0", scope_name))
-            .unwrap()
-            .values
-            .get(id)
-            .unwrap()
-            .clone()
+    /// Value of a variable. Formulas give their ast, unless they are read by a formula being evaluated
+    fn read_variable(&mut self, identifier: &Identifier) -> Value {
+        match self.get_variable_value(identifier) {
+            Value::Ast(formula) if !self.evaluating_formulas.is_empty() => {
+                self.eval_formula(&formula)
+            }
+            value => value,
+        }
+    }
+
+    /// `f.eval[]` of a `:=` formula, computed in the scope that calls it.
+    /// The value is cached and only computed again when a variable the formula names has changed
+    /// since, formulas it names are evaluated too. Functions called by a formula are assumed
+    /// to be pure, the variables they read aren't tracked.
+    /// A name that isn't declared gives an error value, the formula stays symbolic
+    pub fn eval_formula(&mut self, formula: &Expression) -> Value {
+        let key = formula.to_string();
+
+        if self.evaluating_formulas.contains(&key) {
+            return Value::new_error(Value::Str(format!("Formula `{key}` depends on itself")));
+        }
+
+        let names = match self.formulas.get(&key) {
+            Some(cached) => cached
+                .dependencies
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect(),
+            None => dependencies(formula),
+        };
+
+        if let Some(name) = names.iter().find(|name| !self.is_declared(name)) {
+            return Value::new_error(Value::Str(format!(
+                "Formula `{key}` has no value for `{name}`"
+            )));
+        }
+
+        self.evaluating_formulas.insert(key.clone());
+
+        let node = formula.node();
+        let dependencies: Vec<(String, Value)> = names
+            .into_iter()
+            .map(|name| {
+                let value = self.read_variable(&Identifier {
+                    name: name.clone(),
+                    node,
+                });
+                (name, snapshot(&value))
+            })
+            .collect();
+
+        let value = match self.formulas.get(&key) {
+            Some(cached) if cached.dependencies == dependencies => cached.value.clone(),
+            _ => {
+                let value = self.eval_expression(formula);
+                self.formulas.insert(
+                    key.clone(),
+                    CachedFormula {
+                        value: value.clone(),
+                        dependencies,
+                    },
+                );
+                value
+            }
+        };
+
+        self.evaluating_formulas.remove(&key);
+        value
+    }
+
+    fn is_declared(&self, name: &str) -> bool {
+        self.scope_stack
+            .iter()
+            .any(|scope| self.vault[scope].values.contains_key(name))
     }
 
     fn get_variable_value_mut(&mut self, identifier: &Identifier) -> &mut Value {
@@ -581,9 +1587,9 @@ impl Interpreter {
     //     unreachable!("Report ends proccess");
     // }
 
-    fn declare_variable(&mut self, identifier: &String, value: Value) {
+    fn declare_variable(&mut self, identifier: &str, value: Value) {
         self.get_curr_scope_values_mut()
-            .insert(identifier.clone(), value);
+            .insert(identifier.to_owned(), value);
     }
 
     pub fn initialize(&mut self) {
@@ -593,20 +1599,52 @@ impl Interpreter {
         self.update_current_scope();
         self.add_std_lib();
 
+        // method scopes of values (`&str`) are kept, they aren't globals
+        if let Some(natives) = &self.natives {
+            self.vault
+                .get_mut("std$0")
+                .unwrap()
+                .values
+                .retain(|name, _| name.starts_with('&') || natives.contains(name));
+        }
+
         // Initialize global
         self.vault.insert("global$0".to_owned(), ScopeValue::new());
         self.scope_stack.push("global$0".to_owned());
         self.update_current_scope();
 
         // Initialize standard variables
-        self.declare_variable(&"__file__".to_owned(), Value::Str(self.paths[0].clone()));
-        self.declare_variable(&"__name__".to_owned(), Value::Str("main".to_owned()));
-        self.declare_variable(&"__module__".to_owned(), Value::Bool(false));
+        self.declare_variable("__file__", Value::Str(self.paths[0].clone()));
+        self.declare_variable("__name__", Value::Str("main".to_owned()));
+        self.declare_variable("__module__", Value::Bool(false));
 
         //Include std ref to global
         self.send_scope_ref("std$0");
 
-        native::io::inject(self.get_curr_scope_values_mut()); // Inject io to global too
+        // Inject io to global too
+        let mut io = Scope::new();
+        native::io::inject(&mut io);
+        for (name, value) in io {
+            if self
+                .natives
+                .as_ref()
+                .is_none_or(|natives| natives.contains(&name))
+            {
+                self.declare_variable(&name, value);
+            }
+        }
+
+        let host_names: Vec<String> = self.host_functions.keys().cloned().collect();
+        for name in host_names {
+            self.declare_variable(
+                &name,
+                Value::NativeFunction(NativeFunction::Host(name.clone())),
+            );
+        }
+
+        for (name, value) in self.preset_globals.clone() {
+            self.declare_variable(&name, value);
+        }
     }
 
     fn add_std_lib(&mut self) {
@@ -634,6 +1672,19 @@ impl Interpreter {
         new_scope
     }
 
+    /// Like `start_declaration_of_named_scope`, but a scope declared again in the same scope
+    /// keeps its members and base, so `scope a { ... }` twice declares the members of both
+    fn reopen_named_scope(&mut self, name: &str) -> String {
+        let scope = format!("{}.{}$0", self.current_scope, name);
+
+        if !self.vault.contains_key(&scope) {
+            return self.start_declaration_of_named_scope(name);
+        }
+
+        self.enter_named_scope(&scope);
+        scope
+    }
+
     fn end_declaration_of_named_scope(&mut self, name: &str) {
         self.exit_named_scope();
         self.send_scope_ref(name);
@@ -659,7 +1710,11 @@ impl Interpreter {
         let local_name = self.parse_local_name(name);
         self.get_curr_scope_values_mut()
             .insert(local_name, Value::ScopeRef(name.to_owned()));
-        self.get_curr_scope_refs_mut().push(name.to_owned());
+
+        let refs = self.get_curr_scope_refs_mut();
+        if !refs.iter().any(|scope| scope == name) {
+            refs.push(name.to_owned());
+        }
     }
 
     fn parse_local_name(&self, name: &str) -> String {
@@ -683,6 +1738,8 @@ impl Interpreter {
 
     /// Decrements the current scope and deletes named scopes in the current scope
     fn decrement_scope(&mut self) {
+        self.run_defers();
+
         let scope = self.current_scope.clone();
 
         self.remove_refs(&scope);
@@ -693,6 +1750,17 @@ impl Interpreter {
         self.update_current_scope();
     }
 
+    /// Runs deferred blocks of the current scope in reverse order
+    fn run_defers(&mut self) {
+        let scope = self.current_scope.clone();
+
+        while let Some(body) = self.vault.get_mut(&scope).unwrap().defers.pop() {
+            self.increment_scope();
+            self.eval_block(&body);
+            self.decrement_scope();
+        }
+    }
+
     /// Removes all references and subreferences in scope
     fn remove_refs(&mut self, scope_name: &str) {
         for ref_name in self.get_scope_refs_mut(scope_name).clone() {
@@ -717,7 +1785,7 @@ impl Interpreter {
     }
 
     /// Gets the current scope values
-    fn get_curr_scope_values_mut(&mut self) -> &mut HashMap<String, Value> {
+    fn get_curr_scope_values_mut(&mut self) -> &mut Scope {
         &mut self
             .vault
             .get_mut(self.current_scope.as_str())
@@ -738,7 +1806,7 @@ impl Interpreter {
             .named_scope_refs
     }
 
-    /// Gets the current named scopes in the current scope
+    // Gets the current named scopes in the current scope
     // fn get_curr_scope_refs(&self) -> &Vec<String> {
     //     &self
     //         .vault
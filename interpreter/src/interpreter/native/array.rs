@@ -0,0 +1,197 @@
+use std::{cell::RefCell, rc::Rc};
+
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Sequence, SequenceValue, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+pub fn map(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    let items = match_items(&args[0], interpreter, call_expr);
+
+    Value::new_sequence(
+        items
+            .map(|item| interpreter.call_function(call_expr, args[1].clone(), &[item]))
+            .collect(),
+    )
+}
+
+pub fn filter(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    let items = match_items(&args[0], interpreter, call_expr);
+
+    Value::new_sequence(
+        items
+            .filter(|item| {
+                interpreter
+                    .call_function(call_expr, args[1].clone(), std::slice::from_ref(item))
+                    .is_truthy()
+            })
+            .collect(),
+    )
+}
+
+pub fn reduce(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(3, interpreter, call_expr, args);
+
+    let items = match_items(&args[0], interpreter, call_expr);
+
+    items.fold(args[2].clone(), |acc, item| {
+        interpreter.call_function(call_expr, args[1].clone(), &[acc, item])
+    })
+}
+
+pub fn push(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    if let Value::Range(_) = &args[0] {
+        return range_error();
+    }
+
+    let seq = match_sequence(&args[0], interpreter, call_expr);
+    let mut seq = seq.borrow_mut();
+
+    if seq.frozen {
+        return frozen_error();
+    }
+
+    seq.items.push(args[1].clone());
+    Value::None
+}
+
+pub fn pop(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    if let Value::Range(_) = &args[0] {
+        return range_error();
+    }
+
+    let seq = match_sequence(&args[0], interpreter, call_expr);
+    let mut seq = seq.borrow_mut();
+
+    if seq.frozen {
+        return frozen_error();
+    }
+
+    seq.items.pop().unwrap_or(Value::None)
+}
+
+pub fn len(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    let len = match &args[0] {
+        Value::Range(range) => range.len(),
+        value => match_sequence(value, interpreter, call_expr)
+            .borrow()
+            .items
+            .len(),
+    };
+
+    Value::Number(len as f64)
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "map".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrMap),
+    );
+
+    scope.insert(
+        "filter".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrFilter),
+    );
+
+    scope.insert(
+        "reduce".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrReduce),
+    );
+
+    scope.insert(
+        "push".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrPush),
+    );
+
+    scope.insert(
+        "pop".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrPop),
+    );
+
+    scope.insert(
+        "len".to_owned(),
+        Value::NativeFunction(NativeFunction::ArrLen),
+    );
+}
+
+/// The same functions as methods of a sequence: `x.map[]`
+pub fn inject_methods(scope: &mut Scope) {
+    for (name, function) in [
+        ("map", NativeFunction::ArrMap),
+        ("filter", NativeFunction::ArrFilter),
+        ("reduce", NativeFunction::ArrReduce),
+        ("push", NativeFunction::ArrPush),
+        ("pop", NativeFunction::ArrPop),
+        ("len", NativeFunction::ArrLen),
+    ] {
+        scope.insert(
+            name.to_owned(),
+            Value::NativeFunction(NativeFunction::Method(Box::new(function))),
+        );
+    }
+}
+
+/// The array of a value, ranges give a new array of their items
+fn match_sequence(
+    value: &Value,
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+) -> Sequence {
+    match value {
+        Value::Sequence(seq) => seq.clone(),
+        Value::Range(range) => Rc::new(RefCell::new(SequenceValue {
+            items: range.iter().collect(),
+            frozen: false,
+        })),
+        got => {
+            interpreter.report(
+                format!("{} is not an array", got).as_str(),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    }
+}
+
+/// Items of a sequence or a range, ranges are iterated without collecting them
+fn match_items(
+    value: &Value,
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+) -> Box<dyn Iterator<Item = Value>> {
+    match value {
+        Value::Range(range) => Box::new(range.iter()),
+        _ => Box::new(
+            match_sequence(value, interpreter, call_expr)
+                .borrow()
+                .items
+                .clone()
+                .into_iter(),
+        ),
+    }
+}
+
+fn range_error() -> Value {
+    Value::new_error(Value::Str(
+        "Can't change a range, `clone` it into an array first".to_owned(),
+    ))
+}
+
+fn frozen_error() -> Value {
+    Value::new_error(Value::Str("Can't change a frozen array".to_owned()))
+}
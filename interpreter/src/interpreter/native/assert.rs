@@ -0,0 +1,103 @@
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use super::Interpreter;
+
+/// `assert[cond, message?]`, error value when `cond` is falsy
+pub fn assert(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args_range(interpreter, call_expr, args, 1);
+
+    if args[0].is_truthy() {
+        return Value::None;
+    }
+
+    failure("Assertion failed".to_owned(), args.get(1))
+}
+
+/// `assert_eq[left, right, message?]`, values are compared structurally like `==`
+pub fn assert_eq(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args_range(interpreter, call_expr, args, 2);
+
+    if args[0] == args[1] {
+        return Value::None;
+    }
+
+    failure(
+        format!(
+            "Assertion `left == right` failed (left: {}, right: {})",
+            args[0], args[1]
+        ),
+        args.get(2),
+    )
+}
+
+/// `assert_ne[left, right, message?]`
+pub fn assert_ne(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args_range(interpreter, call_expr, args, 2);
+
+    if args[0] != args[1] {
+        return Value::None;
+    }
+
+    failure(
+        format!(
+            "Assertion `left != right` failed (left: {}, right: {})",
+            args[0], args[1]
+        ),
+        args.get(2),
+    )
+}
+
+/// The last argument, a custom message, is optional
+fn expect_args_range(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+    count: usize,
+) {
+    if args.len() < count || args.len() > count + 1 {
+        interpreter.report(
+            &format!(
+                "Wrong number of arguments (expected {} or {})",
+                count,
+                count + 1
+            ),
+            call_expr.node.start,
+            call_expr.node.end,
+        );
+    }
+}
+
+fn failure(message: String, custom: Option<&Value>) -> Value {
+    let message = match custom {
+        Some(custom) => format!("{message}: {custom}"),
+        None => message,
+    };
+
+    Value::new_error(Value::Str(message))
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "assert".to_owned(),
+        Value::NativeFunction(NativeFunction::Assert),
+    );
+    scope.insert(
+        "assert_eq".to_owned(),
+        Value::NativeFunction(NativeFunction::AssertEq),
+    );
+    scope.insert(
+        "assert_ne".to_owned(),
+        Value::NativeFunction(NativeFunction::AssertNe),
+    );
+}
@@ -0,0 +1,28 @@
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+/// `f.eval[]`, the value of a `:=` formula, cached until a variable it reads changes
+pub fn eval(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    match &args[0] {
+        Value::Ast(formula) => interpreter.eval_formula(formula),
+        value => value.clone(),
+    }
+}
+
+/// Methods of formulas: `f.eval[]`
+pub fn inject_methods(scope: &mut Scope) {
+    scope.insert(
+        "eval".to_owned(),
+        Value::NativeFunction(NativeFunction::Method(Box::new(
+            NativeFunction::FormulaEval,
+        ))),
+    );
+}
@@ -0,0 +1,144 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+/// `bytes.new[[104, 105]]`, every item must be a whole number from 0 to 255
+pub fn new(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    let items = match &args[0] {
+        Value::Sequence(seq) => seq.borrow().items.clone(),
+        Value::Range(range) => range.iter().collect(),
+        got => {
+            interpreter.report(
+                &format!("Expected a sequence, got {}", got.type_name()),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    };
+
+    let mut bytes = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(&n) => {
+                bytes.push(n as u8)
+            }
+            got => return Value::new_error(Value::Str(format!("Can't make a byte from {got}"))),
+        }
+    }
+
+    Value::Bytes(bytes)
+}
+
+/// `bytes.get[b, i]`, the byte at index `i` as a number
+pub fn get(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    let bytes = match_bytes(&args[0], interpreter, call_expr);
+
+    match &args[1] {
+        Value::Number(i) if i.fract() == 0.0 && *i >= 0.0 && (*i as usize) < bytes.len() => {
+            Value::Number(bytes[*i as usize] as f64)
+        }
+        got => Value::new_error(Value::Str(format!(
+            "Index {got} is out of range for {} bytes",
+            bytes.len()
+        ))),
+    }
+}
+
+pub fn len(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Number(match_bytes(&args[0], interpreter, call_expr).len() as f64)
+}
+
+/// `bytes.from_base64[text]`, invalid base64 gives an error value
+pub fn from_base64(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    let text = match &args[0] {
+        Value::Str(text) => text,
+        got => {
+            interpreter.report(
+                &format!("Expected a string, got {}", got.type_name()),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    };
+
+    match STANDARD.decode(text) {
+        Ok(bytes) => Value::Bytes(bytes),
+        Err(err) => Value::new_error(Value::Str(format!("Invalid base64: {err}"))),
+    }
+}
+
+/// `bytes.to_base64[b]`, standard alphabet with padding
+pub fn to_base64(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Str(STANDARD.encode(match_bytes(&args[0], interpreter, call_expr)))
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "new".to_owned(),
+        Value::NativeFunction(NativeFunction::BytesNew),
+    );
+
+    scope.insert(
+        "get".to_owned(),
+        Value::NativeFunction(NativeFunction::BytesGet),
+    );
+
+    scope.insert(
+        "len".to_owned(),
+        Value::NativeFunction(NativeFunction::BytesLen),
+    );
+
+    scope.insert(
+        "from_base64".to_owned(),
+        Value::NativeFunction(NativeFunction::BytesFromBase64),
+    );
+
+    scope.insert(
+        "to_base64".to_owned(),
+        Value::NativeFunction(NativeFunction::BytesToBase64),
+    );
+}
+
+fn match_bytes<'a>(
+    value: &'a Value,
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+) -> &'a [u8] {
+    match value {
+        Value::Bytes(bytes) => bytes,
+        got => {
+            interpreter.report(
+                &format!("Expected bytes, got {}", got.type_name()),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use symboscript_types::{
+    interpreter::{ErrorValue, NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::{hashmap, Interpreter};
+
+/// Deep copy of a value.
+///
+/// Assignment only copies the reference of arrays and hashmaps, so mutations are visible
+/// through every variable. `clone` copies them recursively, nested arrays and hashmaps included.
+/// Ranges give an array of their items.
+/// Other values (numbers, strings, functions, named scopes) are returned as is.
+/// Copies are never frozen.
+pub fn clone(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    deep_clone(interpreter, call_expr, &args[0])
+}
+
+fn deep_clone(interpreter: &mut Interpreter, call_expr: &CallExpression, value: &Value) -> Value {
+    match value {
+        Value::Sequence(seq) => {
+            let items = seq.borrow().items.clone();
+
+            Value::new_sequence(
+                items
+                    .iter()
+                    .map(|item| deep_clone(interpreter, call_expr, item))
+                    .collect(),
+            )
+        }
+        Value::Range(range) => Value::new_sequence(range.iter().collect()),
+        Value::Err(err) => Value::Err(Box::new(ErrorValue {
+            message: deep_clone(interpreter, call_expr, &err.message),
+            code: deep_clone(interpreter, call_expr, &err.code),
+            origin: err.origin,
+        })),
+        Value::ScopeRef(name) if hashmap::is_hashmap(interpreter, name) => {
+            let values = interpreter.vault[name].values.clone();
+
+            let copy = hashmap::new(interpreter, call_expr, &[]);
+            let Value::ScopeRef(copy_name) = &copy else {
+                unreachable!("hashmap.new always returns a scope reference")
+            };
+
+            for (key, value) in values.iter().filter(|(key, _)| *key != "this") {
+                let value = deep_clone(interpreter, call_expr, value);
+
+                interpreter
+                    .vault
+                    .get_mut(copy_name)
+                    .unwrap()
+                    .values
+                    .insert(key.clone(), value);
+            }
+
+            copy
+        }
+        _ => value.clone(),
+    }
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "clone".to_owned(),
+        Value::NativeFunction(NativeFunction::Clone),
+    );
+}
@@ -0,0 +1,38 @@
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::{CallExpression, Expression},
+};
+
+use crate::expect_args;
+
+use super::{io, Interpreter};
+
+/// `dbg[expr]` prints `[path:line] expr = value` to the error output and returns the value,
+/// so it can wrap any part of an expression while debugging
+pub fn dbg(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    let source = interpreter.sources.last().unwrap();
+    let argument = match &call_expr.arguments {
+        Expression::SequenceExpression(seq) if seq.expressions.len() == 1 => {
+            seq.expressions[0].node()
+        }
+        arguments => arguments.node(),
+    };
+
+    let line = source[..argument.start].matches('\n').count() + 1;
+    let text = &source[argument.start..argument.end];
+    let message = format!(
+        "[{}:{line}] {text} = {}",
+        interpreter.paths.last().unwrap(),
+        args[0]
+    );
+
+    io::eprintln(interpreter, &[Value::Str(message)]);
+
+    args[0].clone()
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert("dbg".to_owned(), Value::NativeFunction(NativeFunction::Dbg));
+}
@@ -0,0 +1,30 @@
+use symboscript_types::{
+    interpreter::{ErrorValue, NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use super::Interpreter;
+
+/// `Error[message]` or `Error[message, code]`
+pub fn new(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    if args.is_empty() || args.len() > 2 {
+        interpreter.report(
+            "Wrong number of arguments (expected 1 or 2)",
+            call_expr.node.start,
+            call_expr.node.end,
+        );
+    }
+
+    Value::Err(Box::new(ErrorValue {
+        message: args[0].clone(),
+        code: args.get(1).cloned().unwrap_or(Value::None),
+        origin: None,
+    }))
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "Error".to_owned(),
+        Value::NativeFunction(NativeFunction::ErrorNew),
+    );
+}
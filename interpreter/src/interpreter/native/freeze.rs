@@ -0,0 +1,44 @@
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+/// Makes an array or hashmap immutable and returns it.
+///
+/// Changing a frozen value gives an error value instead of changing it.
+/// Freezing is shallow: nested arrays and hashmaps stay mutable, `clone` gives a mutable copy.
+/// Ranges can't be changed anyway and are returned as they are.
+pub fn freeze(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    match &args[0] {
+        Value::Sequence(seq) => seq.borrow_mut().frozen = true,
+        Value::Range(_) => {}
+        Value::ScopeRef(scope) => {
+            if let Some(scope) = interpreter.vault.get_mut(scope) {
+                scope.frozen = true;
+            }
+        }
+        got => {
+            interpreter.report(
+                &format!("{} is not an array or hashmap", got),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    }
+
+    args[0].clone()
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "freeze".to_owned(),
+        Value::NativeFunction(NativeFunction::Freeze),
+    );
+}
@@ -0,0 +1,90 @@
+use std::fs;
+
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+/// Capability to touch the disk, scripts are untrusted by default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FileSystemAccess {
+    #[default]
+    Denied,
+    Allowed,
+}
+
+/// `read_file[path]`, contents of the file as a string
+pub fn read_file(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    if interpreter.fs_access == FileSystemAccess::Denied {
+        return permission_denied();
+    }
+
+    let path = match_path(interpreter, call_expr, &args[0]);
+
+    match fs::read_to_string(&path) {
+        Ok(text) => Value::Str(text),
+        Err(err) => Value::new_error(Value::Str(format!("Can't read {path}: {err}"))),
+    }
+}
+
+/// `write_file[path, contents]`, creates the file or replaces its contents
+pub fn write_file(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    if interpreter.fs_access == FileSystemAccess::Denied {
+        return permission_denied();
+    }
+
+    let path = match_path(interpreter, call_expr, &args[0]);
+
+    match fs::write(&path, args[1].to_string()) {
+        Ok(()) => Value::None,
+        Err(err) => Value::new_error(Value::Str(format!("Can't write {path}: {err}"))),
+    }
+}
+
+fn match_path(interpreter: &mut Interpreter, call_expr: &CallExpression, path: &Value) -> String {
+    match path {
+        Value::Str(path) => path.clone(),
+        got => {
+            interpreter.report(
+                &format!("Expected a path string, got {}", got.type_name()),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    }
+}
+
+fn permission_denied() -> Value {
+    Value::new_error(Value::Str(
+        "Permission denied: file system access is disabled".to_owned(),
+    ))
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "read_file".to_owned(),
+        Value::NativeFunction(NativeFunction::FsReadFile),
+    );
+
+    scope.insert(
+        "write_file".to_owned(),
+        Value::NativeFunction(NativeFunction::FsWriteFile),
+    );
+}
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use symboscript_types::{
     interpreter::{NativeFunction, Scope, Value},
     parser::CallExpression,
@@ -7,9 +9,13 @@ use crate::{expect_args, mut_values_hm};
 
 use super::Interpreter;
 
-pub fn set(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) {
+pub fn set(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     expect_args!(3, interpreter, call_expr, args);
 
+    if is_frozen(interpreter, &args[0]) {
+        return frozen_error();
+    }
+
     let scope_ref = args[0].clone();
     let key = args[1].clone();
     let value = args[2].clone();
@@ -22,81 +28,77 @@ pub fn set(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec
         .unwrap()
         .values
         .insert(key.to_string(), value);
+
+    Value::None
 }
 
-pub fn del(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) {
+pub fn del(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    if args.first().is_some_and(|map| is_frozen(interpreter, map)) {
+        return frozen_error();
+    }
+
     let values = mut_values_hm!(2, interpreter, call_expr, args);
 
-    values.remove(&args[1].to_string());
+    values.shift_remove(&args[1].to_string());
+
+    Value::None
 }
 
-pub fn has(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) -> Value {
+pub fn has(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     let values = mut_values_hm!(2, interpreter, call_expr, args);
 
     Value::Bool(values.contains_key(&args[1].to_string()))
 }
 
-pub fn len(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) -> Value {
+pub fn len(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     let values = mut_values_hm!(1, interpreter, call_expr, args);
 
     Value::Number(values.len() as f64)
 }
 
-pub fn keys(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) -> Value {
+pub fn keys(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     let values = mut_values_hm!(1, interpreter, call_expr, args);
 
-    Value::Sequence(
+    Value::new_sequence(
         values
             .keys()
-            .filter(|v| match v.as_str() {
-                // TODO: Remove this when arrays will be implemented in language and add this to native/lang/hashmap.syms(.rs)
-                "this" | "set" | "get" | "del" | "has" | "len" | "keys" | "values" | "clear" => {
-                    false
-                }
-                _ => true,
-            })
+            .filter(|k| !MEMBERS.contains(&k.as_str()))
             .map(|k| Value::Str(k.to_string()))
             .collect(),
     )
 }
 
-pub fn values(
-    interpreter: &mut Interpreter,
-    call_expr: &CallExpression,
-    args: &Vec<Value>,
-) -> Value {
+pub fn values(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     let values = mut_values_hm!(1, interpreter, call_expr, args);
 
-    Value::Sequence(
+    Value::new_sequence(
         values
             .values()
+            .filter(|&v| !matches!(v, Value::Function(_) | Value::ScopeRef(_)))
             .cloned()
-            .filter(|v| match v {
-                Value::Function(_) | Value::ScopeRef(_) => false,
-                _ => true,
-            })
             .collect(),
     )
 }
 
-pub fn clear(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) {
+pub fn clear(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    if args.first().is_some_and(|map| is_frozen(interpreter, map)) {
+        return frozen_error();
+    }
+
     let values = mut_values_hm!(1, interpreter, call_expr, args);
 
     for key in values.clone().keys() {
-        // TODO: remove this when arrays will be implemented in language and add this to native/lang/hashmap.syms(.rs)
-        if [
-            "this", "set", "get", "del", "has", "len", "keys", "values", "clear",
-        ]
-        .contains(&key.as_str())
-        {
+        if MEMBERS.contains(&key.as_str()) {
             continue;
         }
 
-        values.remove(key);
+        values.shift_remove(key);
     }
+
+    Value::None
 }
 
-pub fn get(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) -> Value {
+pub fn get(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     let values = mut_values_hm!(2, interpreter, call_expr, args);
     let val = values.get(&args[1].to_string());
 
@@ -106,13 +108,39 @@ pub fn get(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec
     }
 }
 
-pub fn new(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &Vec<Value>) -> Value {
+/// Hashmaps are id scopes that reference themselves through `this`
+pub fn is_hashmap(interpreter: &Interpreter, name: &str) -> bool {
+    match interpreter.vault.get(name) {
+        Some(scope) => {
+            matches!(scope.values.get("this"), Some(Value::ScopeRef(this)) if this == name)
+        }
+        None => false,
+    }
+}
+
+/// Key-value pairs of a hashmap, without its methods
+pub fn entries(interpreter: &Interpreter, name: &str) -> Vec<(String, Value)> {
+    interpreter.vault[name]
+        .values
+        .iter()
+        .filter(|(key, _)| !MEMBERS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+// TODO: remove this when arrays will be implemented in language and add this to native/lang/hashmap.syms(.rs)
+const MEMBERS: [&str; 9] = [
+    "this", "set", "get", "del", "has", "len", "keys", "values", "clear",
+];
+
+pub fn new(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
     expect_args!(0, interpreter, call_expr, args);
 
     let scope = interpreter.start_declaration_of_id_scope();
-    interpreter.declare_variable(&"this".to_owned(), Value::ScopeRef(scope.clone()));
+    interpreter.declare_variable("this", Value::ScopeRef(scope.clone()));
 
-    interpreter.eval_ast(interpreter.std_lang.hashmap.clone());
+    let members = Rc::clone(&interpreter.std_lang.hashmap);
+    interpreter.eval_ast(&members);
     interpreter.end_declaration_of_named_scope(&scope);
 
     Value::ScopeRef(scope)
@@ -182,3 +210,14 @@ fn match_scope(
         }
     }
 }
+
+fn is_frozen(interpreter: &Interpreter, scope_ref: &Value) -> bool {
+    match scope_ref {
+        Value::ScopeRef(scope) => interpreter.vault.get(scope).is_some_and(|s| s.frozen),
+        _ => false,
+    }
+}
+
+fn frozen_error() -> Value {
+    Value::new_error(Value::Str("Can't change a frozen hashmap".to_owned()))
+}
@@ -0,0 +1,19 @@
+use symboscript_types::{interpreter::Value, parser::CallExpression};
+
+use super::Interpreter;
+
+/// Rust function callable from scripts, an `Err` message becomes an error value at the call site
+pub type HostFunction = Box<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+/// Calls a function registered with [`Interpreter::register_fn`]
+pub fn call(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    name: &str,
+    args: &[Value],
+) -> Value {
+    match (interpreter.host_functions[name])(args) {
+        Ok(value) => value,
+        Err(message) => Value::new_runtime_error(message, call_expr.node),
+    }
+}
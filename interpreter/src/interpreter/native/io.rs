@@ -1,33 +1,103 @@
 use colored::Colorize;
 use symboscript_types::interpreter::{NativeFunction, Scope, Value};
 
-pub fn println(s: &Vec<Value>) {
-    print(s);
-    println!();
-}
-
-pub fn print(s: &Vec<Value>) {
-    for val in s {
-        match val {
-            Value::None => print!("{}", "None".blue().bold()),
-            Value::Number(n) => print!("{}", n.to_string().green()),
-            Value::Bool(b) => print!("{}", b.to_string().blue().bold()),
-            Value::Str(str) => print!("{}", str),
-            Value::Sequence(seq) => {
-                print!("[");
-                for val in seq {
-                    print!("{}, ", val);
-                }
-                print!("]");
-            }
-            Value::Ast(v) => print!("{}", v),
-            Value::ScopeRef(v) => print!("{}", v),
-            Value::NativeFunction(_) => todo!(),
-            Value::Function(v) => print!("{}", v),
-            Value::Err(e) => print!("{}", e),
+use super::Interpreter;
+
+/// Receives each line printed by the script as soon as it ends, without the line break
+pub type OutputSink = Box<dyn FnMut(&str)>;
+
+/// One stream of printed text, the terminal unless a sink is set
+#[derive(Default)]
+pub struct Output {
+    pub sink: Option<OutputSink>,
+
+    /// Text printed to the sink since the last line break
+    pending_line: String,
+}
+
+impl Output {
+    pub fn new(sink: Option<OutputSink>) -> Self {
+        Self {
+            sink,
+            pending_line: String::new(),
+        }
+    }
+}
+
+/// Values joined by the print separator, then a line break
+pub fn println(interpreter: &mut Interpreter, s: &[Value]) {
+    let separator = &interpreter.print_separator;
+    write(&mut interpreter.stdout, s, separator, |text| {
+        print!("{text}")
+    });
+    end_line(&mut interpreter.stdout, || println!());
+}
+
+/// Values joined by the print separator, without a line break
+pub fn print(interpreter: &mut Interpreter, s: &[Value]) {
+    let separator = &interpreter.print_separator;
+    write(&mut interpreter.stdout, s, separator, |text| {
+        print!("{text}")
+    });
+}
+
+pub fn eprintln(interpreter: &mut Interpreter, s: &[Value]) {
+    let separator = &interpreter.print_separator;
+    write(&mut interpreter.stderr, s, separator, |text| {
+        eprint!("{text}")
+    });
+    end_line(&mut interpreter.stderr, || eprintln!());
+}
+
+pub fn eprint(interpreter: &mut Interpreter, s: &[Value]) {
+    let separator = &interpreter.print_separator;
+    write(&mut interpreter.stderr, s, separator, |text| {
+        eprint!("{text}")
+    });
+}
+
+fn write(output: &mut Output, s: &[Value], separator: &str, terminal: fn(&str)) {
+    for (i, val) in s.iter().enumerate() {
+        let separator = if i == 0 { "" } else { separator };
+        let text = format!("{separator}{}", format_value(val, output.sink.is_none()));
+
+        match output.sink {
+            Some(_) => output.pending_line.push_str(&text),
+            None => terminal(&text),
         }
+    }
+}
+
+/// Printed text of a value, the same for sinks and the terminal apart from the colors
+fn format_value(val: &Value, colored: bool) -> String {
+    let text = val.to_string();
+
+    if !colored {
+        return text;
+    }
 
-        print!(" ")
+    match val {
+        Value::None | Value::Bool(_) => text.blue().bold().to_string(),
+        Value::Number(_) | Value::Integer(_) => text.green().to_string(),
+        _ => text,
+    }
+}
+
+fn end_line(output: &mut Output, terminal: fn()) {
+    match &mut output.sink {
+        Some(sink) => sink(&std::mem::take(&mut output.pending_line)),
+        None => terminal(),
+    }
+}
+
+/// Sends text printed after the last line break to the sinks, called when the program ends
+pub fn flush(interpreter: &mut Interpreter) {
+    for output in [&mut interpreter.stdout, &mut interpreter.stderr] {
+        if let Some(sink) = &mut output.sink {
+            if !output.pending_line.is_empty() {
+                sink(&std::mem::take(&mut output.pending_line));
+            }
+        }
     }
 }
 
@@ -41,4 +111,14 @@ pub fn inject(scope: &mut Scope) {
         "println".to_owned(),
         Value::NativeFunction(NativeFunction::IOPrintln),
     );
+
+    scope.insert(
+        "eprint".to_owned(),
+        Value::NativeFunction(NativeFunction::IOEprint),
+    );
+
+    scope.insert(
+        "eprintln".to_owned(),
+        Value::NativeFunction(NativeFunction::IOEprintln),
+    );
 }
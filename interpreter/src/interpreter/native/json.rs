@@ -0,0 +1,130 @@
+use serde_json::{Map, Number};
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::{hashmap, Interpreter};
+
+type Json = serde_json::Value;
+
+/// `json.parse[text]`, objects become hashmaps and arrays sequences.
+/// Invalid JSON gives an error value.
+pub fn parse(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    let text = match &args[0] {
+        Value::Str(text) => text,
+        got => {
+            interpreter.report(
+                &format!("Expected a string, got {}", got.type_name()),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    };
+
+    match serde_json::from_str::<Json>(text) {
+        Ok(json) => from_json(interpreter, call_expr, json),
+        Err(err) => Value::new_error(Value::Str(format!("Invalid JSON: {err}"))),
+    }
+}
+
+/// `json.stringify[value]`, integral numbers are written without a fraction,
+/// NaN and infinities as `null`. Functions, scopes and other values that have no JSON
+/// form give an error value.
+pub fn stringify(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    match to_json(interpreter, &args[0]) {
+        Ok(json) => Value::Str(json.to_string()),
+        Err(got) => Value::new_error(Value::Str(format!("Can't convert {got} to JSON"))),
+    }
+}
+
+fn from_json(interpreter: &mut Interpreter, call_expr: &CallExpression, json: Json) -> Value {
+    match json {
+        Json::Null => Value::None,
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(n) => Value::Number(n.as_f64().unwrap_or(f64::NAN)),
+        Json::String(s) => Value::Str(s),
+        Json::Array(items) => Value::new_sequence(
+            items
+                .into_iter()
+                .map(|item| from_json(interpreter, call_expr, item))
+                .collect(),
+        ),
+        Json::Object(entries) => {
+            let map = hashmap::new(interpreter, call_expr, &[]);
+            let Value::ScopeRef(name) = &map else {
+                unreachable!("hashmap.new always returns a scope reference")
+            };
+
+            for (key, value) in entries {
+                let value = from_json(interpreter, call_expr, value);
+
+                interpreter
+                    .vault
+                    .get_mut(name)
+                    .unwrap()
+                    .values
+                    .insert(key, value);
+            }
+
+            map
+        }
+    }
+}
+
+/// The error is the type name of the value that can't be converted
+fn to_json(interpreter: &Interpreter, value: &Value) -> Result<Json, &'static str> {
+    Ok(match value {
+        Value::None => Json::Null,
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+            Json::Number(Number::from(*n as i64))
+        }
+        Value::Number(n) => Number::from_f64(*n).map_or(Json::Null, Json::Number),
+        Value::Integer(n) => Json::Number(Number::from(*n)),
+        Value::Str(s) => Json::String(s.clone()),
+        Value::Sequence(seq) => Json::Array(
+            seq.borrow()
+                .items
+                .iter()
+                .map(|item| to_json(interpreter, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Range(range) => Json::Array(
+            range
+                .iter()
+                .map(|item| to_json(interpreter, &item))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::ScopeRef(name) if hashmap::is_hashmap(interpreter, name) => Json::Object(
+            hashmap::entries(interpreter, name)
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), to_json(interpreter, value)?)))
+                .collect::<Result<Map<_, _>, _>>()?,
+        ),
+        got => return Err(got.type_name()),
+    })
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "parse".to_owned(),
+        Value::NativeFunction(NativeFunction::JsonParse),
+    );
+
+    scope.insert(
+        "stringify".to_owned(),
+        Value::NativeFunction(NativeFunction::JsonStringify),
+    );
+}
@@ -1,25 +1,41 @@
+use std::rc::Rc;
+
 use symboscript_types::{
-    interpreter::{NativeFunction, Value},
+    interpreter::{BoundMethod, NativeFunction, Value},
     parser::{Ast, CallExpression},
 };
 
 use super::Interpreter;
 
+pub mod array;
+pub mod assert;
+pub mod ast;
+pub mod bytes;
+pub mod clone;
 pub mod conversions;
+pub mod dbg;
+pub mod error;
+pub mod freeze;
+pub mod fs;
 pub mod hashmap;
+pub mod host;
 pub mod io;
+pub mod json;
+pub mod sizeof;
+pub mod string;
 
 mod lang;
 
 mod macro_utils;
 
+/// Parts of the std library written in SymboScript, parsed once and shared by every call
 pub struct StdLang {
-    pub hashmap: Ast,
+    pub hashmap: Rc<Ast>,
 }
 
 pub fn get_values() -> StdLang {
     StdLang {
-        hashmap: lang::hashmap::value(),
+        hashmap: Rc::new(lang::hashmap::value()),
     }
 }
 
@@ -27,39 +43,126 @@ pub fn run_function(
     interpreter: &mut Interpreter,
     call_expr: &CallExpression,
     native_function: &NativeFunction,
-    args: &Vec<Value>,
+    args: &[Value],
 ) -> Value {
     match native_function {
-        NativeFunction::IOPrintln => io::println(&args),
-        NativeFunction::IOPrint => io::print(&args),
+        NativeFunction::IOPrintln => io::println(interpreter, args),
+        NativeFunction::IOPrint => io::print(interpreter, args),
+        NativeFunction::IOEprintln => io::eprintln(interpreter, args),
+        NativeFunction::IOEprint => io::eprint(interpreter, args),
 
         NativeFunction::ToString => return conversions::to_string(interpreter, call_expr, args),
         NativeFunction::IsError => return conversions::is_err(interpreter, call_expr, args),
 
+        NativeFunction::FormulaEval => return ast::eval(interpreter, call_expr, args),
+
+        NativeFunction::ErrorNew => return error::new(interpreter, call_expr, args),
+
+        NativeFunction::Clone => return clone::clone(interpreter, call_expr, args),
+        NativeFunction::Freeze => return freeze::freeze(interpreter, call_expr, args),
+        NativeFunction::SizeOf => return sizeof::size_of(interpreter, call_expr, args),
+        NativeFunction::Dbg => return dbg::dbg(interpreter, call_expr, args),
+
+        NativeFunction::JsonParse => return json::parse(interpreter, call_expr, args),
+        NativeFunction::JsonStringify => return json::stringify(interpreter, call_expr, args),
+
+        NativeFunction::FsReadFile => return fs::read_file(interpreter, call_expr, args),
+        NativeFunction::FsWriteFile => return fs::write_file(interpreter, call_expr, args),
+
+        NativeFunction::Assert => return assert::assert(interpreter, call_expr, args),
+        NativeFunction::AssertEq => return assert::assert_eq(interpreter, call_expr, args),
+        NativeFunction::AssertNe => return assert::assert_ne(interpreter, call_expr, args),
+
         NativeFunction::HMNew => return hashmap::new(interpreter, call_expr, args),
 
-        NativeFunction::HMSet => hashmap::set(interpreter, call_expr, args),
+        NativeFunction::HMSet => return hashmap::set(interpreter, call_expr, args),
         NativeFunction::HMGet => return hashmap::get(interpreter, call_expr, args),
-        NativeFunction::HMDelete => hashmap::del(interpreter, call_expr, args),
+        NativeFunction::HMDelete => return hashmap::del(interpreter, call_expr, args),
         NativeFunction::HMHas => return hashmap::has(interpreter, call_expr, args),
         NativeFunction::HMLen => return hashmap::len(interpreter, call_expr, args),
         NativeFunction::HMKeys => return hashmap::keys(interpreter, call_expr, args),
         NativeFunction::HMValues => return hashmap::values(interpreter, call_expr, args),
-        NativeFunction::HMClear => hashmap::clear(interpreter, call_expr, args),
+        NativeFunction::HMClear => return hashmap::clear(interpreter, call_expr, args),
+
+        NativeFunction::StrUpper => return string::upper(interpreter, call_expr, args),
+        NativeFunction::StrLower => return string::lower(interpreter, call_expr, args),
+        NativeFunction::StrTrim => return string::trim(interpreter, call_expr, args),
+        NativeFunction::StrSplit => return string::split(interpreter, call_expr, args),
+        NativeFunction::StrReplace => return string::replace(interpreter, call_expr, args),
+        NativeFunction::StrContains => return string::contains(interpreter, call_expr, args),
+
+        NativeFunction::ArrMap => return array::map(interpreter, call_expr, args),
+        NativeFunction::ArrFilter => return array::filter(interpreter, call_expr, args),
+        NativeFunction::ArrReduce => return array::reduce(interpreter, call_expr, args),
+        NativeFunction::ArrPush => return array::push(interpreter, call_expr, args),
+        NativeFunction::ArrPop => return array::pop(interpreter, call_expr, args),
+        NativeFunction::ArrLen => return array::len(interpreter, call_expr, args),
+
+        NativeFunction::BytesNew => return bytes::new(interpreter, call_expr, args),
+        NativeFunction::BytesGet => return bytes::get(interpreter, call_expr, args),
+        NativeFunction::BytesLen => return bytes::len(interpreter, call_expr, args),
+        NativeFunction::BytesFromBase64 => return bytes::from_base64(interpreter, call_expr, args),
+        NativeFunction::BytesToBase64 => return bytes::to_base64(interpreter, call_expr, args),
+
+        NativeFunction::Host(name) => return host::call(interpreter, call_expr, name, args),
+
+        // methods are bound to their value when they are looked up
+        NativeFunction::Method(_) => {
+            return Value::new_error(Value::Str(
+                "Methods can only be called after a dot".to_owned(),
+            ))
+        }
     }
     Value::None
 }
 
+/// Calls the function of a method with the value it was looked up on before the arguments
+pub fn call_method(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    method: &BoundMethod,
+    args: &[Value],
+) -> Value {
+    let args: Vec<Value> = std::iter::once(method.receiver.clone())
+        .chain(args.iter().cloned())
+        .collect();
+
+    run_function(interpreter, call_expr, &method.function, &args)
+}
+
 pub fn inject(interpreter: &mut Interpreter) {
+    error::inject(interpreter.get_curr_scope_values_mut());
+    clone::inject(interpreter.get_curr_scope_values_mut());
+    freeze::inject(interpreter.get_curr_scope_values_mut());
+    sizeof::inject(interpreter.get_curr_scope_values_mut());
+    dbg::inject(interpreter.get_curr_scope_values_mut());
+    assert::inject(interpreter.get_curr_scope_values_mut());
+    fs::inject(interpreter.get_curr_scope_values_mut());
+
     let scope = interpreter.start_declaration_of_named_scope("io");
     io::inject(interpreter.get_curr_scope_values_mut());
     interpreter.end_declaration_of_named_scope(&scope);
 
     // ----------------- Std conversions --------------------------------
 
-    for name in ["&number", "&bool", "&str", "&sequence", "&ast", "&err"] {
+    for name in [
+        "&number",
+        "&bool",
+        "&str",
+        "&sequence",
+        "&bytes",
+        "&ast",
+        "&err",
+        "&none",
+    ] {
         let scope = interpreter.start_declaration_of_named_scope(name);
         conversions::inject_methods(interpreter.get_curr_scope_values_mut());
+        match name {
+            "&str" => string::inject_methods(interpreter.get_curr_scope_values_mut()),
+            "&sequence" => array::inject_methods(interpreter.get_curr_scope_values_mut()),
+            "&ast" => ast::inject_methods(interpreter.get_curr_scope_values_mut()),
+            _ => {}
+        }
         interpreter.end_declaration_of_named_scope(&scope);
     }
 
@@ -68,4 +171,28 @@ pub fn inject(interpreter: &mut Interpreter) {
     let scope = interpreter.start_declaration_of_named_scope("hashmap");
     hashmap::inject(interpreter.get_curr_scope_values_mut());
     interpreter.end_declaration_of_named_scope(&scope);
+
+    // ----------------- Json -------------------------------------------
+
+    let scope = interpreter.start_declaration_of_named_scope("json");
+    json::inject(interpreter.get_curr_scope_values_mut());
+    interpreter.end_declaration_of_named_scope(&scope);
+
+    // ----------------- String -----------------------------------------
+
+    let scope = interpreter.start_declaration_of_named_scope("string");
+    string::inject(interpreter.get_curr_scope_values_mut());
+    interpreter.end_declaration_of_named_scope(&scope);
+
+    // ----------------- Array ------------------------------------------
+
+    let scope = interpreter.start_declaration_of_named_scope("array");
+    array::inject(interpreter.get_curr_scope_values_mut());
+    interpreter.end_declaration_of_named_scope(&scope);
+
+    // ----------------- Bytes ------------------------------------------
+
+    let scope = interpreter.start_declaration_of_named_scope("bytes");
+    bytes::inject(interpreter.get_curr_scope_values_mut());
+    interpreter.end_declaration_of_named_scope(&scope);
 }
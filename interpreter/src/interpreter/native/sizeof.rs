@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::{hashmap, Interpreter};
+
+/// Estimated number of bytes a value holds, for finding large values while debugging.
+///
+/// Counts the data, not the bookkeeping around it: strings and bytes by their length,
+/// numbers as 8 bytes, booleans as 1, `none` as 0. Arrays and hashmaps are the sum of
+/// their items (and hashmap keys), a value reachable twice is counted once.
+/// Functions, scopes and asts count as one interpreter value.
+pub fn size_of(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Number(estimate(interpreter, &args[0], &mut HashSet::new()) as f64)
+}
+
+/// `seen` holds the arrays and hashmaps already counted, so cycles end
+fn estimate(interpreter: &Interpreter, value: &Value, seen: &mut HashSet<String>) -> usize {
+    match value {
+        Value::None => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) | Value::Integer(_) => 8,
+        Value::Str(s) => s.len(),
+        Value::Bytes(bytes) => bytes.len(),
+        Value::Sequence(seq) => {
+            if !seen.insert(format!("{:p}", seq.as_ptr())) {
+                return 0;
+            }
+
+            seq.borrow()
+                .items
+                .iter()
+                .map(|item| estimate(interpreter, item, seen))
+                .sum()
+        }
+        Value::ScopeRef(name) if hashmap::is_hashmap(interpreter, name) => {
+            if !seen.insert(name.clone()) {
+                return 0;
+            }
+
+            hashmap::entries(interpreter, name)
+                .iter()
+                .map(|(key, value)| key.len() + estimate(interpreter, value, seen))
+                .sum()
+        }
+        Value::Err(err) => {
+            estimate(interpreter, &err.message, seen) + estimate(interpreter, &err.code, seen)
+        }
+        _ => std::mem::size_of::<Value>(),
+    }
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "sizeof".to_owned(),
+        Value::NativeFunction(NativeFunction::SizeOf),
+    );
+}
@@ -0,0 +1,130 @@
+use symboscript_types::{
+    interpreter::{NativeFunction, Scope, Value},
+    parser::CallExpression,
+};
+
+use crate::expect_args;
+
+use super::Interpreter;
+
+pub fn upper(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Str(match_str(&args[0], interpreter, call_expr).to_uppercase())
+}
+
+pub fn lower(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Str(match_str(&args[0], interpreter, call_expr).to_lowercase())
+}
+
+pub fn trim(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(1, interpreter, call_expr, args);
+
+    Value::Str(
+        match_str(&args[0], interpreter, call_expr)
+            .trim()
+            .to_owned(),
+    )
+}
+
+pub fn split(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    let string = match_str(&args[0], interpreter, call_expr);
+    let separator = match_str(&args[1], interpreter, call_expr);
+
+    Value::new_sequence(
+        string
+            .split(separator.as_str())
+            .map(|s| Value::Str(s.to_owned()))
+            .collect(),
+    )
+}
+
+pub fn replace(interpreter: &mut Interpreter, call_expr: &CallExpression, args: &[Value]) -> Value {
+    expect_args!(3, interpreter, call_expr, args);
+
+    let string = match_str(&args[0], interpreter, call_expr);
+    let from = match_str(&args[1], interpreter, call_expr);
+    let to = match_str(&args[2], interpreter, call_expr);
+
+    Value::Str(string.replace(from.as_str(), to.as_str()))
+}
+
+pub fn contains(
+    interpreter: &mut Interpreter,
+    call_expr: &CallExpression,
+    args: &[Value],
+) -> Value {
+    expect_args!(2, interpreter, call_expr, args);
+
+    let string = match_str(&args[0], interpreter, call_expr);
+    let pattern = match_str(&args[1], interpreter, call_expr);
+
+    Value::Bool(string.contains(pattern.as_str()))
+}
+
+pub fn inject(scope: &mut Scope) {
+    scope.insert(
+        "upper".to_owned(),
+        Value::NativeFunction(NativeFunction::StrUpper),
+    );
+
+    scope.insert(
+        "lower".to_owned(),
+        Value::NativeFunction(NativeFunction::StrLower),
+    );
+
+    scope.insert(
+        "trim".to_owned(),
+        Value::NativeFunction(NativeFunction::StrTrim),
+    );
+
+    scope.insert(
+        "split".to_owned(),
+        Value::NativeFunction(NativeFunction::StrSplit),
+    );
+
+    scope.insert(
+        "replace".to_owned(),
+        Value::NativeFunction(NativeFunction::StrReplace),
+    );
+
+    scope.insert(
+        "contains".to_owned(),
+        Value::NativeFunction(NativeFunction::StrContains),
+    );
+}
+
+/// The same functions as methods of a string: `x.upper[]`
+pub fn inject_methods(scope: &mut Scope) {
+    for (name, function) in [
+        ("upper", NativeFunction::StrUpper),
+        ("lower", NativeFunction::StrLower),
+        ("trim", NativeFunction::StrTrim),
+        ("split", NativeFunction::StrSplit),
+        ("replace", NativeFunction::StrReplace),
+        ("contains", NativeFunction::StrContains),
+    ] {
+        scope.insert(
+            name.to_owned(),
+            Value::NativeFunction(NativeFunction::Method(Box::new(function))),
+        );
+    }
+}
+
+fn match_str(value: &Value, interpreter: &mut Interpreter, call_expr: &CallExpression) -> String {
+    match value {
+        Value::Str(s) => s.clone(),
+        got => {
+            interpreter.report(
+                format!("{} is not a string", got).as_str(),
+                call_expr.node.start,
+                call_expr.node.end,
+            );
+            unreachable!("Report ends proccess");
+        }
+    }
+}
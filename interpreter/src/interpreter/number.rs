@@ -0,0 +1,161 @@
+use std::str::FromStr;
+
+use rust_decimal::{prelude::ToPrimitive, Decimal, MathematicalOps};
+use symboscript_types::{
+    interpreter::{number_pow, Value},
+    parser::BinaryOperator,
+};
+
+/// How the interpreter does arithmetic on numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NumberMode {
+    /// Plain `f64` arithmetic
+    #[default]
+    Float,
+
+    /// Exact decimal arithmetic (`0.1 + 0.2 == 0.3`).
+    ///
+    /// Numbers are still stored as `f64` between operations,
+    /// so results are exact up to 15 significant digits.
+    Decimal,
+}
+
+/// What integer arithmetic does when the result doesn't fit in an `i64`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OverflowMode {
+    /// Wraps around at the boundary (`i64::MAX + 1i == i64::MIN`)
+    Wrap,
+
+    /// Stays at the boundary (`i64::MAX + 1i == i64::MAX`)
+    Saturate,
+
+    /// The operation evaluates to an error
+    #[default]
+    Error,
+}
+
+/// Arithmetic on two integers, `None` when the operator isn't defined for integers.
+/// The error is the message of the runtime error.
+///
+/// Negative powers give a number, division truncates towards zero.
+/// `%` is the remainder of that division, so it has the sign of the left operand like in C and Rust
+/// (`-7 % 3 == -1`), not of the right one like in Python.
+pub fn integer_binary(
+    left: i64,
+    operator: BinaryOperator,
+    right: i64,
+    mode: OverflowMode,
+) -> Option<Result<Value, String>> {
+    if matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo) && right == 0 {
+        return Some(Err("Integer division by zero".to_owned()));
+    }
+
+    // larger exponents only fit for the bases 0, 1 and -1, the parity decides the sign of -1
+    let exponent = u32::try_from(right).unwrap_or(u32::MAX - 1 + (right & 1) as u32);
+
+    let (checked, wrapping, saturating) = match operator {
+        BinaryOperator::Add => (
+            left.checked_add(right),
+            left.wrapping_add(right),
+            left.saturating_add(right),
+        ),
+        BinaryOperator::Substract => (
+            left.checked_sub(right),
+            left.wrapping_sub(right),
+            left.saturating_sub(right),
+        ),
+        BinaryOperator::Multiply => (
+            left.checked_mul(right),
+            left.wrapping_mul(right),
+            left.saturating_mul(right),
+        ),
+        BinaryOperator::Divide => (
+            left.checked_div(right),
+            left.wrapping_div(right),
+            left.saturating_div(right),
+        ),
+        // `i64::MIN % -1` is 0, only the division behind it overflows
+        BinaryOperator::Modulo => return Some(Ok(Value::Integer(left.wrapping_rem(right)))),
+        BinaryOperator::Power if right < 0 => {
+            return Some(Ok(Value::Number(number_pow(left as f64, right as f64))))
+        }
+        BinaryOperator::Power => (
+            left.checked_pow(exponent),
+            left.wrapping_pow(exponent),
+            left.saturating_pow(exponent),
+        ),
+        BinaryOperator::BitAnd => return Some(Ok(Value::Integer(left & right))),
+        BinaryOperator::BitOr => return Some(Ok(Value::Integer(left | right))),
+        BinaryOperator::BitXor => return Some(Ok(Value::Integer(left ^ right))),
+        _ => return None,
+    };
+
+    Some(match (checked, mode) {
+        (Some(result), _) => Ok(Value::Integer(result)),
+        (None, OverflowMode::Wrap) => Ok(Value::Integer(wrapping)),
+        (None, OverflowMode::Saturate) => Ok(Value::Integer(saturating)),
+        (None, OverflowMode::Error) => {
+            Err(format!("Integer overflow in `{left} {operator} {right}`"))
+        }
+    })
+}
+
+/// Operand of an arithmetic operation
+#[derive(Debug, Clone, Copy)]
+pub enum Number {
+    Float(f64),
+    Decimal(Decimal),
+}
+
+impl Number {
+    /// In decimal mode NaN, infinities and numbers out of decimal range stay floats
+    pub fn new(n: f64, mode: NumberMode) -> Self {
+        match mode {
+            NumberMode::Float => Number::Float(n),
+            NumberMode::Decimal => Decimal::from_str(&n.to_string())
+                .or_else(|_| Decimal::from_scientific(&format!("{n:e}")))
+                .map_or(Number::Float(n), Number::Decimal),
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Number::Float(n) => n,
+            Number::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// Arithmetic on two numbers, `None` when the operator isn't arithmetic.
+    /// Decimal results that don't fit (overflow, division by zero) are computed as floats.
+    ///
+    /// `%` truncates like the integer one, `-7 % 3 == -1` and `7 % -3 == 1` in both modes.
+    pub fn binary(self, operator: BinaryOperator, rhs: Number) -> Option<Value> {
+        if let (Number::Decimal(left), Number::Decimal(right)) = (self, rhs) {
+            let result = match operator {
+                BinaryOperator::Add => left.checked_add(right),
+                BinaryOperator::Substract => left.checked_sub(right),
+                BinaryOperator::Multiply => left.checked_mul(right),
+                BinaryOperator::Divide => left.checked_div(right),
+                BinaryOperator::Modulo => left.checked_rem(right),
+                BinaryOperator::Power => left.checked_powd(right),
+                _ => return None,
+            };
+
+            if let Some(result) = result.and_then(|d| d.to_f64()) {
+                return Some(Value::Number(result));
+            }
+        }
+
+        let (left, right) = (self.to_f64(), rhs.to_f64());
+
+        Some(Value::Number(match operator {
+            BinaryOperator::Add => left + right,
+            BinaryOperator::Substract => left - right,
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide => left / right,
+            BinaryOperator::Modulo => left % right,
+            BinaryOperator::Power => number_pow(left, right),
+            _ => return None,
+        }))
+    }
+}
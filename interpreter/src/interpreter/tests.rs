@@ -0,0 +1,1559 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use serde::de::{
+    value::{Error as DeError, U32Deserializer},
+    DeserializeOwned, IntoDeserializer,
+};
+use symboscript_parser::{bin_op, Parser};
+use symboscript_types::{interpreter::Value, lexer::TokenKind, parser::*};
+
+use super::{
+    DeclarationMode, FileSystemAccess, Interpreter, InterpreterConfig, NumberMode, OverflowMode,
+};
+
+/// Runs the program and returns the value of a global variable
+fn run_and_get(source: &str, name: &str) -> Value {
+    run_with_mode_and_get(source, name, NumberMode::Float)
+}
+
+fn run_with_mode_and_get(source: &str, name: &str, number_mode: NumberMode) -> Value {
+    let interpreter = Interpreter::new("test", source, false).with_number_mode(number_mode);
+    run_interpreter_and_get(interpreter, source, name)
+}
+
+fn run_interpreter_and_get(mut interpreter: Interpreter, source: &str, name: &str) -> Value {
+    let ast = Parser::new("test", source).parse();
+    interpreter.run(ast);
+
+    interpreter.get_variable_value(&Identifier {
+        node: Node::default(),
+        name: name.to_owned(),
+    })
+}
+
+macro_rules! assert_value {
+    ($src: expr, $name: expr, $expected: expr) => {{
+        assert_eq!(format!("{}", run_and_get($src, $name)), $expected);
+    }};
+}
+
+#[test]
+fn string_scope() {
+    assert_value!(
+        r#"let parts = string.split["a,b,c", ","];"#,
+        "parts",
+        "[a, b, c]"
+    );
+    assert_value!(r#"let s = string.upper["abc"];"#, "s", "ABC");
+    assert_value!(r#"let s = string.lower["ABC"];"#, "s", "abc");
+    assert_value!(r#"let s = string.trim["  abc "];"#, "s", "abc");
+    assert_value!(r#"let s = string.replace["a-b", "-", "+"];"#, "s", "a+b");
+    assert_value!(r#"let s = string.contains["abc", "b"];"#, "s", "true");
+}
+
+#[test]
+fn array_scope() {
+    let fns = "fn double[x] return x * 2; fn even[x] return x % 2 == 0; fn add[a, b] return a + b;";
+
+    assert_value!(
+        &format!("{fns} let a = array.map[[1, 2, 3], double];"),
+        "a",
+        "[2, 4, 6]"
+    );
+    assert_value!(
+        &format!("{fns} let a = array.filter[[1, 2, 3, 4], even];"),
+        "a",
+        "[2, 4]"
+    );
+    assert_value!(
+        &format!("{fns} let a = array.reduce[[1, 2, 3], add, 0];"),
+        "a",
+        "6"
+    );
+
+    assert_value!(
+        "let a = [1, 2]; let b = a; array.push[b, 3];",
+        "a",
+        "[1, 2, 3]"
+    );
+    assert_value!("let a = [1, 2]; let b = array.pop[a];", "b", "2");
+    assert_value!("let a = [1, 2]; array.pop[a];", "a", "[1]");
+    assert_value!("let a = array.len[[1, 2, 3]];", "a", "3");
+}
+
+#[test]
+fn structured_errors() {
+    let risky = r#"fn risky[] { throw Error["boom", 42]; } let error = risky[];"#;
+
+    assert_value!(&format!("{risky} let m = error.message;"), "m", "boom");
+    assert_value!(&format!("{risky} let c = error.code;"), "c", "42");
+    assert_value!(&format!("{risky} let e = error.is_err[];"), "e", "true");
+    assert_value!(risky, "error", "boom (code 42)");
+
+    assert_value!(
+        r#"fn risky[] { throw "plain"; } let m = risky[].message;"#,
+        "m",
+        "plain"
+    );
+}
+
+#[test]
+fn value_equality() {
+    assert_value!("let e = [1, 2, 3] == [1, 2, 3];", "e", "true");
+    assert_value!("let e = [1, 2, 3] == [1, 2];", "e", "false");
+    assert_value!(
+        r#"let e = [1, ["a", [true]]] == [1, ["a", [true]]];"#,
+        "e",
+        "true"
+    );
+    assert_value!(
+        r#"let e = [1, ["a", [true]]] == [1, ["a", [false]]];"#,
+        "e",
+        "false"
+    );
+    assert_value!("let nan = 0 / 0; let e = nan == nan;", "e", "false");
+    assert_value!("let nan = 0 / 0; let e = nan != nan;", "e", "true");
+
+    assert_value!("fn f[] {} fn g[] {} let e = f == f;", "e", "true");
+    assert_value!("fn f[] {} fn g[] {} let e = f == g;", "e", "false");
+}
+
+#[test]
+fn value_hashing() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let left = run_and_get(r#"let v = [1, ["a", 0]];"#, "v");
+    let right = run_and_get(r#"let v = [1, ["a", -0]];"#, "v");
+
+    assert_eq!(left, right);
+    assert_eq!(hash(&left), hash(&right));
+}
+
+#[test]
+fn deep_clone() {
+    let nested = "let inner = [2, 3]; let a = [1, inner]; let b = clone[a]; array.push[b, 4];";
+
+    assert_value!(nested, "a", "[1, [2, 3]]");
+    assert_value!(nested, "b", "[1, [2, 3], 4]");
+
+    let inner = "let inner = [2, 3]; let b = clone[[1, inner]]; let copy = array.pop[b]; array.push[copy, 5];";
+
+    assert_value!(inner, "inner", "[2, 3]");
+    assert_value!(inner, "copy", "[2, 3, 5]");
+    assert_value!("let a = [1]; let b = a; array.push[b, 2];", "a", "[1, 2]");
+    assert_value!(
+        r#"let a = hashmap.new[]; a.set["k", [1]]; let b = clone[a]; array.push[b.k, 2]; b.set["n", 1]; let k = a.k; let n = a.has["n"];"#,
+        "k",
+        "[1]"
+    );
+    assert_value!(
+        r#"let a = hashmap.new[]; a.set["k", 1]; let b = clone[a]; b.set["n", 1]; let n = a.has["n"];"#,
+        "n",
+        "false"
+    );
+}
+
+#[test]
+fn block_expression() {
+    assert_value!("let x = { let a = 1; a + 1 };", "x", "2");
+    assert_value!("let x = 2 * { let a = 3; a };", "x", "6");
+    assert_value!("let x = { let a = 1; };", "x", "None");
+    assert_value!(
+        "let a = 10; let x = { let a = 1; a }; let y = a;",
+        "y",
+        "10"
+    );
+
+    // control flow leaves the block and the statement the block is in
+    assert_value!(
+        "fn f[c] { let x = { if (c) { return 5; } 1 }; return x + 100; } let r = [f[true], f[false]];",
+        "r",
+        "[5, 101]"
+    );
+    assert_value!(
+        r#"let log = []; fn g[] { let y = { throw "boom"; 1 }; log.push[y]; return 7; } let m = g[].message;"#,
+        "m",
+        "boom"
+    );
+    assert_value!(
+        r#"let log = []; fn g[] { let y = { throw "boom"; 1 }; log.push[y]; return 7; } g[];"#,
+        "log",
+        "[]"
+    );
+    assert_value!(
+        "let seen = []; for (let i = 0; i < 4; ++i) { seen.push[{ if (i == 2) { break; } i }]; }",
+        "seen",
+        "[0, 1]"
+    );
+}
+
+#[test]
+fn defer_statement() {
+    assert_value!(
+        "let log = []; fn f[] { defer array.push[log, 1]; defer array.push[log, 2]; array.push[log, 0]; } f[];",
+        "log",
+        "[0, 2, 1]"
+    );
+    assert_value!(
+        "let log = []; fn f[] { defer array.push[log, 1]; return 2; array.push[log, 3]; } let r = f[]; array.push[log, r];",
+        "log",
+        "[1, 2]"
+    );
+    assert_value!(
+        r#"let log = []; fn f[] { defer { array.push[log, 1]; } throw "oops"; } let r = f[];"#,
+        "log",
+        "[1]"
+    );
+    assert_value!(
+        "let log = []; { defer array.push[log, 1]; array.push[log, 0]; } array.push[log, 2];",
+        "log",
+        "[0, 1, 2]"
+    );
+}
+
+#[test]
+fn scope_inheritance() {
+    let scopes = r#"
+        scope A { let name = "A"; let kind = "base"; }
+        scope B : A { let name = "B"; }
+        scope C : B {}
+    "#;
+
+    assert_value!(&format!("{scopes} let k = B.kind;"), "k", "base");
+    assert_value!(&format!("{scopes} let n = B.name;"), "n", "B");
+    assert_value!(&format!("{scopes} let n = A.name;"), "n", "A");
+    assert_value!(&format!("{scopes} let k = C.kind;"), "k", "base");
+    assert_value!(&format!("{scopes} let n = C.name;"), "n", "B");
+}
+
+#[test]
+fn loop_else() {
+    assert_value!(
+        "let log = []; let i = 0; while (i < 3) { mut i += 1; } else { array.push[log, i]; }",
+        "log",
+        "[3]"
+    );
+    assert_value!(
+        "let log = []; let i = 0; while (i < 3) { mut i += 1; if (i == 2) break } else { array.push[log, i]; }",
+        "log",
+        "[]"
+    );
+    assert_value!(
+        "let log = []; for (let i = 0; i < 3; ++i) { array.push[log, i]; } else { array.push[log, \"done\"]; }",
+        "log",
+        "[0, 1, 2, done]"
+    );
+    assert_value!(
+        "let log = []; for (let i = 0; i < 3; ++i) { if (i == 1) break; array.push[log, i]; } else { array.push[log, \"done\"]; }",
+        "log",
+        "[0]"
+    );
+}
+
+#[test]
+fn bitwise_assign() {
+    assert_value!("let x = 3; mut x <<= 2;", "x", "12");
+    assert_value!("let x = 12; mut x >>= 2;", "x", "3");
+    assert_value!("let x = 5; mut x &= 6;", "x", "4");
+    assert_value!("let x = 5; mut x |= 6;", "x", "7");
+    assert_value!("let x = 5; mut x bxor= 6;", "x", "3");
+}
+
+#[test]
+fn repeat_statement() {
+    assert_value!("let n = 0; repeat (3) { mut n += 1; }", "n", "3");
+    assert_value!("let n = 0; repeat (0) { mut n += 1; }", "n", "0");
+    assert_value!(
+        "let n = 0; repeat (10) { mut n += 1; if (n == 4) break }",
+        "n",
+        "4"
+    );
+    assert_value!(
+        "let log = []; let i = 0; repeat (4) { mut i += 1; if (i % 2 == 0) continue array.push[log, i]; }",
+        "log",
+        "[1, 3]"
+    );
+
+    assert_value!(
+        "fn f[] { repeat (-1) {} } let e = f[].message;",
+        "e",
+        "repeat count must be a non-negative integer, got -1"
+    );
+    assert_value!(
+        "fn f[] { repeat (1.5) {} } let e = f[].is_err[];",
+        "e",
+        "true"
+    );
+}
+
+#[test]
+fn decimal_mode() {
+    let decimal =
+        |source, name| run_with_mode_and_get(source, name, NumberMode::Decimal).to_string();
+
+    assert_eq!(decimal("let e = 0.1 + 0.2 == 0.3;", "e"), "true");
+    assert_eq!(decimal("let x = 0.1 + 0.2;", "x"), "0.3");
+    assert_eq!(decimal("let x = 1.1 * 1.1;", "x"), "1.21");
+    assert_eq!(decimal("let x = 0.3 - 0.1;", "x"), "0.2");
+    assert_eq!(decimal("let x = 1 / 0;", "x"), "inf");
+
+    assert_value!("let e = 0.1 + 0.2 == 0.3;", "e", "false");
+}
+
+#[test]
+fn freeze() {
+    let frozen = "let a = freeze[[1, 2]]; let r = array.push[a, 3];";
+
+    assert_value!(frozen, "a", "[1, 2]");
+    assert_value!(
+        &format!("{frozen} let m = r.message;"),
+        "m",
+        "Can't change a frozen array"
+    );
+    assert_value!(
+        "let a = [1, 2]; freeze[a]; let e = array.pop[a].is_err[];",
+        "e",
+        "true"
+    );
+    assert_value!(
+        "let a = [1, 2]; let r = array.push[a, 3]; let e = r.is_err[];",
+        "e",
+        "false"
+    );
+    assert_value!(
+        "let a = freeze[[1]]; let b = clone[a]; array.push[b, 2];",
+        "b",
+        "[1, 2]"
+    );
+
+    let map = r#"let h = hashmap.new[]; h.set["a", 1]; freeze[h];"#;
+
+    assert_value!(
+        &format!(r#"{map} let e = h.set["b", 2].is_err[];"#),
+        "e",
+        "true"
+    );
+    assert_value!(
+        &format!(r#"{map} h.set["b", 2]; let b = h.has["b"];"#),
+        "b",
+        "false"
+    );
+    assert_value!(
+        &format!(r#"{map} let e = h.del["a"].is_err[]; "#),
+        "e",
+        "true"
+    );
+    assert_value!(&format!("{map} h.clear[]; let a = h.a;"), "a", "1");
+    assert_value!(
+        r#"let h = hashmap.new[]; let e = h.set["a", 1].is_err[];"#,
+        "e",
+        "false"
+    );
+}
+
+#[test]
+fn type_error_origin() {
+    let source = r#"let e = "a" * true; let span = [e.start, e.end];"#;
+
+    assert_value!(source, "span", "[8, 11]");
+    assert_value!(
+        &format!("{source} let m = e.message;"),
+        "m",
+        "Unsupported operand types for `*`: str and bool"
+    );
+    assert_value!(
+        r#"let x = 2; let e = x - "b"; let span = [e.start, e.end];"#,
+        "span",
+        "[23, 26]"
+    );
+    // the error keeps its origin when it flows into other operations
+    assert_value!(
+        r#"let e = ("a" * true) + 1; let span = [e.start, e.end];"#,
+        "span",
+        "[9, 12]"
+    );
+    assert_value!(r#"let s = "a" * 3;"#, "s", "aaa");
+}
+
+#[test]
+fn integer_power() {
+    assert_value!("let e = 2^62 == 4611686018427387904;", "e", "true");
+    assert_value!("let e = 2^62 == 2^31 * 2^31;", "e", "true");
+    assert_value!("let e = 10^18 == 1000000000000000000;", "e", "true");
+    assert_value!("let e = 10^18 - 10^17 * 10 == 0;", "e", "true");
+    assert_value!("let x = 7^0;", "x", "1");
+
+    // non-integers and overflowing results take the float path
+    assert_value!("let x = 4^0.5;", "x", "2");
+    assert_value!("let x = 2^(0-1);", "x", "0.5");
+    assert_value!("let x = 2.5^2;", "x", "6.25");
+    assert_value!("let e = 2^64 == 18446744073709551616;", "e", "true");
+    assert_value!("let x = 10^400;", "x", "inf");
+}
+
+#[test]
+fn destructuring() {
+    assert_value!("let [a, b] = [1, 2];", "a", "1");
+    assert_value!("let [a, b] = [1, 2];", "b", "2");
+    assert_value!("let [a, b] = [[1, 2], 3];", "a", "[1, 2]");
+    assert_value!("let s = [3, 4]; let [x, y] = s; let z = x + y;", "z", "7");
+
+    assert_value!(
+        "fn f[] { let [a, b] = [1, 2, 3]; } let m = f[].message;",
+        "m",
+        "Can't destructure a sequence of 3 items into 2 names"
+    );
+    assert_value!(
+        "fn f[] { let [a] = 1; } let m = f[].message;",
+        "m",
+        "Can't destructure number 1, expected a sequence"
+    );
+}
+
+#[test]
+fn global_scope_access() {
+    assert_value!(
+        "let x = 1; fn f[] { let x = 2; return global.x; } let r = f[];",
+        "r",
+        "1"
+    );
+    assert_value!(
+        "let g = 0; fn f[] { mut g = 5; let g = 7; return [g, global.g]; } let r = f[];",
+        "r",
+        "[7, 5]"
+    );
+    assert_value!(
+        "let global = hashmap.new[]; global.set[\"x\", 1]; let r = global.x;",
+        "r",
+        "1"
+    );
+}
+
+#[test]
+fn assertions() {
+    assert_value!("let r = assert_eq[[1, 2], [1, 2]];", "r", "None");
+    assert_value!("let r = assert_ne[1, 2];", "r", "None");
+    assert_value!("let r = assert[1 < 2];", "r", "None");
+
+    assert_value!(
+        "let m = assert_eq[[1, 2], [1, 3]].message;",
+        "m",
+        "Assertion `left == right` failed (left: [1, 2], right: [1, 3])"
+    );
+    assert_value!(
+        r#"let m = assert_ne["a", "a", "names differ"].message;"#,
+        "m",
+        "Assertion `left != right` failed (left: a, right: a): names differ"
+    );
+    assert_value!(
+        r#"let m = assert[false, "custom"].message;"#,
+        "m",
+        "Assertion failed: custom"
+    );
+}
+
+#[test]
+fn json() {
+    assert_value!(
+        r#"let s = json.stringify[json.parse["{\"a\":[1,2]}"]];"#,
+        "s",
+        r#"{"a":[1,2]}"#
+    );
+    assert_value!(
+        r#"let m = json.parse["{\"a\": {\"b\": true}}"]; let b = m.a.b;"#,
+        "b",
+        "true"
+    );
+    assert_value!(
+        r#"let v = json.parse["[1.5, null, \"x\", false]"];"#,
+        "v",
+        "[1.5, None, x, false]"
+    );
+    assert_value!(
+        r#"let n; let m = hashmap.new[]; m.set["k", [1, "a", n]]; let s = json.stringify[m];"#,
+        "s",
+        r#"{"k":[1,"a",null]}"#
+    );
+
+    assert_value!(r#"let e = json.parse["{"].is_err[];"#, "e", "true");
+    assert_value!(
+        "fn f[] {} let m = json.stringify[[f]].message;",
+        "m",
+        "Can't convert function to JSON"
+    );
+}
+
+#[test]
+fn file_system_access() {
+    let dir = std::env::temp_dir().join(format!("symboscript-fs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir
+        .join("note.txt")
+        .display()
+        .to_string()
+        .replace('\\', "/");
+
+    let source = format!(r#"let w = write_file["{path}", "hello"]; let r = read_file["{path}"];"#);
+    let allowed =
+        || Interpreter::new("test", &source, false).with_fs_access(FileSystemAccess::Allowed);
+
+    assert_eq!(
+        run_interpreter_and_get(allowed(), &source, "w").to_string(),
+        "None"
+    );
+    assert_eq!(
+        run_interpreter_and_get(allowed(), &source, "r").to_string(),
+        "hello"
+    );
+
+    let missing = dir
+        .join("missing.txt")
+        .display()
+        .to_string()
+        .replace('\\', "/");
+    let source = format!(r#"let e = read_file["{missing}"].is_err[];"#);
+    let interpreter =
+        Interpreter::new("test", &source, false).with_fs_access(FileSystemAccess::Allowed);
+    assert_eq!(
+        run_interpreter_and_get(interpreter, &source, "e").to_string(),
+        "true"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // denied by default, nothing is written
+    let denied = dir
+        .join("denied.txt")
+        .display()
+        .to_string()
+        .replace('\\', "/");
+    assert_value!(
+        &format!(r#"let m = write_file["{denied}", "x"].message;"#),
+        "m",
+        "Permission denied: file system access is disabled"
+    );
+    assert_value!(
+        &format!(r#"let m = read_file["{path}"].message;"#),
+        "m",
+        "Permission denied: file system access is disabled"
+    );
+    assert!(!dir.join("denied.txt").exists());
+}
+
+#[test]
+fn curried_functions() {
+    let add = "fn add[a, b, ..] return a + b;";
+
+    assert_value!(&format!("{add} let r = add[2][3];"), "r", "5");
+    assert_value!(&format!("{add} let r = add[2, 3];"), "r", "5");
+    assert_value!(
+        &format!("{add} let inc = add[1]; let r = inc[41];"),
+        "r",
+        "42"
+    );
+    assert_value!(&format!("{add} let r = add[][1][2];"), "r", "3");
+    assert_value!(&format!("{add} let r = add[2];"), "r", "add[2, ..]");
+
+    assert_value!(
+        "fn sum3[a, b, c, ..] return a + b + c; let r = sum3[1][2][3];",
+        "r",
+        "6"
+    );
+    assert_value!(
+        "fn pair[a, b] return [a, b]; fn make[] return pair; let r = make[][1, 2];",
+        "r",
+        "[1, 2]"
+    );
+}
+
+#[test]
+fn named_arguments() {
+    let f = "fn f[a, b, c] return [a, b, c];";
+
+    assert_value!(
+        &format!("{f} let r = f[c = 3, a = 1, b = 2];"),
+        "r",
+        "[1, 2, 3]"
+    );
+    assert_value!(
+        &format!("{f} let r = f[1, c = 3, b = 2];"),
+        "r",
+        "[1, 2, 3]"
+    );
+    assert_value!(
+        &format!("{f} let r = f[1, 2, c = 1 + 2];"),
+        "r",
+        "[1, 2, 3]"
+    );
+    assert_value!(
+        "fn add[a, b, ..] return a - b; let r = add[b = 1];",
+        "r",
+        "Missing argument `a`"
+    );
+    assert_value!(
+        "fn sub[a, b, ..] return a - b; let r = sub[a = 5][b = 1];",
+        "r",
+        "4"
+    );
+
+    assert_value!(
+        &format!("{f} let m = f[1, 2, d = 3].message;"),
+        "m",
+        "Unknown argument `d`"
+    );
+    assert_value!(
+        &format!("{f} let m = f[1, a = 2, c = 3].message;"),
+        "m",
+        "Argument `a` is given more than once"
+    );
+    assert_value!(
+        &format!("{f} let m = f[b = 1, b = 2, c = 3].message;"),
+        "m",
+        "Argument `b` is given more than once"
+    );
+    assert_value!(
+        &format!("{f} let m = f[1, c = 3].message;"),
+        "m",
+        "Missing argument `b`"
+    );
+}
+
+#[test]
+fn while_let() {
+    let queue = "let items = [3, 0, 2]; fn next[] return array.pop[items];";
+
+    assert_value!(
+        &format!("{queue} let sum = 0; while let x = next[] {{ mut sum += x; }}"),
+        "sum",
+        "5"
+    );
+    assert_value!(
+        &format!(
+            "{queue} let seen = []; while let x = next[] {{ let y = x * 2; array.push[seen, y]; }}"
+        ),
+        "seen",
+        "[4, 0, 6]"
+    );
+    assert_value!(
+        &format!("{queue} let r = 0; while let x = next[] {{ if (x == 0) {{ break }} mut r = x; }} else {{ mut r = 100; }}"),
+        "r",
+        "2"
+    );
+    assert_value!(
+        &format!("{queue} let r = 0; while let x = next[] {{ }} else {{ mut r = 1; }} let left = array.len[items];"),
+        "left",
+        "0"
+    );
+    assert_value!(
+        "let x = 7; let i = [1]; fn next[] return array.pop[i]; while let x = next[] { } let r = x;",
+        "r",
+        "7"
+    );
+}
+
+#[test]
+fn default_assign() {
+    assert_value!("let x; mut x ??= 5;", "x", "5");
+    assert_value!("let x = 0; mut x ??= 5;", "x", "0");
+    assert_value!(
+        "let calls = 0; fn fallback[] { mut calls += 1; return 5; } let x = 1; mut x ??= fallback[];",
+        "calls",
+        "0"
+    );
+    assert_value!(
+        "let calls = 0; fn fallback[] { mut calls += 1; return 5; } let x; mut x ??= fallback[]; mut x ??= fallback[];",
+        "calls",
+        "1"
+    );
+}
+
+#[test]
+fn host_functions() {
+    let run = |source: &str, name: &str| {
+        let mut interpreter = Interpreter::new("test", source, false);
+        interpreter.register_fn(
+            "double",
+            Box::new(|args| match args {
+                [Value::Number(n)] => Ok(Value::Number(n * 2.0)),
+                _ => Err("double expects one number".to_owned()),
+            }),
+        );
+
+        format!("{}", run_interpreter_and_get(interpreter, source, name))
+    };
+
+    assert_eq!(run("let x = double[21];", "x"), "42");
+    assert_eq!(run("let x = array.map[[1, 2], double];", "x"), "[2, 4]");
+    assert_eq!(run("let f = double;", "f"), "double");
+    assert_eq!(
+        run(r#"let m = double["a"].message;"#, "m"),
+        "double expects one number"
+    );
+}
+
+#[test]
+fn print_native_functions() {
+    let source = r#"println[double, println, string.upper, json.parse]; let done = true;"#;
+    let double: fn(&[Value]) -> Result<Value, String> = |args| Ok(args[0].clone());
+
+    // the terminal path, it used to panic on native functions
+    let mut interpreter = Interpreter::new("test", source, false);
+    interpreter.register_fn("double", Box::new(double));
+    run_interpreter_and_get(interpreter, source, "done");
+
+    let lines = Rc::new(RefCell::new(Vec::<String>::new()));
+    let sink_lines = lines.clone();
+    let mut interpreter =
+        Interpreter::new("test", source, false).with_output(Box::new(move |line| {
+            sink_lines.borrow_mut().push(line.to_owned())
+        }));
+    interpreter.register_fn("double", Box::new(double));
+    run_interpreter_and_get(interpreter, source, "done");
+
+    assert_eq!(*lines.borrow(), ["double println string.upper json.parse"]);
+}
+
+#[test]
+fn print_declared_functions() {
+    let lines = Rc::new(RefCell::new(Vec::<String>::new()));
+    let sink_lines = lines.clone();
+    let interpreter = Interpreter::new("test", "", false).with_output(Box::new(move |line| {
+        sink_lines.borrow_mut().push(line.to_owned())
+    }));
+
+    let source = "fn add[a, b] { return a + b; } println[add, 1]; let done = true;";
+    run_interpreter_and_get(interpreter, source, "done");
+
+    assert_eq!(*lines.borrow(), ["fn add(a, b) {\nreturn (a+b);\n} 1"]);
+}
+
+#[test]
+fn preset_globals() {
+    let source = "let doubled = limit * 2; let greeting = name;";
+    let run = |name: &str| {
+        let mut interpreter = Interpreter::new("test", source, false);
+        interpreter.set_global("limit", Value::Number(21.0));
+        interpreter.set_global("name", Value::Str("host".to_owned()));
+
+        format!("{}", run_interpreter_and_get(interpreter, source, name))
+    };
+
+    assert_eq!(run("doubled"), "42");
+    assert_eq!(run("greeting"), "host");
+}
+
+#[test]
+fn bytes_scope() {
+    assert_value!(
+        "let b = bytes.new[[104, 105, 0, 255]]; let s = bytes.to_base64[b];",
+        "s",
+        "aGkA/w=="
+    );
+    assert_value!(
+        r#"let b = bytes.from_base64["aGkA/w=="];"#,
+        "b",
+        "bytes[104, 105, 0, 255]"
+    );
+    assert_value!(
+        "let b = bytes.new[[1, 2, 3]]; let same = bytes.from_base64[bytes.to_base64[b]] == b;",
+        "same",
+        "true"
+    );
+    assert_value!(
+        "let b = bytes.new[[7, 8]]; let x = bytes.get[b, 1]; let n = bytes.len[b];",
+        "x",
+        "8"
+    );
+    assert_value!(
+        "let b = bytes.new[[7, 8]]; let m = bytes.get[b, 2].message;",
+        "m",
+        "Index 2 is out of range for 2 bytes"
+    );
+    assert_value!(
+        "let m = bytes.new[[256]].message;",
+        "m",
+        "Can't make a byte from 256"
+    );
+    assert_value!(
+        r#"let r = bytes.from_base64["not base64!"].is_err[];"#,
+        "r",
+        "true"
+    );
+}
+
+#[test]
+fn hashmap_insertion_order() {
+    let map = r#"let h = hashmap.new[]; h.set["zeta", 1]; h.set["alpha", 2]; h.set["mid", 3];"#;
+
+    assert_value!(
+        &format!("{map} let k = h.keys[];"),
+        "k",
+        "[zeta, alpha, mid]"
+    );
+    assert_value!(&format!("{map} let v = h.values[];"), "v", "[1, 2, 3]");
+    assert_value!(
+        &format!(r#"{map} h.del["zeta"]; h.set["zeta", 4]; h.set["alpha", 5]; let k = h.keys[];"#),
+        "k",
+        "[alpha, mid, zeta]"
+    );
+}
+
+#[test]
+fn loop_expression() {
+    assert_value!("let x = loop { break 5; };", "x", "5");
+    assert_value!(
+        "let i = 0; let x = loop { mut i += 1; if (i == 4) { break i * 10; } };",
+        "x",
+        "40"
+    );
+    assert_value!("let x = loop { break };", "x", "None");
+    assert_value!(
+        "let i = 0; loop { mut i += 1; if (i == 3) { break i; } }",
+        "i",
+        "3"
+    );
+    assert_value!(
+        "let x = loop { throw \"stop\"; }; let m = x.message;",
+        "m",
+        "stop"
+    );
+}
+
+#[test]
+fn streamed_output() {
+    let lines = Rc::new(RefCell::new(Vec::<String>::new()));
+
+    let sink_lines = lines.clone();
+    let mut interpreter = Interpreter::new("test", "", false).with_output(Box::new(move |line| {
+        sink_lines.borrow_mut().push(line.to_owned())
+    }));
+
+    // the host sees how many lines arrived while the loop is still running
+    let seen_lines = lines.clone();
+    interpreter.register_fn(
+        "received",
+        Box::new(move |_| Ok(Value::Number(seen_lines.borrow().len() as f64))),
+    );
+
+    let source = r#"let counts = []; repeat (3) { print["line"]; println[array.len[counts]]; array.push[counts, received[]]; } print["end"];"#;
+    let counts = run_interpreter_and_get(interpreter, source, "counts");
+
+    assert_eq!(format!("{counts}"), "[1, 2, 3]");
+    assert_eq!(*lines.borrow(), ["line0", "line1", "line2", "end"]);
+}
+
+#[test]
+fn separate_error_output() {
+    let out = Rc::new(RefCell::new(Vec::<String>::new()));
+    let err = Rc::new(RefCell::new(Vec::<String>::new()));
+
+    let (out_lines, err_lines) = (out.clone(), err.clone());
+    let interpreter = Interpreter::new("test", "", false)
+        .with_output(Box::new(move |line| {
+            out_lines.borrow_mut().push(line.to_owned())
+        }))
+        .with_error_output(Box::new(move |line| {
+            err_lines.borrow_mut().push(line.to_owned())
+        }));
+
+    let source = r#"let x = 1; println["data", x]; eprint["log"]; eprintln[x]; print["more"]; eprint["end"];"#;
+    run_interpreter_and_get(interpreter, source, "x");
+
+    assert_eq!(*out.borrow(), ["data 1", "more"]);
+    assert_eq!(*err.borrow(), ["log1", "end"]);
+}
+
+#[test]
+fn hoisted_functions() {
+    assert_value!(
+        "let r = a[3]; fn a[n] return b[n] + 1; fn b[n] return n * 2;",
+        "r",
+        "7"
+    );
+    assert_value!(
+        "let r = even[4]; fn even[n] { if (n == 0) { return true; } return odd[n - 1]; } fn odd[n] { if (n == 0) { return false; } return even[n - 1]; }",
+        "r",
+        "true"
+    );
+    assert_value!(
+        "scope s { let r = f[]; fn f[] return 1; } let r = s.r;",
+        "r",
+        "1"
+    );
+}
+
+#[test]
+fn none_literal() {
+    assert_value!("let x = none;", "x", "None");
+    assert_value!("let x = none; let r = x == none;", "r", "true");
+    assert_value!("let r = 0 == none;", "r", "false");
+}
+
+#[test]
+fn mixed_type_comparison() {
+    assert_value!(
+        r#"let e = 1 < "a"; let m = e.message;"#,
+        "m",
+        "Unsupported operand types for `<`: number and str"
+    );
+    assert_value!(
+        "let e = [1, 2] >= 2; let m = e.message;",
+        "m",
+        "Unsupported operand types for `>=`: sequence and number"
+    );
+    assert_value!(
+        r#"let x = 1; let e = x > "b"; let span = [e.start, e.end];"#,
+        "span",
+        "[23, 26]"
+    );
+    assert_value!(r#"let r = ["a" < "b", "b" <= "a"];"#, "r", "[true, false]");
+    assert_value!("let r = [1 < 2, 2 > 3];", "r", "[true, false]");
+}
+
+#[test]
+fn check_without_running() {
+    let source = "let x = 1; println[x, y]; fn f[a] return a.len + string.upper[a]; mut z = f[x];";
+    let printed = Rc::new(RefCell::new(Vec::<String>::new()));
+
+    let sink_printed = printed.clone();
+    let interpreter = Interpreter::new("test", source, false).with_output(Box::new(move |line| {
+        sink_printed.borrow_mut().push(line.to_owned())
+    }));
+
+    let messages: Vec<_> = interpreter
+        .check()
+        .into_iter()
+        .map(|d| (d.code, d.message, &source[d.span]))
+        .collect();
+
+    assert_eq!(
+        messages,
+        [
+            (
+                "undefined_variable",
+                "Variable `y` not found".to_owned(),
+                "y"
+            ),
+            (
+                "undefined_variable",
+                "Variable `z` not found".to_owned(),
+                "mut z"
+            )
+        ]
+    );
+    assert!(printed.borrow().is_empty());
+
+    let interpreter = Interpreter::new("test", "return 1;", false);
+    assert_eq!(interpreter.check()[0].code, "misplaced_statement");
+}
+
+#[test]
+fn check_applies_limits() {
+    let limited = |max_source_len, max_tokens| {
+        let config = InterpreterConfig {
+            max_source_len,
+            max_tokens,
+            ..Default::default()
+        };
+        let interpreter = Interpreter::with_config("test", "let x = 1; x;", false, config);
+        let codes: Vec<_> = interpreter.check().into_iter().map(|d| d.code).collect();
+        codes
+    };
+
+    assert!(!limited(None, None).contains(&"too_many_tokens"));
+    assert!(limited(Some(5), None).contains(&"source_too_large"));
+    assert!(limited(None, Some(5)).contains(&"too_many_tokens"));
+}
+
+#[test]
+fn word_expressions() {
+    assert_value!(
+        "let x = 1; let removed = delete x; let x = 2; let r = [removed, x];",
+        "r",
+        "[1, 2]"
+    );
+    // the inner variable is deleted, the outer one becomes visible again
+    assert_value!("let x = 1; let r = { let x = 2; delete x; x };", "r", "1");
+    assert_value!(
+        "scope a { let b = 1; } let e = delete a.b; let m = e.message;",
+        "m",
+        "Only variables can be deleted"
+    );
+    assert_value!("fn f[] return 6; let r = await f[];", "r", "6");
+}
+
+#[test]
+fn chained_methods() {
+    assert_value!(r#"let r = "abc".upper[];"#, "r", "ABC");
+    assert_value!(r#"let r = " a,b ".trim[].split[","].len[];"#, "r", "2");
+    // the receiver of the argument's method doesn't replace the outer one
+    assert_value!(
+        r#"let s = "x;y"; let r = s.split[";".trim[]].len[];"#,
+        "r",
+        "2"
+    );
+    assert_value!("let a = [1]; a.push[2]; let r = a.len[];", "r", "2");
+    assert_value!(r#"let r = (1 + 2).to_string[];"#, "r", "3");
+    // the receiver is bound when the method is looked up
+    assert_value!(r#"let up = "abc".upper; let r = up[];"#, "r", "ABC");
+    assert_value!(
+        r#"let up = "abc".upper; let r = [1, 2].map[fn [x] { return up[]; }];"#,
+        "r",
+        "[ABC, ABC]"
+    );
+    assert_value!(
+        "let n = (5).to_string; let r = [n[], n == (5).to_string, n == (6).to_string];",
+        "r",
+        "[5, true, false]"
+    );
+}
+
+#[test]
+fn integer_overflow() {
+    let overflow = |source: &str, mode| {
+        let interpreter = Interpreter::new("test", source, false).with_overflow_mode(mode);
+        run_interpreter_and_get(interpreter, source, "r").to_string()
+    };
+    let max_plus_one = "let r = 9223372036854775807i + 1i;";
+
+    assert_eq!(
+        overflow(max_plus_one, OverflowMode::Wrap),
+        i64::MIN.to_string()
+    );
+    assert_eq!(
+        overflow(max_plus_one, OverflowMode::Saturate),
+        i64::MAX.to_string()
+    );
+    assert_value!(
+        &format!("{max_plus_one} let m = r.message;"),
+        "m",
+        "Integer overflow in `9223372036854775807 + 1`"
+    );
+
+    assert_value!("let r = 7i / 2i + 2i ** 10i;", "r", "1027");
+    assert_value!("let r = 1i + 0.5;", "r", "1.5");
+    assert_value!(
+        "let r = 1i / 0i; let m = r.message;",
+        "m",
+        "Integer division by zero"
+    );
+}
+
+#[test]
+fn compound_assignment_matches_binary() {
+    for operator in ["+", "-", "*", "/", "^", "%", "<<", ">>", "&", "|"] {
+        let source = format!("let a = 12 {operator} 5; let b = 12; mut b {operator}= 5;");
+
+        assert_eq!(
+            run_and_get(&source, "b").to_string(),
+            run_and_get(&source, "a").to_string(),
+            "{operator}="
+        );
+    }
+
+    // compound assignments share the overflow and type checks of binary expressions
+    assert_value!(
+        "let x = 9223372036854775807i; mut x += 1i; let m = x.message;",
+        "m",
+        "Integer overflow in `9223372036854775807 + 1`"
+    );
+    assert_value!(
+        r#"let x = 1; mut x -= "a"; let m = x.message;"#,
+        "m",
+        "Unsupported operand types for `-`: number and str"
+    );
+}
+
+/// Every variant of a fieldless enum, in declaration order
+fn variants<T: DeserializeOwned>() -> Vec<T> {
+    (0..)
+        .map_while(|i: u32| {
+            let index: U32Deserializer<DeError> = i.into_deserializer();
+            T::deserialize(index).ok()
+        })
+        .collect()
+}
+
+#[test]
+fn binary_operators_are_parsed_and_evaluated() {
+    use BinaryOperator::*;
+
+    // `6 op 3`, logic operators get `true op false`.
+    // A new operator doesn't compile here until its result is added
+    let expected = |operator| match operator {
+        Add => "9",
+        Substract => "3",
+        Multiply => "18",
+        Divide => "2",
+        Power => "216",
+        Range => "6..3",
+        Modulo => "0",
+        And => "false",
+        Or => "true",
+        Xor => "true",
+        BitAnd => "2",
+        BitOr => "7",
+        BitXor => "5",
+        BitLeftShift => "48",
+        BitRightShift => "0",
+        Equal => "false",
+        NotEqual => "true",
+        Is => "false",
+        Less => "false",
+        LessEqual => "false",
+        Greater => "true",
+        GreaterEqual => "true",
+    };
+
+    let mut interpreter = Interpreter::new("test", "", false);
+    let mut parsed = vec![];
+
+    for kind in variants::<TokenKind>() {
+        let Some(operator) = bin_op(kind) else {
+            continue;
+        };
+        parsed.push(operator);
+
+        let (left, right) = match operator {
+            And | Or | Xor => (Value::Bool(true), Value::Bool(false)),
+            _ => (Value::Number(6.0), Value::Number(3.0)),
+        };
+        let node = Node::default();
+        let value = interpreter.binary_operation(left, operator, right, node, (node, node));
+
+        assert_eq!(value.to_string(), expected(operator), "{kind:?}");
+    }
+
+    for operator in variants::<BinaryOperator>() {
+        assert!(
+            parsed.contains(&operator),
+            "no token parses to {operator:?}"
+        );
+    }
+}
+
+#[test]
+fn with_statement() {
+    let resource = r#"let log = []; scope res { fn close[] { array.push[log, "closed"]; } }"#;
+
+    assert_value!(
+        &format!(r#"{resource} with res as r {{ array.push[log, "body"]; }}"#),
+        "log",
+        "[body, closed]"
+    );
+    assert_value!(
+        &format!(r#"{resource} fn f[] {{ with res as r {{ throw "boom"; }} }} let e = f[];"#),
+        "log",
+        "[closed]"
+    );
+    assert_value!(
+        &format!(r#"{resource} fn f[] {{ with res as r {{ return 1; }} }} let x = f[];"#),
+        "log",
+        "[closed]"
+    );
+    // values without `close` are only bound
+    assert_value!("let x = 0; with 5 as n { mut x = n; }", "x", "5");
+}
+
+#[test]
+fn template_strings() {
+    assert_value!("let r = `sum=${1+2}`;", "r", "sum=3");
+    assert_value!(
+        r#"let x = 2; let r = `${x} ${ {x * 2} } \${x} ${"}"}`;"#,
+        "r",
+        "2 4 ${x} }"
+    );
+    assert_value!("let r = `${true}`.upper[];", "r", "TRUE");
+}
+
+#[test]
+fn eval_single_expression() {
+    let mut interpreter = Interpreter::new("test", "", false);
+
+    assert_eq!(interpreter.eval_expr("2 + 3 * 4"), Value::Number(14.0));
+    assert_eq!(
+        interpreter.eval_expr(r#""a,b".split[","].len[]"#),
+        Value::Number(2.0)
+    );
+
+    let source = "let rate = 3;";
+    let mut interpreter = Interpreter::new("test", source, false);
+    interpreter.run(Parser::new("test", source).parse());
+    assert_eq!(interpreter.eval_expr("rate * 2"), Value::Number(6.0));
+}
+
+#[test]
+fn empty_programs() {
+    for source in ["", "   \n", "# just a comment"] {
+        let mut interpreter = Interpreter::new("test", source, false);
+        interpreter.run(Parser::new("test", source).parse());
+
+        assert_eq!(interpreter.check(), []);
+    }
+}
+
+#[test]
+fn function_expressions() {
+    assert_value!(
+        "let double = fn[x] { return x * 2; }; let r = double[4];",
+        "r",
+        "8"
+    );
+    assert_value!(
+        "let r = [1, 2, 3].map[fn[x] { return x + 1; }];",
+        "r",
+        "[2, 3, 4]"
+    );
+    assert_value!(
+        "let add = fn[a, b, ..] { return a + b; }; let r = add[1][2];",
+        "r",
+        "3"
+    );
+}
+
+#[test]
+fn size_of_values() {
+    assert_value!(r#"let r = sizeof["abc"];"#, "r", "3");
+    assert_value!(r#"let r = sizeof["héllo"];"#, "r", "6");
+    assert_value!("let r = sizeof[[1, 2, 3]];", "r", "24");
+    assert_value!(r#"let r = sizeof[["ab", [true, none]]];"#, "r", "3");
+    assert_value!(
+        r#"let h = hashmap.new[]; h.set["key", "value"]; let r = sizeof[h];"#,
+        "r",
+        "8"
+    );
+    // a value reachable twice is counted once, so cycles end
+    assert_value!("let a = [1]; a.push[a]; let r = sizeof[a];", "r", "8");
+}
+
+#[test]
+fn identity_operator() {
+    let source = r#"let a = [1, 2]; let b = [1, 2]; let c = a;
+let h = hashmap.new[]; let g = hashmap.new[];
+let r = [a == b, a is b, a is c, 2 is 2, "x" is "x", h is h, h is g];"#;
+
+    assert_value!(source, "r", "[true, false, true, true, true, true, false]");
+}
+
+#[test]
+fn logical_xor() {
+    assert_value!("let r = true xor false;", "r", "true");
+    assert_value!("let r = true xor true;", "r", "false");
+    assert_value!("let r = false xor false;", "r", "false");
+    assert_value!("let r = 1 < 2 xor 2 < 1;", "r", "true");
+}
+
+#[test]
+fn function_decorators() {
+    // curried decorators bind the function and take the call's arguments
+    let source = r#"let calls = 0;
+fn log[f, x, ..] { mut calls += 1; return f[x]; }
+fn twice[f, x, ..] { return f[f[x]]; }
+
+@log
+@twice
+fn double[x] { return x * 2; }
+
+let r = [double[3], double[1], calls];"#;
+
+    assert_value!(source, "r", "[12, 4, 2]");
+}
+
+#[test]
+fn print_separator() {
+    let printed = |interpreter: Interpreter| {
+        let lines = Rc::new(RefCell::new(Vec::<String>::new()));
+        let sink_lines = lines.clone();
+        let interpreter = interpreter.with_output(Box::new(move |line| {
+            sink_lines.borrow_mut().push(line.to_owned())
+        }));
+
+        let source =
+            r#"let x = 1; println["a", "b"]; print[x, 2]; print[3]; println[]; println[[1, 2]];"#;
+        run_interpreter_and_get(interpreter, source, "x");
+        lines.take()
+    };
+
+    assert_eq!(
+        printed(Interpreter::new("test", "", false)),
+        ["a b", "1 23", "[1, 2]"]
+    );
+    assert_eq!(
+        printed(Interpreter::new("test", "", false).with_print_separator(", ")),
+        ["a, b", "1, 23", "[1, 2]"]
+    );
+}
+
+#[test]
+fn reopened_scopes() {
+    let source = r#"scope base { let kind = "base"; }
+scope foo : base { let a = 1; let b = 1; }
+scope foo { let b = 2; fn sum[] return a + b; }
+let r = [foo.a, foo.b, foo.sum[], foo.kind];"#;
+
+    assert_value!(source, "r", "[1, 2, 3, base]");
+
+    // every call declares the scope anew
+    assert_value!(
+        "fn f[x] { scope s { let n = x; } return s.n; } let r = [f[1], f[2]];",
+        "r",
+        "[1, 2]"
+    );
+}
+
+#[test]
+fn try_propagation() {
+    let source = r#"let log = [];
+fn risky[fail] { if (fail) { throw "boom"; } return 1; }
+fn middle[fail] { let x = try risky[fail]; log.push["middle"]; return x + 1; }
+fn outer[fail] { let y = try middle[fail]; log.push["outer"]; return y * 10; }
+let ok = outer[false];
+let failed = outer[true];
+let five = 5;
+let r = [ok, failed.is_err[], failed.message, log, try five];"#;
+
+    // the failed call stops at the first `try` and skips the rest of both functions
+    assert_value!(source, "r", "[20, true, boom, [middle, outer], 5]");
+
+    let mut interpreter = Interpreter::new("test", "", false);
+    assert!(matches!(
+        interpreter.eval_expr(r#"try Error["no"] + 1"#),
+        Value::Err(_)
+    ));
+}
+
+#[test]
+fn declaration_modes() {
+    let source = "fn f[] { mut x = 5; mut x += 1; } f[]; let r = x;";
+    let undefined = |mode| {
+        Interpreter::new("test", source, false)
+            .with_declaration_mode(mode)
+            .check()
+            .into_iter()
+            .filter(|d| d.code == "undefined_variable")
+            .map(|d| &source[d.span])
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(undefined(DeclarationMode::Strict), ["mut x", "mut x", "x"]);
+    assert!(undefined(DeclarationMode::Lax).is_empty());
+
+    // the variable outlives the function that assigned it
+    let interpreter =
+        Interpreter::new("test", source, false).with_declaration_mode(DeclarationMode::Lax);
+    assert_eq!(
+        run_interpreter_and_get(interpreter, source, "r").to_string(),
+        "6"
+    );
+}
+
+#[test]
+fn lazy_ranges() {
+    assert_value!(
+        "let r = [1..5, (1..5).len[], 0..10..3, (0..10..3).map[fn [x] { return x; }], 5..1..-2, (5..1).len[]];",
+        "r",
+        "[1..5, 5, 0..10..3, [0, 3, 6, 9], 5..1..-2, 0]"
+    );
+
+    // the items are counted without being stored
+    assert_value!(
+        "let big = 0..100000; let r = [big.reduce[fn [acc, x] { return acc + x; }, 0], sizeof[big] == sizeof[0..1]];",
+        "r",
+        "[5000050000, true]"
+    );
+
+    assert_value!("let [a, b] = 3..4; let r = [a, b];", "r", "[3, 4]");
+
+    // a range is equal to the array of its items
+    assert_value!(
+        "let r = [(1..3) == [1, 2, 3], [0, 2] == (0..2..2), (1..3) == (1..4), (3..1) == (5..2)];",
+        "r",
+        "[true, true, false, true]"
+    );
+
+    // ranges can't be changed, `clone` gives an array that can
+    assert_value!(
+        "let a = 1..3; let e = a.push[4]; let c = clone[a]; c.push[4]; let r = [e.message, a.pop[].is_err[], c, freeze[a], a.len[]];",
+        "r",
+        "[Can't change a range, `clone` it into an array first, true, [1, 2, 3, 4], 1..3, 3]"
+    );
+}
+
+#[test]
+fn dbg_prints_source_and_value() {
+    let err = Rc::new(RefCell::new(Vec::<String>::new()));
+
+    let source = "let r = dbg[1+2];\nlet s = dbg[r] * 2;";
+
+    let err_lines = err.clone();
+    let interpreter =
+        Interpreter::new("test", source, false).with_error_output(Box::new(move |line| {
+            err_lines.borrow_mut().push(line.to_owned())
+        }));
+
+    assert_eq!(
+        run_interpreter_and_get(interpreter, source, "s").to_string(),
+        "6"
+    );
+    assert_eq!(*err.borrow(), ["[test:1] 1+2 = 3", "[test:2] r = 3"]);
+}
+
+#[test]
+fn configured_natives() {
+    let source = "let x = [1, 2]; print[x]; println[json.stringify[x], sizeof[x]];";
+    let printed = Rc::new(RefCell::new(Vec::<String>::new()));
+
+    let sink_printed = printed.clone();
+    let config = InterpreterConfig {
+        natives: Some(HashSet::from(["print".to_owned()])),
+        output: Some(Box::new(move |line| {
+            sink_printed.borrow_mut().push(line.to_owned())
+        })),
+        ..Default::default()
+    };
+    let interpreter = Interpreter::with_config("test", source, false, config);
+
+    let undefined = |interpreter: &Interpreter| -> Vec<String> {
+        interpreter
+            .check()
+            .into_iter()
+            .filter(|d| d.code == "undefined_variable")
+            .map(|d| source[d.span].to_owned())
+            .collect()
+    };
+
+    // all of them are registered by default
+    assert!(undefined(&Interpreter::new("test", source, false)).is_empty());
+    assert_eq!(
+        undefined(&interpreter),
+        ["println[json.stringify[x], sizeof[x]]", "json", "sizeof[x]"]
+    );
+
+    // methods of values don't depend on the registered globals
+    let source = "let x = [1, 2]; print[x.len[]];";
+    run_interpreter_and_get(interpreter, source, "x");
+    assert_eq!(*printed.borrow(), ["2"]);
+}
+
+#[test]
+fn modulo_keeps_sign_of_dividend() {
+    let source = "let r = [-7 % 3, 7 % -3, -7 % -3, 7.5 % 2, -7.5 % 2, -7i % 3i, 7i % -3i];";
+    let expected = "[-1, 1, -1, 1.5, -1.5, -1, 1]";
+
+    assert_value!(source, "r", expected);
+    assert_eq!(
+        run_with_mode_and_get(source, "r", NumberMode::Decimal).to_string(),
+        expected
+    );
+}
+
+#[test]
+fn string_ordering() {
+    // code points, not locale: uppercase letters sort before lowercase ones
+    assert_value!(
+        r#"let r = ["apple" < "banana", "apple" < "applesauce", "Zebra" < "apple", "b" >= "b", "é" > "z"];"#,
+        "r",
+        "[true, true, true, true, true]"
+    );
+}
+
+#[test]
+fn update_operators() {
+    assert_value!(
+        "let x = 1; let y = 1i; let r = [++x, x, --y, y];",
+        "r",
+        "[2, 2, 0, 0]"
+    );
+
+    let interpreter = Interpreter::new("test", "++5;", false);
+    assert_eq!(interpreter.check()[0].code, "invalid_update");
+}
+
+#[test]
+fn typed_accessors() {
+    assert_eq!(Value::Integer(3).as_number(), Ok(3.0));
+    assert_eq!(Value::Str("a".to_owned()).as_string(), Ok("a"));
+    assert_eq!(
+        Value::Number(1.0).as_bool(),
+        Err("Expected bool, got number".to_owned())
+    );
+    assert!(Value::Number(1.0).is_truthy());
+
+    let source = r#"let r = [half[5], half["ten"].message];"#;
+    let mut interpreter = Interpreter::new("test", source, false);
+    interpreter.register_fn(
+        "half",
+        Box::new(|args| Ok(Value::Number(args[0].as_number()? / 2.0))),
+    );
+
+    assert_eq!(
+        run_interpreter_and_get(interpreter, source, "r").to_string(),
+        "[2.5, Expected number, got str]"
+    );
+}
+
+#[test]
+fn scope_methods_see_self() {
+    let source = r#"scope counter { let count = 1; fn next[] { mut count += 1; return self.count; } fn get[] return this.count; }
+scope base { fn describe[] return self.name; }
+scope dog : base { let name = "dog"; }
+let r = [counter.next[], counter.get[], dog.describe[]];"#;
+
+    // inherited methods see the scope they are called on
+    assert_value!(source, "r", "[2, 2, dog]");
+}
+
+#[test]
+fn formulas_are_cached() {
+    let source = r#"let calls = 0;
+fn square[n] { mut calls += 1; return n * n; }
+let a = 2;
+let f := square[a] + 1;
+let g := f * 2;
+let before = [f.eval[], f.eval[], g.eval[], calls];
+mut a = 3;
+let after = [g.eval[], f.eval[], g.eval[], calls];
+let xs = [1];
+let n := xs.len[];
+let pushed = [n.eval[]];
+xs.push[2];
+pushed.push[n.eval[]];"#;
+
+    assert_value!(source, "before", "[5, 5, 10, 1]");
+    // one change of `a` computes `square[a]` once, for `g` and `f` together
+    assert_value!(source, "after", "[20, 10, 20, 2]");
+    assert_value!(source, "pushed", "[1, 2]");
+}
+
+#[test]
+fn formulas_stay_symbolic() {
+    let source = r#"let f := 20x^2 + 1;
+let text = f.to_string[];
+let missing = f.eval[].is_err[];
+let x = 2;
+let value = f.eval[];"#;
+
+    assert_value!(source, "text", "((20*(x^2))+1)");
+    assert_value!(source, "missing", "true");
+    assert_value!(source, "value", "81");
+}
+
+#[test]
+fn failed_try_writes_nothing() {
+    let source = r#"let x = 5; let y = 1; let log = [];
+fn risky[] { throw "boom"; }
+fn f[] { mut x = try risky[]; return 1; }
+fn g[] { mut y += try risky[]; return 1; }
+fn h[] { with try risky[] as r { log.push["body"]; } return 1; }
+f[]; g[]; h[];
+let r = [x, y, log];"#;
+
+    assert_value!(source, "r", "[5, 1, []]");
+}
@@ -0,0 +1,6 @@
+mod interpreter;
+
+pub use interpreter::{
+    DeclarationMode, FileSystemAccess, HostFunction, Interpreter, InterpreterConfig, NumberMode,
+    OutputSink, OverflowMode,
+};
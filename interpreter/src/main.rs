@@ -3,11 +3,11 @@ use std::fs::OpenOptions;
 
 // use symboscript_optimizer as optimizer;
 use symboscript_parser as parser;
+use symboscript_utils::{report_diagnostic, set_tab_width};
 
-mod interpreter;
 mod repl;
 
-use interpreter::Interpreter;
+use symboscript_interpreter::{DeclarationMode, FileSystemAccess, Interpreter, NumberMode};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -19,10 +19,31 @@ struct Args {
     /// TODO: implement
     #[clap(short, long)]
     debug: bool,
+
+    /// Use exact decimal arithmetic (0.1 + 0.2 == 0.3)
+    #[clap(long)]
+    decimal: bool,
+
+    /// Allow the script to read and write files
+    #[clap(long)]
+    allow_fs: bool,
+
+    /// Let `mut x = 1` declare a global when `x` is not declared
+    #[clap(long)]
+    implicit_globals: bool,
+
+    /// Don't report the lint with this code, e.g. `--allow shadowed_variable`
+    #[clap(long)]
+    allow: Vec<String>,
+
+    /// Columns per tab in reported positions
+    #[clap(long, default_value_t = 1)]
+    tab_width: usize,
 }
 
 fn main() {
     let args = Args::parse();
+    set_tab_width(args.tab_width);
 
     match args.path {
         Some(path) => {
@@ -30,11 +51,40 @@ fn main() {
             let text = &std::io::read_to_string(text).unwrap();
 
             let mut parser = parser::Parser::new(&path, text);
+            for code in &args.allow {
+                parser.allow_lint(code);
+            }
 
             let ast = parser.parse();
             // let ast = optimizer::optimize(&ast);
 
-            let mut interpreter = Interpreter::new(&path, text, false);
+            for diagnostic in parser.diagnostics().iter() {
+                report_diagnostic(&path, text, diagnostic);
+            }
+
+            if parser.diagnostics().has_errors() {
+                std::process::exit(1);
+            }
+
+            let number_mode = match args.decimal {
+                true => NumberMode::Decimal,
+                false => NumberMode::Float,
+            };
+
+            let fs_access = match args.allow_fs {
+                true => FileSystemAccess::Allowed,
+                false => FileSystemAccess::Denied,
+            };
+
+            let declaration_mode = match args.implicit_globals {
+                true => DeclarationMode::Lax,
+                false => DeclarationMode::Strict,
+            };
+
+            let mut interpreter = Interpreter::new(&path, text, false)
+                .with_number_mode(number_mode)
+                .with_fs_access(fs_access)
+                .with_declaration_mode(declaration_mode);
 
             interpreter.run(ast);
         }
@@ -1,5 +1,6 @@
 use crate::parser::Parser;
 use crate::Interpreter;
+use symboscript_types::interpreter::ControlFlow;
 
 use rustyline::error::ReadlineError;
 use rustyline::Result;
@@ -53,7 +54,9 @@ pub fn start() -> Result<()> {
 
                 interpreter.append_to_current_source(line);
 
-                interpreter.eval_ast(ast);
+                if let ControlFlow::Throw(thrown) = interpreter.eval_ast(&ast) {
+                    interpreter.print_uncaught(&thrown);
+                }
             }
 
             Err(ReadlineError::Interrupted) => {
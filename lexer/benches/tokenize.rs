@@ -0,0 +1,34 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use symboscript_lexer::Lexer;
+
+/// The basic examples repeated until the source is about 1 MB
+fn large_source() -> String {
+    let mut examples = String::new();
+
+    for entry in fs::read_dir("../examples/basic").unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().is_some_and(|ext| ext == "syms") {
+            examples.push_str(&fs::read_to_string(path).unwrap());
+            examples.push('\n');
+        }
+    }
+
+    examples.repeat(1_000_000 / examples.len() + 1)
+}
+
+fn tokenize(c: &mut Criterion) {
+    let source = large_source();
+
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("tokenize", |b| {
+        b.iter(|| Lexer::new("bench", &source, false).tokenize())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tokenize);
+criterion_main!(benches);
@@ -1,7 +1,115 @@
-use std::str::Chars;
-use symboscript_types::lexer::{Token, TokenKind, TokenValue};
+use phf::phf_map;
+use std::{ops::Range, str::Chars};
+use symboscript_types::{
+    diagnostic::Diagnostics,
+    lexer::{Token, TokenKind, TokenValue},
+};
 use symboscript_utils::report_error;
 
+mod template;
+
+pub use template::{template_parts, TemplatePart};
+
+/// Keywords and the tokens they lex to, the lexer and editor tooling share this table
+pub static KEYWORDS: phf::Map<&'static str, TokenKind> = phf_map! {
+    "true" => TokenKind::True,
+    "false" => TokenKind::False,
+    "None" => TokenKind::None,
+    "none" => TokenKind::None,
+    "if" => TokenKind::If,
+    "else" => TokenKind::Else,
+    "elif" => TokenKind::Elif,
+    "while" => TokenKind::While,
+    "loop" => TokenKind::Loop,
+    "repeat" => TokenKind::Repeat,
+    "for" => TokenKind::For,
+    "let" => TokenKind::Let,
+    "fn" => TokenKind::Function,
+    "scope" => TokenKind::Scope,
+    "return" => TokenKind::Return,
+    "yield" => TokenKind::Yield,
+    "break" => TokenKind::Break,
+    "continue" => TokenKind::Continue,
+    "in" => TokenKind::In,
+    "of" => TokenKind::Of,
+    "delete" => TokenKind::Delete,
+    "throw" => TokenKind::Throw,
+    "defer" => TokenKind::Defer,
+    "with" => TokenKind::With,
+    "import" => TokenKind::Import,
+    "as" => TokenKind::As,
+    "context" => TokenKind::Context,
+    "async" => TokenKind::Async,
+    "await" => TokenKind::Await,
+    "try" => TokenKind::Try,
+    "block" => TokenKind::Block,
+    "mut" => TokenKind::Mut,
+    // ---Keyword2Operator---
+    "band" => TokenKind::Ampersand,
+    "bxor" => TokenKind::BitXor,
+    "bor" => TokenKind::Pipe,
+    "bnot" => TokenKind::Tilde,
+    "bshl" => TokenKind::BitLeftShift,
+    "bshr" => TokenKind::BitRightShift,
+    "xor" => TokenKind::Xor,
+    "and" => TokenKind::AmpersandAmpersand,
+    "or" => TokenKind::PipePipe,
+    "not" => TokenKind::ExclamationMark,
+    "is" => TokenKind::Is,
+    //---Keyword2Operator---
+};
+
+pub fn is_keyword(ident: &str) -> bool {
+    KEYWORDS.contains_key(ident)
+}
+
+/// Length of the quotes around a lexed string, 3 for `"""..."""`
+fn quote_len(string: &str) -> usize {
+    let mut chars = string.chars();
+    let quote = chars.next();
+
+    match chars.take(2).all(|c| Some(c) == quote) && string.len() >= 6 {
+        true => 3,
+        false => 1,
+    }
+}
+
+/// Invalid escape sequence, offsets are relative to the string contents
+#[derive(Debug, PartialEq)]
+pub struct EscapeError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LexerOptions {
+    /// Lex comments
+    pub comment: bool,
+
+    /// Emit `Newline` tokens instead of skipping line breaks
+    pub newlines: bool,
+
+    /// Skip `\` at the end of a line together with the line break
+    pub line_continuations: bool,
+
+    /// Collect errors as diagnostics and keep lexing instead of ending the process.
+    /// Unexpected chars become `Unexpected` tokens, malformed numbers and strings have no value
+    pub recover: bool,
+
+    /// Match keywords regardless of case (`IF`, `While`) for BASIC-like dialects,
+    /// identifiers stay case-sensitive
+    pub ignore_keyword_case: bool,
+
+    /// Longest source in bytes, a longer source is an error and lexes as if it were empty.
+    /// Together with `max_tokens` it bounds the memory spent on untrusted sources,
+    /// set `recover` too so going over a limit is a diagnostic and doesn't end the process
+    pub max_source_len: Option<usize>,
+
+    /// Most tokens lexed, the token after the limit is an error and lexing ends there
+    pub max_tokens: Option<usize>,
+}
+
 pub struct Lexer<'a> {
     /// Path of the source file
     path: &'a str,
@@ -14,16 +122,84 @@ pub struct Lexer<'a> {
 
     /// Lex comments
     comment: bool,
+
+    /// Emit `Newline` tokens
+    newlines: bool,
+
+    /// Skip `\` line continuations
+    line_continuations: bool,
+
+    /// Keep lexing after errors
+    recover: bool,
+
+    /// Match keywords regardless of case
+    ignore_keyword_case: bool,
+
+    /// Limit of `lexed`
+    max_tokens: Option<usize>,
+
+    /// Number of tokens lexed so far
+    lexed: usize,
+
+    /// Errors found while recovering
+    diagnostics: Diagnostics,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(path: &'a str, source: &'a str, comment: bool) -> Self {
-        Self {
+        Self::with_options(
+            path,
+            source,
+            LexerOptions {
+                comment,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn with_options(path: &'a str, source: &'a str, options: LexerOptions) -> Self {
+        let mut lexer = Self {
             path,
             source,
             chars: source.chars(),
-            comment,
+            comment: options.comment,
+            newlines: options.newlines,
+            line_continuations: options.line_continuations,
+            recover: options.recover,
+            ignore_keyword_case: options.ignore_keyword_case,
+            max_tokens: options.max_tokens,
+            lexed: 0,
+            diagnostics: Diagnostics::new(),
+        };
+
+        if let Some(max) = options.max_source_len.filter(|max| source.len() > *max) {
+            lexer.error(
+                "source_too_large",
+                &format!("Source is {} bytes, the limit is {max}", source.len()),
+                max..source.len(),
+            );
+            lexer.seek(source.len());
+        }
+
+        lexer
+    }
+
+    /// Continues lexing at the byte `offset` of the source
+    pub fn seek(&mut self, offset: usize) {
+        self.chars = self.source[offset..].chars();
+    }
+
+    /// Errors found so far, only collected when lexing with `recover`
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    fn error(&mut self, code: &'static str, message: &str, span: Range<usize>) {
+        if !self.recover {
+            report_error(self.path, self.source, message, span.start, span.end);
         }
+
+        self.diagnostics.error(code, message.to_owned(), span);
     }
 
     pub fn tokenize(&mut self) -> Vec<Token> {
@@ -40,6 +216,62 @@ impl<'a> Lexer<'a> {
         tokens
     }
 
+    /// Re-tokenizes the source after an edit, reusing tokens of the previous version.
+    ///
+    /// The lexer holds the old source, `prev` are its tokens and `edit` is the byte range of it
+    /// that was replaced to give `new_source`. The lexer continues on `new_source`.
+    /// The lexer keeps no state between tokens besides its position, so lexing resumes
+    /// at the end of the last token that can't see the edit, before any trivia that the edit
+    /// could have changed, and stops as soon as
+    /// a new token lines up with an old one after the edit.
+    pub fn relex(&mut self, prev: &[Token], edit: Range<usize>, new_source: &'a str) -> Vec<Token> {
+        let inserted = new_source.len() + edit.len() - self.source.len();
+        let delta = inserted as isize - edit.len() as isize;
+        let edit_end = edit.start + inserted;
+        self.source = new_source;
+
+        // tokens peek at most two chars after their end
+        let reused = prev
+            .iter()
+            .take_while(|token| token.end + 2 <= edit.start)
+            .count();
+
+        let mut tokens = prev[..reused].to_vec();
+        let resume = reused.checked_sub(1).map_or(0, |last| prev[last].end);
+        self.chars = self.source[resume.min(self.source.len())..].chars();
+
+        // old tokens after the edit, moved to the new offsets
+        let mut old = prev[reused..]
+            .iter()
+            .filter(|token| token.start >= edit.end)
+            .map(|token| Token {
+                start: (token.start as isize + delta) as usize,
+                end: (token.end as isize + delta) as usize,
+                ..token.clone()
+            })
+            .peekable();
+
+        loop {
+            let token = self.next_token();
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+
+            if token.start >= edit_end {
+                while old.next_if(|old| old.start < token.start).is_some() {}
+
+                if old.peek() == Some(&token) {
+                    tokens.extend(old);
+                    break;
+                }
+            }
+
+            tokens.push(token);
+        }
+
+        tokens
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.skip_trivia();
         let start = self.offset();
@@ -50,14 +282,29 @@ impl<'a> Lexer<'a> {
             return self.next_token();
         }
 
+        if kind != TokenKind::Eof {
+            self.lexed += 1;
+
+            if let Some(max) = self.max_tokens.filter(|max| self.lexed > *max) {
+                self.error(
+                    "too_many_tokens",
+                    &format!("Source has more than {max} tokens"),
+                    start..end,
+                );
+                self.seek(self.source.len());
+                return self.next_token();
+            }
+        }
+
         let s = self.source[start..end].to_owned();
 
         let mut value = TokenValue::None;
 
         match kind {
-            TokenKind::Number => {
-                value = TokenValue::Number(s.parse::<f64>().unwrap_or_default());
-            }
+            TokenKind::Number => match parse_number(&s) {
+                Ok(number) => value = number,
+                Err(message) => self.error("malformed_number", &message, start..end),
+            },
 
             TokenKind::Identifier => {
                 kind = self.match_keyword(&s);
@@ -68,13 +315,36 @@ impl<'a> Lexer<'a> {
             }
 
             TokenKind::Str => {
-                value = TokenValue::Str(s[1..s.len() - 1].to_string().replace("\\n", "\n"));
+                let quotes = quote_len(&s);
+
+                match unescape(&s[quotes..s.len() - quotes]) {
+                    Ok(unescaped) => value = TokenValue::Str(unescaped),
+                    Err(err) => self.error(
+                        "invalid_escape",
+                        &err.message,
+                        start + quotes + err.start..start + quotes + err.end,
+                    ),
+                }
+            }
+
+            TokenKind::Template => {
+                let contents = &s[1..s.len() - 1];
+
+                match template_parts(contents) {
+                    Ok(_) => value = TokenValue::Str(contents.to_owned()),
+                    Err(err) => self.error(
+                        "invalid_escape",
+                        &err.message,
+                        start + 1 + err.start..start + 1 + err.end,
+                    ),
+                }
             }
 
             TokenKind::DocComment => value = TokenValue::Str(s),
 
             TokenKind::Unexpected => {
-                report_error(self.path, self.source, "Unexpected token", start, end)
+                self.error("unexpected_token", "Unexpected token", start..end);
+                value = TokenValue::Str(s);
             }
             _ => {}
         };
@@ -88,10 +358,11 @@ impl<'a> Lexer<'a> {
     }
 
     fn next_kind(&mut self) -> TokenKind {
-        while let Some(c) = self.next() {
+        if let Some(c) = self.next() {
             match c {
                 '#' => return self.read_comment(),
 
+                '\n' => return TokenKind::Newline,
                 ';' => return TokenKind::Semicolon,
                 ',' => return TokenKind::Comma,
                 ':' => return self.read_one_more('=', TokenKind::FormulaAssign, TokenKind::Colon),
@@ -111,39 +382,79 @@ impl<'a> Lexer<'a> {
                         &[TokenKind::MinusAssign, TokenKind::MinusMinus],
                     )
                 }
-                '*' => return self.read_one_more('=', TokenKind::MultiplyAssign, TokenKind::Star),
+                // `**` is an alias for `^`
+                '*' => {
+                    return match self.read_one_more_variants(
+                        TokenKind::Star,
+                        &['=', '*'],
+                        &[TokenKind::MultiplyAssign, TokenKind::Caret],
+                    ) {
+                        TokenKind::Caret => {
+                            self.read_one_more('=', TokenKind::PowerAssign, TokenKind::Caret)
+                        }
+                        kind => kind,
+                    }
+                }
                 '/' => return self.read_one_more('=', TokenKind::DivideAssign, TokenKind::Slash),
                 '^' => return self.read_one_more('=', TokenKind::PowerAssign, TokenKind::Caret),
                 '%' => return self.read_one_more('=', TokenKind::ModuloAssign, TokenKind::Modulo),
 
                 '&' => {
-                    return self.read_one_more(
-                        '&',
-                        TokenKind::AmpersandAmpersand,
+                    return self.read_one_more_variants(
                         TokenKind::Ampersand,
+                        &['&', '='],
+                        &[TokenKind::AmpersandAmpersand, TokenKind::BitAndAssign],
+                    )
+                }
+                '|' => {
+                    return self.read_one_more_variants(
+                        TokenKind::Pipe,
+                        &['|', '='],
+                        &[TokenKind::PipePipe, TokenKind::BitOrAssign],
                     )
                 }
-                '|' => return self.read_one_more('|', TokenKind::PipePipe, TokenKind::Pipe),
                 '~' => return TokenKind::Tilde,
-                '?' => return TokenKind::Question,
+                '@' => return TokenKind::At,
+                '?' => {
+                    if self.peek() == Some('?') && self.peek_n(1) == Some('=') {
+                        self.next();
+                        self.next();
+                        return TokenKind::DefaultAssign;
+                    }
+                    return TokenKind::Question;
+                }
 
                 '=' => return self.read_one_more('=', TokenKind::Equal, TokenKind::Assign),
                 '!' => {
                     return self.read_one_more('=', TokenKind::NotEqual, TokenKind::ExclamationMark)
                 }
                 '<' => {
-                    return self.read_one_more_variants(
+                    return match self.read_one_more_variants(
                         TokenKind::Less,
                         &['=', '<'],
                         &[TokenKind::LessEqual, TokenKind::BitLeftShift],
-                    )
+                    ) {
+                        TokenKind::BitLeftShift => self.read_one_more(
+                            '=',
+                            TokenKind::BitLeftShiftAssign,
+                            TokenKind::BitLeftShift,
+                        ),
+                        kind => kind,
+                    }
                 }
                 '>' => {
-                    return self.read_one_more_variants(
+                    return match self.read_one_more_variants(
                         TokenKind::Greater,
                         &['=', '>'],
                         &[TokenKind::GreaterEqual, TokenKind::BitRightShift],
-                    )
+                    ) {
+                        TokenKind::BitRightShift => self.read_one_more(
+                            '=',
+                            TokenKind::BitRightShiftAssign,
+                            TokenKind::BitRightShift,
+                        ),
+                        kind => kind,
+                    }
                 }
 
                 '(' => return TokenKind::LParen,
@@ -165,124 +476,102 @@ impl<'a> Lexer<'a> {
     }
 
     fn match_keyword(&self, ident: &str) -> TokenKind {
-        // all keywords are 1 <= length <= 10
+        // all keywords are 1 < length <= 10
         if ident.len() == 1 || ident.len() > 10 {
             return TokenKind::Identifier;
         }
 
-        match ident {
-            "true" => TokenKind::True,
-            "false" => TokenKind::False,
-            "None" => TokenKind::None,
-
-            "if" => TokenKind::If,
-            "else" => TokenKind::Else,
-            "while" => TokenKind::While,
-            "loop" => TokenKind::Loop,
-            "for" => TokenKind::For,
-            "let" => TokenKind::Let,
-            "fn" => TokenKind::Function,
-            "scope" => TokenKind::Scope,
-            "return" => TokenKind::Return,
-            "yield" => TokenKind::Yield,
-            "break" => TokenKind::Break,
-            "continue" => TokenKind::Continue,
-            "in" => TokenKind::In,
-            "of" => TokenKind::Of,
-            "delete" => TokenKind::Delete,
-
-            "throw" => TokenKind::Throw,
-
-            "import" => TokenKind::Import,
-            "as" => TokenKind::As,
-            "context" => TokenKind::Context,
-
-            "async" => TokenKind::Async,
-            "await" => TokenKind::Await,
-
-            "block" => TokenKind::Block,
-
-            "mut" => TokenKind::Mut,
-
-            // ---Keyword2Operator---
-            "band" => TokenKind::Ampersand,
-            "bxor" => TokenKind::BitXor,
-            "bor" => TokenKind::Pipe,
-            "bnot" => TokenKind::Tilde,
-            "bshl" => TokenKind::BitLeftShift,
-            "bshr" => TokenKind::BitRightShift,
-
-            "xor" => TokenKind::Xor,
-            "and" => TokenKind::AmpersandAmpersand,
-            "or" => TokenKind::PipePipe,
-            "not" => TokenKind::ExclamationMark,
-            //---Keyword2Operator---
-
-            //
-            _ => TokenKind::Identifier,
-        }
+        let keyword = if self.ignore_keyword_case {
+            KEYWORDS.get(ident.to_ascii_lowercase().as_str())
+        } else {
+            KEYWORDS.get(ident)
+        };
+
+        keyword.map_or(TokenKind::Identifier, |kind| *kind)
     }
 
     fn skip_trivia(&mut self) {
         while let Some(c) = self.peek() {
             match c {
+                '\n' if self.newlines => break,
                 ' ' | '\t' | '\n' | '\r' => {
                     self.next();
                 }
+                '\\' if self.line_continuations => match (self.peek_n(1), self.peek_n(2)) {
+                    (Some('\n'), _) => {
+                        self.next();
+                        self.next();
+                    }
+                    (Some('\r'), Some('\n')) => {
+                        self.next();
+                        self.next();
+                        self.next();
+                    }
+                    _ => break,
+                },
                 _ => break,
             }
         }
     }
 
     fn read_dot(&mut self) -> TokenKind {
-        if self.peek() == Some('.') {
-            self.next();
-            return TokenKind::Range;
-        } else if ("0"..="9").contains(&self.peek().unwrap_or_default().to_string().as_str()) {
-            return self.read_number();
+        match self.peek_n(0) {
+            Some('.') => {
+                self.next();
+                TokenKind::Range
+            }
+            // `a.5` is a member access, `a .5` and `.5` are numbers
+            Some('0'..='9') if !self.dot_follows_value() => self.read_number(),
+            _ => TokenKind::Dot,
         }
-        return TokenKind::Dot;
+    }
+
+    /// Whether the `.` just read directly follows an identifier, a number, a string or a `)`/`]`
+    fn dot_follows_value(&self) -> bool {
+        let dot = self.offset() - 1;
+
+        self.source[..dot]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | ')' | ']' | '"' | '\'' | '`'))
     }
 
     fn read_number(&mut self) -> TokenKind {
-        while let Some(c) = self.peek() {
-            match c {
-                '0'..='9' => {
+        while let Some(c) = self.peek_n(0) {
+            match (c, self.peek_n(1)) {
+                ('0'..='9', _) => {
                     self.next();
                 }
-                '.' | 'e' | 'E' => {
-                    if let Some(c) = self.peek_two() {
-                        match c {
-                            '0'..='9' => {
-                                self.next();
-                                self.next();
-                            }
-                            _ => {
-                                break;
-                            }
-                        }
-                    } else {
-                        break;
-                    }
+                ('.' | 'e' | 'E', Some('0'..='9')) => {
+                    self.next();
+                    self.next();
                 }
                 _ => break,
             };
         }
 
+        // `i`/`f` suffixes and a dangling exponent (`1e`) end the number, but `2ex` is still `2 * ex`
+        if let (Some('e' | 'E' | 'i' | 'f'), next) = (self.peek_n(0), self.peek_n(1)) {
+            if !next.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                self.next();
+            }
+        }
+
         TokenKind::Number
     }
 
     fn read_comment(&mut self) -> TokenKind {
-        if self.eat('/') {
+        // `## text` documents the declaration below it, the parser needs it even without comments
+        let doc_line = self.eat('#');
+
+        if !doc_line && self.eat('/') {
             while let Some(c) = self.peek() {
                 self.next();
-                if c == '/' {
-                    if self.eat('#') {
-                        if self.comment {
-                            return TokenKind::DocComment;
-                        } else {
-                            return TokenKind::Skip;
-                        }
+                if c == '/' && self.eat('#') {
+                    if self.comment {
+                        return TokenKind::DocComment;
+                    } else {
+                        return TokenKind::Skip;
                     }
                 }
             }
@@ -290,6 +579,8 @@ impl<'a> Lexer<'a> {
 
         while let Some(c) = self.peek() {
             match c {
+                // the line break is a separate token in newlines mode
+                '\n' if self.newlines => break,
                 '\n' => {
                     self.next();
                     break;
@@ -300,7 +591,9 @@ impl<'a> Lexer<'a> {
             };
         }
 
-        if self.comment {
+        if doc_line {
+            TokenKind::DocComment
+        } else if self.comment {
             TokenKind::Comment
         } else {
             TokenKind::Skip
@@ -308,11 +601,36 @@ impl<'a> Lexer<'a> {
     }
 
     fn read_string(&mut self, init_char: char) -> TokenKind {
+        if self.peek_n(0) == Some(init_char) && self.peek_n(1) == Some(init_char) {
+            self.next();
+            self.next();
+            return self.read_triple_string(init_char);
+        }
+
+        // backtick strings with `${...}` are templates
+        let mut template = false;
+
         while let Some(c) = self.peek() {
             match c {
                 c if c == init_char => {
                     self.next();
-                    return TokenKind::Str;
+                    return match template {
+                        true => TokenKind::Template,
+                        false => TokenKind::Str,
+                    };
+                }
+                '$' if init_char == '`' && self.peek_n(1) == Some('{') => {
+                    self.next();
+                    self.next();
+
+                    let rest = self.chars.as_str();
+                    let Some(len) = template::interpolation_len(rest) else {
+                        self.chars = rest[rest.len()..].chars();
+                        return TokenKind::Unexpected;
+                    };
+
+                    self.chars = rest[len + 1..].chars();
+                    template = true;
                 }
                 '\\' => {
                     self.next();
@@ -326,7 +644,32 @@ impl<'a> Lexer<'a> {
         TokenKind::Unexpected
     }
 
+    /// `"""..."""`, ends at the first three quotes in a row, so single quotes need no escaping
+    fn read_triple_string(&mut self, quote: char) -> TokenKind {
+        while let Some(c) = self.next() {
+            match c {
+                '\\' => {
+                    self.next();
+                }
+                c if c == quote
+                    && self.peek_n(0) == Some(quote)
+                    && self.peek_n(1) == Some(quote) =>
+                {
+                    self.next();
+                    self.next();
+                    return TokenKind::Str;
+                }
+                _ => {}
+            }
+        }
+        TokenKind::Unexpected
+    }
+
+    /// Identifier or keyword, keywords are matched later. `bxor=` is a compound assignment,
+    /// as `^=` is power assign
     fn read_identifier(&mut self) -> TokenKind {
+        let start = self.offset() - 1;
+
         while let Some(c) = self.peek() {
             match c {
                 'a'..='z' | 'A'..='Z' | '_' | '0'..='9' => {
@@ -336,6 +679,14 @@ impl<'a> Lexer<'a> {
             };
         }
 
+        if self.peek() == Some('=')
+            && self.peek_n(1) != Some('=')
+            && self.match_keyword(&self.source[start..self.offset()]) == TokenKind::BitXor
+        {
+            self.next();
+            return TokenKind::BitXorAssign;
+        }
+
         TokenKind::Identifier
     }
 
@@ -348,9 +699,9 @@ impl<'a> Lexer<'a> {
         match self.peek() {
             Some(c) if c == ch => {
                 self.next();
-                return kind_expected;
+                kind_expected
             }
-            _ => return kind_unexpected,
+            _ => kind_unexpected,
         }
     }
 
@@ -363,10 +714,10 @@ impl<'a> Lexer<'a> {
         match self.peek() {
             Some(c) if char_expected.contains(&c) => {
                 self.next();
-                return kind_expected[char_expected.iter().position(|&x| x == c).unwrap()];
+                kind_expected[char_expected.iter().position(|&x| x == c).unwrap()]
             }
 
-            _ => return kind_unexpected,
+            _ => kind_unexpected,
         }
     }
 
@@ -375,14 +726,14 @@ impl<'a> Lexer<'a> {
         self.source.len() - self.chars.as_str().len()
     }
 
-    fn peek(&self) -> Option<char> {
-        self.chars.as_str().chars().next()
+    /// Next char without consuming it
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.peek_n(0)
     }
 
-    fn peek_two(&self) -> Option<char> {
-        let mut new_chars = self.chars.as_str().chars();
-        new_chars.next();
-        new_chars.next()
+    /// Char `n` positions ahead (`0` is the next char), `None` past the end of source
+    pub(crate) fn peek_n(&self, n: usize) -> Option<char> {
+        self.chars.clone().nth(n)
     }
 
     fn eat(&mut self, ch: char) -> bool {
@@ -398,3 +749,124 @@ impl<'a> Lexer<'a> {
         self.chars.next()
     }
 }
+
+/// Value of a number token, `10i` is an integer and `10f` or `10` a float.
+/// `1.2.3`, `1e` and `1.5i` are malformed
+pub(crate) fn parse_number(raw: &str) -> Result<TokenValue, String> {
+    if let Some(digits) = raw.strip_suffix('i') {
+        if digits.contains(['.', 'e', 'E']) {
+            return Err(format!(
+                "Malformed integer `{raw}`, integers have no fraction or exponent"
+            ));
+        }
+
+        return digits
+            .parse::<i64>()
+            .map(TokenValue::Integer)
+            .map_err(|_| format!("Integer `{raw}` is too large"));
+    }
+
+    let digits = raw.strip_suffix('f').unwrap_or(raw);
+
+    if digits.matches('.').count() > 1 {
+        return Err(format!(
+            "Malformed number `{raw}`, more than one decimal point"
+        ));
+    }
+
+    if digits.ends_with(['e', 'E']) {
+        return Err(format!(
+            "Malformed number `{raw}`, the exponent has no digits"
+        ));
+    }
+
+    digits
+        .parse::<f64>()
+        .map(TokenValue::Number)
+        .map_err(|_| format!("Malformed number `{raw}`"))
+}
+
+/// Replaces escape sequences in the string contents (without quotes).
+///
+/// Supported: `\n`, `\r`, `\t`, `\0`, `\\`, quotes, `\xNN` (ASCII, up to `\x7F`)
+/// and `\u{N..}` (1 to 6 hex digits, any unicode scalar value).
+pub fn unescape(raw: &str) -> Result<String, EscapeError> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let error = |message: &str, end: usize| EscapeError {
+            message: message.to_owned(),
+            start,
+            end,
+        };
+
+        let escaped = match chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, '0')) => '\0',
+            Some((_, c @ ('\\' | '"' | '\'' | '`' | '$'))) => c,
+
+            Some((i, 'x')) => {
+                let digits = raw.get(i + 1..i + 3).unwrap_or_default();
+                let end = i + 1 + digits.len();
+
+                if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(error("Hex escape must have exactly two hex digits", end));
+                }
+
+                chars.nth(1);
+
+                match u8::from_str_radix(digits, 16).unwrap() {
+                    code @ 0..=0x7F => code as char,
+                    _ => return Err(error("Hex escape must be in range [\\x00-\\x7F]", end)),
+                }
+            }
+
+            Some((i, 'u')) => {
+                if chars.next_if(|(_, c)| *c == '{').is_none() {
+                    return Err(error("Unicode escape must look like \\u{...}", i + 1));
+                }
+
+                let mut digits = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((j, '}')) => break j + 1,
+                        Some((_, c)) if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                        Some((j, _)) => {
+                            return Err(error("Unicode escape must have 1 to 6 hex digits", j + 1))
+                        }
+                        None => return Err(error("Unterminated unicode escape", raw.len())),
+                    }
+                };
+
+                if digits.is_empty() {
+                    return Err(error("Unicode escape must have 1 to 6 hex digits", end));
+                }
+
+                match char::from_u32(u32::from_str_radix(&digits, 16).unwrap()) {
+                    Some(c) => c,
+                    None => return Err(error("Invalid unicode escape, not a scalar value", end)),
+                }
+            }
+
+            Some((i, c)) => {
+                return Err(error(
+                    &format!("Unknown escape sequence \\{c}"),
+                    i + c.len_utf8(),
+                ))
+            }
+            None => return Err(error("Unterminated escape sequence", raw.len())),
+        };
+
+        result.push(escaped);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,95 @@
+use std::ops::Range;
+
+use super::{unescape, EscapeError};
+
+/// Piece of a template string `` `a${x}b` ``
+#[derive(Debug, PartialEq)]
+pub enum TemplatePart {
+    /// Text with its escapes resolved
+    Text(String),
+
+    /// Byte range of an embedded expression, relative to the string contents
+    Expr(Range<usize>),
+}
+
+/// Byte length of the expression after `${`, up to its `}`.
+/// Braces of nested blocks and inside string literals are skipped, `None` when the `}` is missing
+pub fn interpolation_len(rest: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = rest.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            '"' | '\'' | '`' => loop {
+                match chars.next()?.1 {
+                    '\\' => {
+                        chars.next();
+                    }
+                    quote if quote == c => break,
+                    _ => {}
+                }
+            },
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Splits the contents of a template string into text and embedded expressions.
+/// `\$` is a `$` that doesn't start an expression
+pub fn template_parts(contents: &str) -> Result<Vec<TemplatePart>, EscapeError> {
+    let bytes = contents.as_bytes();
+    let mut parts = vec![];
+
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                push_text(&mut parts, contents, text_start..i)?;
+
+                let start = i + 2;
+                let len = interpolation_len(&contents[start..]).ok_or(EscapeError {
+                    message: "Unclosed `${` in template string".to_owned(),
+                    start: i,
+                    end: contents.len(),
+                })?;
+
+                parts.push(TemplatePart::Expr(start..start + len));
+
+                i = start + len + 1;
+                text_start = i;
+            }
+            _ => i += 1,
+        }
+    }
+
+    push_text(&mut parts, contents, text_start..contents.len())?;
+
+    Ok(parts)
+}
+
+fn push_text(
+    parts: &mut Vec<TemplatePart>,
+    contents: &str,
+    span: Range<usize>,
+) -> Result<(), EscapeError> {
+    if span.is_empty() {
+        return Ok(());
+    }
+
+    let text = unescape(&contents[span.clone()]).map_err(|err| EscapeError {
+        start: span.start + err.start,
+        end: span.start + err.end,
+        ..err
+    })?;
+
+    parts.push(TemplatePart::Text(text));
+    Ok(())
+}
@@ -1,3 +1,8 @@
 mod lexer;
 
-pub use lexer::Lexer;
+pub use lexer::{
+    is_keyword, template_parts, unescape, EscapeError, Lexer, LexerOptions, TemplatePart, KEYWORDS,
+};
+
+#[cfg(test)]
+mod tests;
@@ -1,8 +1,7 @@
 use clap::Parser;
 use std::fs::OpenOptions;
 
-mod lexer;
-
+use symboscript_lexer as lexer;
 use symboscript_utils as utils;
 
 #[derive(Parser, Debug)]
@@ -0,0 +1,476 @@
+pub mod escape_tests {
+    use crate::{unescape, EscapeError, Lexer};
+    use symboscript_types::lexer::TokenValue;
+
+    fn lex_str(source: &str) -> TokenValue {
+        Lexer::new("test", source, false).next_token().value
+    }
+
+    #[test]
+    fn simple_escapes() {
+        assert_eq!(unescape(r"a\nb\tc").unwrap(), "a\nb\tc");
+        assert_eq!(unescape(r#"\"\'\`\\"#).unwrap(), "\"'`\\");
+        assert_eq!(lex_str(r#""line\n""#), TokenValue::Str("line\n".to_owned()));
+    }
+
+    #[test]
+    fn triple_quoted_strings() {
+        let source = "let s = \"\"\"He said \"hi\",\n  'twice'\\t\"\"\";";
+        let token = Lexer::new("test", source, false).tokenize().remove(3);
+
+        assert_eq!(
+            token.value,
+            TokenValue::Str("He said \"hi\",\n  'twice'\t".to_owned())
+        );
+        assert_eq!(
+            &source[token.start..token.end],
+            &source[8..source.len() - 1]
+        );
+
+        assert_eq!(lex_str("\"\" + 1"), TokenValue::Str(String::new()));
+    }
+
+    #[test]
+    fn hex_escapes() {
+        assert_eq!(unescape(r"\x41\x62c").unwrap(), "Abc");
+        assert_eq!(lex_str(r#""\x41""#), TokenValue::Str("A".to_owned()));
+
+        assert!(unescape(r"\x4").is_err());
+        assert!(unescape(r"\xZZ").is_err());
+        assert!(unescape(r"\xFF").is_err());
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        assert_eq!(unescape(r"\u{41}").unwrap(), "A");
+        assert_eq!(unescape(r"\u{1F600}!").unwrap(), "😀!");
+        assert_eq!(lex_str(r#""\u{1F600}""#), TokenValue::Str("😀".to_owned()));
+
+        assert!(unescape(r"\u{}").is_err());
+        assert!(unescape(r"\u41").is_err());
+        assert!(unescape(r"\u{41").is_err());
+        assert!(unescape(r"\u{D800}").is_err());
+    }
+
+    #[test]
+    fn out_of_range_unicode_escape() {
+        assert_eq!(
+            unescape(r"ab\u{110000}"),
+            Err(EscapeError {
+                message: "Invalid unicode escape, not a scalar value".to_owned(),
+                start: 2,
+                end: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_escape() {
+        assert!(unescape(r"\q").is_err());
+    }
+}
+
+pub mod relex_tests {
+    use crate::Lexer;
+    use symboscript_types::lexer::Token;
+
+    /// Applies the edit and checks incremental tokens against a full tokenize,
+    /// with comments kept as tokens and skipped
+    fn assert_relex(source: &str, start: usize, end: usize, text: &str) {
+        let new_source = format!("{}{}{}", &source[..start], text, &source[end..]);
+
+        for comment in [true, false] {
+            let mut lexer = Lexer::new("test", source, comment);
+            let prev = lexer.tokenize();
+
+            let full: Vec<Token> = Lexer::new("test", &new_source, comment).tokenize();
+            let incremental = lexer.relex(&prev, start..end, &new_source);
+
+            assert_eq!(incremental, full, "{new_source}");
+        }
+    }
+
+    #[test]
+    fn relex_matches_full_tokenize() {
+        let source = "let a = 1 + 2;\nlet b = \"str\"; # comment\nprintln[a, b];\n";
+
+        assert_relex(source, 8, 9, "42");
+        assert_relex(source, 10, 11, "-");
+        assert_relex(source, 0, 0, "let x = 0; ");
+        assert_relex(source, source.len(), source.len(), "a.b;");
+        assert_relex(source, 4, 14, "");
+        assert_relex(source, 9, 9, ".5");
+        assert_relex(source, 10, 10, "=");
+    }
+
+    #[test]
+    fn relex_edit_changes_following_tokens() {
+        let source = "let a = 1; let b = 2; # \"\nlet c = 3;\n";
+
+        // the quote in the comment closes an opened string
+        assert_relex(source, 8, 8, "\"");
+        assert_relex(source, 8, 8, "# ");
+    }
+
+    #[test]
+    fn relex_edit_inside_comment() {
+        // the edited comment is skipped before the first token after it
+        assert_relex("a #/ xx /# b;", 6, 7, "yx");
+        assert_relex("a #/ xx /# b;", 8, 10, "");
+
+        let source = "let a = 1; # note\nlet b = 2;\n";
+        assert_relex(source, 14, 15, "#/");
+        assert_relex(source, 17, 18, " ");
+    }
+}
+
+pub mod peek_tests {
+    use crate::Lexer;
+
+    #[test]
+    fn peek_n() {
+        let mut lexer = Lexer::new("test", "ab€d", false);
+
+        assert_eq!(lexer.peek_n(0), Some('a'));
+        assert_eq!(lexer.peek_n(2), Some('€'));
+        assert_eq!(lexer.peek_n(3), Some('d'));
+        assert_eq!(lexer.peek_n(4), None);
+        assert_eq!(lexer.peek_n(100), None);
+
+        lexer.next_token();
+
+        assert_eq!(lexer.peek(), Some('€'));
+        assert_eq!(lexer.peek_n(1), Some('d'));
+    }
+
+    #[test]
+    fn peek_at_end() {
+        let lexer = Lexer::new("test", "", false);
+
+        assert_eq!(lexer.peek(), None);
+        assert_eq!(lexer.peek_n(1), None);
+    }
+
+    #[test]
+    fn numbers_and_dots() {
+        use symboscript_types::lexer::{TokenKind, TokenValue};
+
+        let kinds = |source| {
+            Lexer::new("test", source, false)
+                .tokenize()
+                .into_iter()
+                .map(|t| (t.kind, t.value))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds("1.5 .5 1..2 a.b 1e3"),
+            vec![
+                (TokenKind::Number, TokenValue::Number(1.5)),
+                (TokenKind::Number, TokenValue::Number(0.5)),
+                (TokenKind::Number, TokenValue::Number(1.0)),
+                (TokenKind::Range, TokenValue::None),
+                (TokenKind::Number, TokenValue::Number(2.0)),
+                (
+                    TokenKind::Identifier,
+                    TokenValue::Identifier("a".to_owned())
+                ),
+                (TokenKind::Dot, TokenValue::None),
+                (
+                    TokenKind::Identifier,
+                    TokenValue::Identifier("b".to_owned())
+                ),
+                (TokenKind::Number, TokenValue::Number(1000.0)),
+            ]
+        );
+    }
+}
+
+pub mod continuation_tests {
+    use crate::{Lexer, LexerOptions};
+    use symboscript_types::lexer::TokenKind;
+
+    fn kinds(source: &str, newlines: bool) -> Vec<TokenKind> {
+        let options = LexerOptions {
+            newlines,
+            line_continuations: true,
+            ..Default::default()
+        };
+
+        Lexer::with_options("test", source, options)
+            .tokenize()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn backslash_newline_is_whitespace() {
+        let joined = kinds("let a = 1 + 2;", true);
+
+        assert_eq!(kinds("let a = 1 + \\\n  2;", true), joined);
+        assert_eq!(kinds("let a = 1 \\\r\n+ 2;", true), joined);
+        assert_eq!(kinds("let a = 1 + \\\n  2;", false), joined);
+        assert!(kinds("a\nb", true).contains(&TokenKind::Newline));
+    }
+}
+
+pub mod keyword_tests {
+    use crate::{is_keyword, Lexer, LexerOptions, KEYWORDS};
+    use symboscript_types::lexer::{TokenKind, TokenValue};
+
+    #[test]
+    fn keyword_table_matches_lexer() {
+        for (keyword, kind) in KEYWORDS.entries() {
+            let tokens = Lexer::new("test", keyword, false).tokenize();
+
+            assert_eq!(tokens.len(), 1, "`{keyword}` is one token");
+            assert_eq!(tokens[0].kind, *kind, "`{keyword}` lexes to its table kind");
+            assert!(is_keyword(keyword));
+        }
+
+        assert!(!is_keyword("x"));
+        assert!(!is_keyword("while_"));
+        assert_eq!(
+            Lexer::new("test", "while_", false).tokenize()[0].kind,
+            TokenKind::Identifier
+        );
+    }
+
+    #[test]
+    fn ignore_keyword_case() {
+        let kinds = |source, ignore_keyword_case| {
+            let options = LexerOptions {
+                ignore_keyword_case,
+                ..Default::default()
+            };
+
+            Lexer::with_options("test", source, options)
+                .tokenize()
+                .iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            kinds("IF While NONE Count", true),
+            [
+                TokenKind::If,
+                TokenKind::While,
+                TokenKind::None,
+                TokenKind::Identifier
+            ]
+        );
+        assert_eq!(
+            kinds("IF While", false),
+            [TokenKind::Identifier, TokenKind::Identifier]
+        );
+
+        let tokens = Lexer::with_options(
+            "test",
+            "Count",
+            LexerOptions {
+                ignore_keyword_case: true,
+                ..Default::default()
+            },
+        )
+        .tokenize();
+        assert_eq!(tokens[0].value, TokenValue::Identifier("Count".to_owned()));
+    }
+}
+
+pub mod number_tests {
+    use crate::{lexer::parse_number, Lexer};
+    use symboscript_types::lexer::{TokenKind, TokenValue};
+
+    #[test]
+    fn malformed_numbers() {
+        assert_eq!(
+            parse_number("1.2.3"),
+            Err("Malformed number `1.2.3`, more than one decimal point".to_owned())
+        );
+        assert_eq!(
+            parse_number("1e"),
+            Err("Malformed number `1e`, the exponent has no digits".to_owned())
+        );
+        assert!(parse_number("1e5e3").is_err());
+
+        assert_eq!(parse_number("1.5e3"), Ok(TokenValue::Number(1500.0)));
+        assert_eq!(parse_number(".5"), Ok(TokenValue::Number(0.5)));
+    }
+
+    #[test]
+    fn number_suffixes() {
+        assert_eq!(parse_number("10i"), Ok(TokenValue::Integer(10)));
+        assert_eq!(parse_number("10f"), Ok(TokenValue::Number(10.0)));
+        assert_eq!(
+            parse_number("1.5i"),
+            Err("Malformed integer `1.5i`, integers have no fraction or exponent".to_owned())
+        );
+
+        let mut lexer = Lexer::new("test", "10i 2.5f 2ix", false);
+
+        assert_eq!(lexer.next_token().value, TokenValue::Integer(10));
+        assert_eq!(lexer.next_token().value, TokenValue::Number(2.5));
+        // a suffix followed by more identifier chars is implicit multiplication
+        assert_eq!(lexer.next_token().value, TokenValue::Number(2.0));
+        assert_eq!(
+            lexer.next_token().value,
+            TokenValue::Identifier("ix".to_owned())
+        );
+    }
+
+    #[test]
+    fn exponent_or_identifier() {
+        let mut lexer = Lexer::new("test", "2ex 2e_", false);
+
+        // `e` starting an identifier is implicit multiplication, not an exponent
+        assert_eq!(lexer.next_token().value, TokenValue::Number(2.0));
+        assert_eq!(lexer.next_token().kind, TokenKind::Identifier);
+        assert_eq!(lexer.next_token().value, TokenValue::Number(2.0));
+        assert_eq!(lexer.next_token().kind, TokenKind::Identifier);
+    }
+}
+
+pub mod dot_tests {
+    use crate::Lexer;
+    use symboscript_types::lexer::{TokenKind, TokenValue};
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Lexer::new("test", source, false)
+            .tokenize()
+            .iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn leading_dot_floats() {
+        let mut lexer = Lexer::new("test", ".5 .5e3 a .5", false);
+
+        assert_eq!(lexer.next_token().value, TokenValue::Number(0.5));
+        assert_eq!(lexer.next_token().value, TokenValue::Number(500.0));
+        assert_eq!(lexer.next_token().kind, TokenKind::Identifier);
+        assert_eq!(lexer.next_token().value, TokenValue::Number(0.5));
+    }
+
+    #[test]
+    fn member_access() {
+        use TokenKind::*;
+
+        assert_eq!(kinds("a.b"), [Identifier, Dot, Identifier]);
+        // a digit after a value is a member, not `a * 0.5`
+        assert_eq!(kinds("a.5"), [Identifier, Dot, Number]);
+        assert_eq!(kinds("f[].5"), [Identifier, LSquare, RSquare, Dot, Number]);
+        assert_eq!(kinds("1..5"), [Number, Range, Number]);
+    }
+}
+
+pub mod template_tests {
+    use crate::{template_parts, Lexer, TemplatePart};
+    use symboscript_types::lexer::{TokenKind, TokenValue};
+
+    #[test]
+    fn parts() {
+        let contents = r#"a${x}\${y}${ {f["}"]} }"#;
+
+        assert_eq!(
+            template_parts(contents).unwrap(),
+            [
+                TemplatePart::Text("a".to_owned()),
+                TemplatePart::Expr(3..4),
+                TemplatePart::Text("${y}".to_owned()),
+                TemplatePart::Expr(12..22),
+            ]
+        );
+        assert_eq!(&contents[12..22], r#" {f["}"]} "#);
+    }
+
+    #[test]
+    fn template_token() {
+        let mut lexer = Lexer::new("test", "`n=${n}` `plain`", false);
+
+        let template = lexer.next_token();
+        assert_eq!(template.kind, TokenKind::Template);
+        assert_eq!(template.value, TokenValue::Str("n=${n}".to_owned()));
+        assert_eq!(lexer.next_token().kind, TokenKind::Str);
+    }
+}
+
+pub mod recovery_tests {
+    use crate::{Lexer, LexerOptions};
+    use symboscript_types::lexer::{TokenKind, TokenValue};
+
+    fn recovering(source: &str) -> Lexer<'_> {
+        Lexer::with_options(
+            "test",
+            source,
+            LexerOptions {
+                recover: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn unexpected_chars() {
+        // `#` starts a comment and `@` a decorator, so the unexpected chars are `$`, `¤` and `§`
+        let mut lexer = recovering("$¤§ x");
+        let tokens = lexer.tokenize();
+
+        let unexpected: Vec<_> = tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Unexpected)
+            .map(|token| token.value.clone())
+            .collect();
+        assert_eq!(
+            unexpected,
+            ["$", "¤", "§"].map(|c| TokenValue::Str(c.to_owned()))
+        );
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Identifier);
+
+        let spans: Vec<_> = lexer.diagnostics().iter().map(|d| d.span.clone()).collect();
+        assert_eq!(spans, [0..1, 1..3, 3..5]);
+    }
+
+    #[test]
+    fn malformed_literals() {
+        let mut lexer = recovering(r#"1.2.3 "\q" 7"#);
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[2].value, TokenValue::Number(7.0));
+
+        let codes: Vec<_> = lexer.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["malformed_number", "invalid_escape"]);
+    }
+
+    #[test]
+    fn size_limits() {
+        let limited = |source, max_source_len, max_tokens| {
+            let options = LexerOptions {
+                recover: true,
+                max_source_len,
+                max_tokens,
+                ..Default::default()
+            };
+
+            let mut lexer = Lexer::with_options("test", source, options);
+            let tokens = lexer.tokenize();
+            let codes: Vec<_> = lexer.diagnostics().iter().map(|d| d.code).collect();
+            (tokens.len(), codes)
+        };
+
+        let source = "let x = 1;".repeat(1000);
+
+        assert_eq!(limited(&source, Some(10_000), Some(5000)), (5000, vec![]));
+        assert_eq!(
+            limited(&source, Some(9_999), None),
+            (0, vec!["source_too_large"])
+        );
+        assert_eq!(
+            limited(&source, None, Some(4999)),
+            (4999, vec!["too_many_tokens"])
+        );
+    }
+}
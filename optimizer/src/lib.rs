@@ -1,3 +1,6 @@
 mod optimizer;
 
 pub use optimizer::*;
+
+#[cfg(test)]
+mod tests;
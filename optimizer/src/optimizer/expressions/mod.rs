@@ -1,19 +1,29 @@
-use symboscript_types::parser::*;
+use symboscript_types::{lexer::TokenValue, parser::*};
 
 pub fn optim_expression(expression_stmt: &Expression) -> Expression {
     match expression_stmt {
-        Expression::BinaryExpression(binary_expression) => {
+        Expression::BinaryExpression(binary_expression)
+            if binary_expression.operator == BinaryOperator::Add =>
+        {
             optim_binary_expression(&sort_plus_binary_expression(binary_expression))
         }
+        Expression::BinaryExpression(binary_expression) => {
+            optim_binary_expression(binary_expression)
+        }
         _ => expression_stmt.clone(),
     }
 }
 
 pub fn optim_expression_sub(expression_stmt: &Expression) -> Expression {
     match expression_stmt {
-        Expression::BinaryExpression(binary_expression) => {
+        Expression::BinaryExpression(binary_expression)
+            if binary_expression.operator == BinaryOperator::Add =>
+        {
             optim_binary_expression(&sort_plus_binary_expression(binary_expression))
         }
+        Expression::BinaryExpression(binary_expression) => {
+            optim_binary_expression(binary_expression)
+        }
         _ => expression_stmt.clone(),
     }
 }
@@ -22,47 +32,160 @@ pub fn optim_binary_expression(binary_expression: &BinaryExpression) -> Expressi
     let left = optim_expression_sub(&binary_expression.left);
     let right = optim_expression_sub(&binary_expression.right);
 
-    match (left.clone(), right.clone()) {
-        (Expression::Literal(left), Expression::Literal(right)) => {
-            match binary_expression.operator {
-                BinaryOperator::Add => {
-                    return Expression::Literal(Literal {
-                        node: binary_expression.node.clone(),
-                        value: left.value + right.value,
-                    })
-                }
-                BinaryOperator::Substract => {
-                    return Expression::Literal(Literal {
-                        node: binary_expression.node.clone(),
-                        value: left.value - right.value,
-                    })
-                }
-                BinaryOperator::Multiply => {
-                    return Expression::Literal(Literal {
-                        node: binary_expression.node.clone(),
-                        value: left.value * right.value,
-                    })
-                }
-                BinaryOperator::Divide => {
-                    return Expression::Literal(Literal {
-                        node: binary_expression.node.clone(),
-                        value: left.value / right.value,
-                    })
-                }
-                _ => {}
+    if let Some(folded) = fold_logical(binary_expression, &left, &right) {
+        return folded;
+    }
+
+    if let (Expression::Literal(left), Expression::Literal(right)) = (left.clone(), right.clone()) {
+        let integer = |literal: &Literal| matches!(literal.value, TokenValue::Integer(_));
+        if integer(&left) || integer(&right) {
+            return Expression::BinaryExpression(Box::new(BinaryExpression {
+                left: Expression::Literal(left),
+                right: Expression::Literal(right),
+                operator: binary_expression.operator,
+                node: binary_expression.node,
+            }));
+        }
+
+        match binary_expression.operator {
+            BinaryOperator::Add => {
+                return Expression::Literal(Literal {
+                    node: binary_expression.node,
+                    value: left.value + right.value,
+                })
+            }
+            BinaryOperator::Substract => {
+                return Expression::Literal(Literal {
+                    node: binary_expression.node,
+                    value: left.value - right.value,
+                })
             }
+            BinaryOperator::Multiply => {
+                return Expression::Literal(Literal {
+                    node: binary_expression.node,
+                    value: left.value * right.value,
+                })
+            }
+            BinaryOperator::Divide => {
+                return Expression::Literal(Literal {
+                    node: binary_expression.node,
+                    value: left.value / right.value,
+                })
+            }
+            _ => {}
         }
-        _ => {}
     }
 
     Expression::BinaryExpression(Box::new(BinaryExpression {
         left,
         right,
-        operator: binary_expression.operator.clone(),
-        node: binary_expression.node.clone(),
+        operator: binary_expression.operator,
+        node: binary_expression.node,
     }))
 }
 
+/// `true || x` is `true`, `false && x` is `false`, `false || x` and `true && x` are `x`.
+///
+/// Both operands are always evaluated and non-bool operands make the result `false`,
+/// so the other operand must be a bool, and also pure to be dropped.
+fn fold_logical(
+    binary_expression: &BinaryExpression,
+    left: &Expression,
+    right: &Expression,
+) -> Option<Expression> {
+    let decisive = match binary_expression.operator {
+        BinaryOperator::Or => true,
+        BinaryOperator::And => false,
+        _ => return None,
+    };
+
+    let (constant, other) = match (bool_literal(left), bool_literal(right)) {
+        (Some(constant), _) => (constant, right),
+        (_, Some(constant)) => (constant, left),
+        _ => return None,
+    };
+
+    if !is_bool(other) {
+        return None;
+    }
+
+    if constant != decisive {
+        return Some(other.clone());
+    }
+
+    if !is_pure(other) {
+        return None;
+    }
+
+    Some(Expression::Literal(Literal {
+        node: binary_expression.node,
+        value: TokenValue::Bool(decisive),
+    }))
+}
+
+fn bool_literal(expression: &Expression) -> Option<bool> {
+    match expression {
+        Expression::Literal(Literal {
+            value: TokenValue::Bool(b),
+            ..
+        }) => Some(*b),
+        _ => None,
+    }
+}
+
+/// Expressions that always evaluate to a bool.
+/// Comparing values that aren't numbers gives an error, so `<` needs number literals
+fn is_bool(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(literal) => matches!(literal.value, TokenValue::Bool(_)),
+        Expression::BinaryExpression(binary_expression) => match binary_expression.operator {
+            BinaryOperator::And
+            | BinaryOperator::Or
+            | BinaryOperator::Xor
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Is => true,
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => {
+                is_number_literal(&binary_expression.left)
+                    && is_number_literal(&binary_expression.right)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_number_literal(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Literal(Literal {
+            value: TokenValue::Number(_) | TokenValue::Integer(_),
+            ..
+        })
+    )
+}
+
+/// Expressions that can be dropped without changing the program: literals and operators on them.
+/// Reading a variable that isn't declared ends the program, so identifiers are kept
+fn is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(literal) => !matches!(literal.value, TokenValue::Identifier(_)),
+        Expression::BinaryExpression(binary_expression) => {
+            is_pure(&binary_expression.left) && is_pure(&binary_expression.right)
+        }
+        Expression::UnaryExpression(unary_expression) => {
+            !matches!(
+                unary_expression.operator,
+                UnaryOperator::PlusPlus | UnaryOperator::MinusMinus
+            ) && is_pure(&unary_expression.right)
+        }
+        _ => false,
+    }
+}
+
 fn sort_plus_binary_expression(binary_expression: &BinaryExpression) -> BinaryExpression {
     let mut flat = flatten_plus_binary_expression(binary_expression);
 
@@ -84,8 +207,8 @@ fn sort_plus_binary_expression(binary_expression: &BinaryExpression) -> BinaryEx
     unflat_plus_binary_expression(&flat)
 }
 
-fn unflat_plus_binary_expression(flat: &Vec<Expression>) -> BinaryExpression {
-    let mut flat = flat.clone();
+fn unflat_plus_binary_expression(flat: &[Expression]) -> BinaryExpression {
+    let mut flat = flat.to_owned();
 
     flat.reverse();
 
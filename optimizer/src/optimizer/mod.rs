@@ -22,7 +22,7 @@ fn optim_body(body: &BlockStatement) -> BlockStatement {
         match statement {
             Statement::ExpressionStatement(expression) => {
                 new_body.push(Statement::ExpressionStatement(
-                    expressions::optim_expression(&expression),
+                    expressions::optim_expression(expression),
                 ));
             }
 
@@ -0,0 +1,34 @@
+use crate::optimize;
+use symboscript_parser::Parser;
+
+macro_rules! assert_optimized {
+    ($str: expr, $ast_str: expr) => {{
+        let ast = Parser::new("test", $str).parse();
+        assert_eq!(format!("{}", optimize(&ast)), format!("{};\n", $ast_str));
+    }};
+}
+
+#[test]
+fn logical_folding() {
+    // the constant decides the result
+    assert_optimized!("true || 2 == 1;", "true");
+    assert_optimized!("false && 1 < 2;", "false");
+    assert_optimized!("2 == 1 || true;", "true");
+    assert_optimized!("true || false;", "true");
+
+    // reading a variable isn't dropped
+    assert_optimized!("true || a == 1;", "(true||(a==1))");
+
+    // the constant doesn't decide, the other operand is kept
+    assert_optimized!("false || a == 1;", "(a==1)");
+    assert_optimized!("true && f[] == 1;", "((f[[]])==1)");
+
+    // calls and increments are not dropped
+    assert_optimized!("true || f[] == 1;", "(true||((f[[]])==1))");
+    assert_optimized!("false && ++a == 1;", "(false&&((++a)==1))");
+
+    // non-bool operands make `||` and `&&` false, so they can't be folded
+    assert_optimized!("true || a;", "(true||a)");
+    assert_optimized!("false || a;", "(false||a)");
+    assert_optimized!("false || (1 < \"a\");", "(false||(1<\"a\"))");
+}
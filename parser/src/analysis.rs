@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use symboscript_types::{diagnostic::Diagnostics, parser::*};
+
+use crate::visitor::{walk_ast, walk_expression, walk_statement, Visitor};
+
+/// Checks whose problems are errors, the program should not be run when one is found
+pub fn check(ast: &Ast, diagnostics: &mut Diagnostics) {
+    call_arity(ast, diagnostics);
+    misplaced_statements(ast, diagnostics);
+    invalid_updates(ast, diagnostics);
+}
+
+/// `++` and `--` of something that isn't a variable, like `++5` or `++f[]`
+fn invalid_updates(ast: &Ast, diagnostics: &mut Diagnostics) {
+    walk_ast(&mut Updates { diagnostics }, ast);
+}
+
+struct Updates<'a> {
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Visitor for Updates<'_> {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::UnaryExpression(expr) = expression {
+            if matches!(
+                expr.operator,
+                UnaryOperator::PlusPlus | UnaryOperator::MinusMinus
+            ) && !matches!(expr.right, Expression::Identifier(_))
+            {
+                let operand = expr.right.node();
+                self.diagnostics.error(
+                    "invalid_update",
+                    format!("`{}` can only update a variable", expr.operator),
+                    operand.start..operand.end,
+                );
+            }
+        }
+
+        walk_expression(self, expression);
+    }
+}
+
+/// `break` and `continue` outside of a loop body, a function body starts outside of any loop.
+/// `return` outside of a function body
+fn misplaced_statements(ast: &Ast, diagnostics: &mut Diagnostics) {
+    walk_ast(
+        &mut Placement {
+            loops: 0,
+            functions: 0,
+            diagnostics,
+        },
+        ast,
+    );
+}
+
+struct Placement<'a> {
+    /// Number of loop bodies around the current statement
+    loops: usize,
+
+    /// Number of function bodies around the current statement
+    functions: usize,
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Placement<'_> {
+    fn misplaced(&mut self, message: &str, node: Node) {
+        self.diagnostics.error(
+            "misplaced_statement",
+            message.to_owned(),
+            node.start..node.end,
+        );
+    }
+
+    fn loop_body(&mut self, body: &BlockStatement) {
+        self.loops += 1;
+        self.visit_block(body);
+        self.loops -= 1;
+    }
+
+    fn function_body(&mut self, body: &BlockStatement) {
+        let loops = std::mem::take(&mut self.loops);
+        self.functions += 1;
+        self.visit_block(body);
+        self.functions -= 1;
+        self.loops = loops;
+    }
+}
+
+impl Visitor for Placement<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::BreakStatement(_) | Statement::ContinueStatement(_) if self.loops == 0 => {
+                let message = match statement {
+                    Statement::BreakStatement(_) => "`break` outside of a loop",
+                    _ => "`continue` outside of a loop",
+                };
+                self.misplaced(message, statement.node().unwrap());
+            }
+            Statement::ReturnStatement(stmt) if self.functions == 0 => {
+                self.misplaced("`return` outside of a function", stmt.node);
+            }
+            Statement::FunctionDeclaration(decl) => return self.function_body(&decl.body),
+            // `else` blocks of loops run after the loop, so only the body counts
+            Statement::WhileStatement(stmt) => {
+                self.visit_expression(&stmt.test);
+                self.loop_body(&stmt.body);
+                self.visit_block(&stmt.alternate);
+                return;
+            }
+            Statement::ForStatement(stmt) => {
+                self.visit_statement(&stmt.init);
+                self.visit_expression(&stmt.test);
+                self.visit_expression(&stmt.update);
+                self.loop_body(&stmt.body);
+                self.visit_block(&stmt.alternate);
+                return;
+            }
+            Statement::LoopStatement(stmt) => return self.loop_body(&stmt.body),
+            Statement::RepeatStatement(stmt) => {
+                self.visit_expression(&stmt.count);
+                self.loop_body(&stmt.body);
+                return;
+            }
+            _ => {}
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::LoopExpression(expr) => self.loop_body(&expr.body),
+            Expression::FunctionExpression(expr) => self.function_body(&expr.body),
+            _ => walk_expression(self, expression),
+        }
+    }
+}
+
+/// Calls of top-level functions with a different number of arguments than declared.
+///
+/// Curried functions accept fewer arguments, decorated ones are replaced by their decorators,
+/// and names declared more than once anywhere
+/// (redeclared functions, variables or parameters with the same name) are skipped
+/// because calls can't be resolved without scopes.
+fn call_arity(ast: &Ast, diagnostics: &mut Diagnostics) {
+    let mut declarations = Declarations::default();
+    walk_ast(&mut declarations, ast);
+
+    let functions = ast
+        .program
+        .body
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::FunctionDeclaration(decl)
+                if !decl.curried
+                    && decl.decorators.is_empty()
+                    && declarations.count[&decl.id] == 1 =>
+            {
+                Some((decl.id.clone(), decl.params.len()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    walk_ast(
+        &mut Arity {
+            functions,
+            diagnostics,
+        },
+        ast,
+    );
+}
+
+#[derive(Default)]
+struct Declarations {
+    /// How many times each name is declared
+    count: HashMap<String, usize>,
+}
+
+impl Declarations {
+    fn declare(&mut self, name: &str) {
+        *self.count.entry(name.to_owned()).or_default() += 1;
+    }
+}
+
+impl Visitor for Declarations {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::FunctionDeclaration(decl) => {
+                self.declare(&decl.id);
+                for param in &decl.params {
+                    self.declare(param);
+                }
+            }
+            Statement::VariableDeclaration(decl) => match &decl.pattern {
+                Some(names) => names.iter().for_each(|name| self.declare(name)),
+                None => self.declare(&decl.id),
+            },
+            Statement::WhileStatement(stmt) => {
+                if let Some(name) = &stmt.binding {
+                    self.declare(name);
+                }
+            }
+            Statement::WithStatement(stmt) => self.declare(&stmt.name),
+            Statement::ScopeDeclaration(decl) => self.declare(&decl.id),
+            Statement::ContextDeclaration(decl) => self.declare(&decl.id),
+            Statement::AssignStatement(assign) => self.declare(&assign.left.name),
+            _ => {}
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::FunctionExpression(function) = expression {
+            for param in &function.params {
+                self.declare(param);
+            }
+        }
+
+        walk_expression(self, expression);
+    }
+}
+
+struct Arity<'a> {
+    /// Parameter count of each checked function
+    functions: HashMap<String, usize>,
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Visitor for Arity<'_> {
+    fn visit_expression(&mut self, expression: &Expression) {
+        if let Expression::CallExpression(call) = expression {
+            if let (Some(&expected), Expression::SequenceExpression(arguments)) =
+                (self.functions.get(&call.callee), &call.arguments)
+            {
+                let got = arguments.expressions.len();
+
+                if got != expected {
+                    self.diagnostics.error(
+                        "call_arity",
+                        format!(
+                            "Function `{}` takes {expected} arguments, got {got}",
+                            call.callee
+                        ),
+                        call.node.start..call.node.end,
+                    );
+                }
+            }
+        }
+
+        walk_expression(self, expression);
+    }
+}
@@ -1,6 +1,12 @@
+mod analysis;
+mod lints;
 mod parser;
+pub mod sexpr;
+pub mod visitor;
 
-pub use parser::Parser;
+pub use parser::{bin_op, Parser};
+pub use sexpr::SExpr;
+pub use visitor::Visitor;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,246 @@
+use std::collections::{HashMap, HashSet};
+
+use symboscript_types::{
+    diagnostic::{Diagnostic, Diagnostics, Note, Severity},
+    parser::*,
+};
+
+use crate::visitor::{walk_ast, walk_block, walk_expression, walk_statement, Visitor};
+
+/// Checks that don't stop parsing, problems are reported as warnings unless their code is allowed
+pub fn check(ast: &Ast, diagnostics: &mut Diagnostics, allowed: &[String]) {
+    let mut found = Diagnostics::new();
+
+    unused_variables(ast, &mut found);
+    unreachable_code(ast, &mut found);
+    shadowed_variables(ast, &mut found);
+    unused_expressions(ast, &mut found);
+
+    for diagnostic in found.iter() {
+        if !allowed.iter().any(|code| code == diagnostic.code) {
+            diagnostics.push(diagnostic.clone());
+        }
+    }
+}
+
+/// `let` in a block declaring a name that an enclosing block already declared
+fn shadowed_variables(ast: &Ast, diagnostics: &mut Diagnostics) {
+    walk_ast(
+        &mut Shadowing {
+            scopes: vec![],
+            diagnostics,
+        },
+        ast,
+    );
+}
+
+struct Shadowing<'a> {
+    /// Names declared in each enclosing block, innermost last
+    scopes: Vec<HashMap<String, Node>>,
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Shadowing<'_> {
+    fn declare(&mut self, name: &str, node: Node) {
+        let (current, outer) = self.scopes.split_last_mut().unwrap();
+
+        if let Some(shadowed) = outer.iter().rev().find_map(|scope| scope.get(name)) {
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "shadowed_variable",
+                message: format!("Variable `{name}` shadows a variable from an outer scope"),
+                span: node.start..node.end,
+                note: Some(Note {
+                    message: format!("`{name}` is declared here"),
+                    span: shadowed.start..shadowed.end,
+                }),
+            });
+        }
+
+        current.insert(name.to_owned(), node);
+    }
+}
+
+impl Visitor for Shadowing<'_> {
+    fn visit_block(&mut self, block: &BlockStatement) {
+        self.scopes.push(HashMap::new());
+        walk_block(self, block);
+        self.scopes.pop();
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+
+        if let Statement::VariableDeclaration(decl) = statement {
+            match &decl.pattern {
+                Some(names) => {
+                    for name in names {
+                        self.declare(name, decl.node);
+                    }
+                }
+                None => self.declare(&decl.id, decl.node),
+            }
+        }
+    }
+}
+
+/// Expression statements like `1 + 2;` that compute a value and throw it away
+fn unused_expressions(ast: &Ast, diagnostics: &mut Diagnostics) {
+    walk_ast(&mut UnusedExpressions { diagnostics }, ast);
+}
+
+struct UnusedExpressions<'a> {
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Visitor for UnusedExpressions<'_> {
+    fn visit_statement(&mut self, statement: &Statement) {
+        if let Statement::ExpressionStatement(expression) = statement {
+            if is_pure(expression) {
+                let node = expression.node();
+                self.diagnostics.warning(
+                    "unused_expression",
+                    "Value of the expression is never used".to_owned(),
+                    node.start..node.end,
+                );
+            }
+        }
+
+        walk_statement(self, statement);
+    }
+}
+
+/// Evaluating the expression changes nothing. Calls, `++`, `--`, word operators
+/// and expressions with a body may have side effects
+fn is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(_)
+        | Expression::Identifier(_)
+        | Expression::None(_)
+        | Expression::FunctionExpression(_) => true,
+        Expression::BinaryExpression(expr) => is_pure(&expr.left) && is_pure(&expr.right),
+        Expression::UnaryExpression(expr) => {
+            !matches!(
+                expr.operator,
+                UnaryOperator::PlusPlus | UnaryOperator::MinusMinus
+            ) && is_pure(&expr.right)
+        }
+        Expression::ConditionalExpression(expr) => {
+            is_pure(&expr.test) && is_pure(&expr.consequent) && is_pure(&expr.alternate)
+        }
+        Expression::SequenceExpression(expr) => expr.expressions.iter().all(is_pure),
+        Expression::MemberExpression(expr) => is_pure(&expr.object) && is_pure(&expr.property),
+        _ => false,
+    }
+}
+
+/// Statements after `return`, `break`, `continue` or `throw` in the same block,
+/// reported once per block at the first dead statement
+fn unreachable_code(ast: &Ast, diagnostics: &mut Diagnostics) {
+    walk_ast(&mut Unreachable { diagnostics }, ast);
+}
+
+struct Unreachable<'a> {
+    diagnostics: &'a mut Diagnostics,
+}
+
+impl Visitor for Unreachable<'_> {
+    fn visit_block(&mut self, block: &BlockStatement) {
+        let exit = block.iter().position(|statement| {
+            matches!(
+                statement,
+                Statement::ReturnStatement(_)
+                    | Statement::BreakStatement(_)
+                    | Statement::ContinueStatement(_)
+                    | Statement::ThrowStatement(_)
+            )
+        });
+
+        if let Some(exit) = exit {
+            let dead = block[exit + 1..]
+                .iter()
+                .find_map(|statement| statement.node());
+
+            if let Some(node) = dead {
+                let keyword = match block[exit] {
+                    Statement::ReturnStatement(_) => "return",
+                    Statement::BreakStatement(_) => "break",
+                    Statement::ContinueStatement(_) => "continue",
+                    _ => "throw",
+                };
+
+                self.diagnostics.warning(
+                    "unreachable_code",
+                    format!("Unreachable statement after `{keyword}`"),
+                    node.start..node.end,
+                );
+            }
+        }
+
+        walk_block(self, block);
+    }
+}
+
+/// `let` bindings whose name is never read anywhere in the program.
+///
+/// Names are not resolved through scopes, so a binding counts as used when any
+/// identifier, callee or member with the same name appears. Names starting with `_` are skipped.
+fn unused_variables(ast: &Ast, diagnostics: &mut Diagnostics) {
+    let mut collector = Bindings::default();
+    walk_ast(&mut collector, ast);
+
+    for (name, node) in collector.declared {
+        if !name.starts_with('_') && !collector.used.contains(&name) {
+            diagnostics.warning(
+                "unused_variable",
+                format!("Variable `{name}` is never used"),
+                node.start..node.end,
+            );
+        }
+    }
+}
+
+#[derive(Default)]
+struct Bindings {
+    declared: Vec<(String, Node)>,
+    used: HashSet<String>,
+}
+
+impl Visitor for Bindings {
+    fn visit_statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::WhileStatement(stmt) => {
+                if let Some(name) = &stmt.binding {
+                    self.declared.push((name.clone(), stmt.node));
+                }
+            }
+            Statement::VariableDeclaration(decl) => match &decl.pattern {
+                Some(names) => self
+                    .declared
+                    .extend(names.iter().map(|name| (name.clone(), decl.node))),
+                None => self.declared.push((decl.id.clone(), decl.node)),
+            },
+            // compound assignments read the variable
+            Statement::AssignStatement(assign) if assign.operator != AssignOperator::Assign => {
+                self.used.insert(assign.left.name.clone());
+            }
+            _ => {}
+        }
+
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(id) => {
+                self.used.insert(id.name.clone());
+            }
+            Expression::CallExpression(call) => {
+                self.used.insert(call.callee.clone());
+            }
+            _ => {}
+        }
+
+        walk_expression(self, expression);
+    }
+}
@@ -2,7 +2,8 @@ use clap::Parser;
 use serde_json::json;
 use std::fs::OpenOptions;
 
-mod parser;
+use symboscript_parser as parser;
+use symboscript_utils::report_diagnostic;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -25,6 +26,10 @@ fn main() {
 
     let ast = parser.parse();
 
+    for diagnostic in parser.diagnostics().iter() {
+        report_diagnostic(&args.path, text, diagnostic);
+    }
+
     println!("{}", ast);
 
     if args.debug {
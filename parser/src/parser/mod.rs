@@ -1,5 +1,6 @@
-use symboscript_lexer::Lexer;
+use symboscript_lexer::{template_parts, Lexer, LexerOptions, TemplatePart};
 use symboscript_types::{
+    diagnostic::Diagnostics,
     lexer::{Token, TokenKind, TokenValue},
     parser::*,
 };
@@ -21,24 +22,81 @@ pub struct Parser<'a> {
     cur_token: Token,
 
     prev_token_end: usize,
+
+    /// Newlines mode, a line break ends a statement where a semicolon is expected
+    newlines: bool,
+
+    /// There was a line break before the current token (newlines mode only)
+    newline_before: bool,
+
+    /// `##` comment lines right before the current token, taken by the declaration starting there
+    doc: Option<String>,
+
+    /// Errors and warnings found in the parsed program
+    diagnostics: Diagnostics,
+
+    /// Codes of lints that are not reported
+    allowed_lints: Vec<String>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(path: &'a str, source: &'a str) -> Self {
+        Self::with_lexer_options(path, source, LexerOptions::default())
+    }
+
+    pub fn with_lexer_options(path: &'a str, source: &'a str, options: LexerOptions) -> Self {
         Self {
             path,
             source,
-            lexer: Lexer::new(path, source, false),
+            lexer: Lexer::with_options(path, source, options),
             cur_token: Token::default(),
             prev_token_end: 0,
+            newlines: options.newlines,
+            newline_before: false,
+            doc: None,
+            diagnostics: Diagnostics::new(),
+            allowed_lints: vec![],
         }
     }
 
     pub fn parse(&mut self) -> Ast {
         self.eat(TokenKind::Start);
-        return Ast {
+        let ast = Ast {
             program: self.program(),
         };
+
+        self.add_lexer_diagnostics();
+        crate::analysis::check(&ast, &mut self.diagnostics);
+        crate::lints::check(&ast, &mut self.diagnostics, &self.allowed_lints);
+
+        ast
+    }
+
+    /// Parses a source that is a single expression, like `2 + 3 * 4`.
+    /// Tokens left after the expression are reported as an error, a trailing `;` too
+    pub fn parse_expression(&mut self) -> Expression {
+        self.eat(TokenKind::Start);
+        let expression = self.expr();
+        self.eat(TokenKind::Eof);
+
+        self.add_lexer_diagnostics();
+        expression
+    }
+
+    /// Errors the lexer recovered from, like a source over `max_source_len`
+    fn add_lexer_diagnostics(&mut self) {
+        for diagnostic in self.lexer.diagnostics().iter() {
+            self.diagnostics.push(diagnostic.clone());
+        }
+    }
+
+    /// Silences the lint with this code, e.g. `shadowed_variable`
+    pub fn allow_lint(&mut self, code: &str) {
+        self.allowed_lints.push(code.to_owned());
+    }
+
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
     }
 
     // -------------------- program ------------------------
@@ -71,9 +129,18 @@ impl<'a> Parser<'a> {
     // -------------------- statements ---------------------
 
     fn statement(&mut self) -> Statement {
-        match self.cur_kind() {
+        match self.keyword_statement() {
+            Some(statement) => statement,
+            None => self.expr_stmt(),
+        }
+    }
+
+    /// Statements that start with a keyword or `{`, `None` for expression statements
+    fn keyword_statement(&mut self) -> Option<Statement> {
+        let statement = match self.cur_kind() {
             TokenKind::Let => self.var_decl(false),
             TokenKind::Function | TokenKind::Async => self.fn_decl(),
+            TokenKind::At => self.decorated_fn_decl(),
             TokenKind::Scope => self.scope_decl(),
             TokenKind::Context => self.context_decl(),
 
@@ -82,11 +149,14 @@ impl<'a> Parser<'a> {
             TokenKind::For => self.for_stmt(),
             TokenKind::While => self.while_stmt(),
             TokenKind::Loop => self.loop_stmt(),
+            TokenKind::Repeat => self.repeat_stmt(),
 
             TokenKind::Continue => self.continue_stmt(),
             TokenKind::Break => self.break_stmt(),
 
             TokenKind::Throw => self.throw_stmt(),
+            TokenKind::Defer => self.defer_stmt(),
+            TokenKind::With => self.with_stmt(),
 
             TokenKind::Return => self.return_stmt(),
             TokenKind::Yield => self.yield_stmt(),
@@ -98,8 +168,10 @@ impl<'a> Parser<'a> {
 
             TokenKind::Mut => self.assign_statement(),
 
-            _ => self.expr_stmt(),
-        }
+            _ => return None,
+        };
+
+        Some(statement)
     }
 
     fn block_stmt(&mut self) -> Vec<Statement> {
@@ -117,7 +189,7 @@ impl<'a> Parser<'a> {
             body.push(self.statement());
         }
 
-        return body;
+        body
     }
 
     // --------------- import statement ----------------
@@ -171,14 +243,32 @@ impl<'a> Parser<'a> {
 
     fn scope_decl(&mut self) -> Statement {
         let start = self.cur_token.start;
+        let doc = self.doc.take();
         self.eat(TokenKind::Scope);
 
         let id = format!("{}", self.cur_token.clone().value);
         self.eat(TokenKind::Identifier);
 
+        let base = match self.cur_kind() {
+            TokenKind::Colon => {
+                self.advance();
+
+                let base = format!("{}", self.cur_token.clone().value);
+                self.eat(TokenKind::Identifier);
+
+                Some(base)
+            }
+            _ => None,
+        };
+
         let body = self.block_stmt();
 
-        Statement::ScopeDeclaration(uni_builder!(self, ScopeDeclarator, start, [id, body]))
+        Statement::ScopeDeclaration(uni_builder!(
+            self,
+            ScopeDeclarator,
+            start,
+            [id, base, body, doc]
+        ))
     }
 
     fn context_decl(&mut self) -> Statement {
@@ -196,30 +286,76 @@ impl<'a> Parser<'a> {
     // --------------- loop statement ------------------
 
     fn loop_stmt(&mut self) -> Statement {
+        Statement::LoopStatement(self.loop_body())
+    }
+
+    fn loop_body(&mut self) -> LoopStatement {
         let start = self.cur_token.start;
         self.eat(TokenKind::Loop);
         let body = self.block_stmt();
 
-        Statement::LoopStatement(uni_builder!(self, LoopStatement, start, [body]))
+        uni_builder!(self, LoopStatement, start, [body])
     }
 
-    // --------------- while statement ------------------
+    // --------------- repeat statement ------------------
 
-    fn while_stmt(&mut self) -> Statement {
+    fn repeat_stmt(&mut self) -> Statement {
         let start = self.cur_token.start;
-        self.eat(TokenKind::While);
+        self.eat(TokenKind::Repeat);
 
-        let test = {
+        let count = {
             let start = self.cur_token.start;
             self.eat(TokenKind::LParen);
-            let test = self.expr();
+            let count = self.expr();
             self.eat_with_start(TokenKind::RParen, start);
-            test
+            count
+        };
+
+        let body = self.block_stmt();
+
+        Statement::RepeatStatement(uni_builder!(self, RepeatStatement, start, [count, body]))
+    }
+
+    // --------------- while statement ------------------
+
+    fn while_stmt(&mut self) -> Statement {
+        let start = self.cur_token.start;
+        self.eat(TokenKind::While);
+
+        // while let x = expr { ... }
+        let binding = match self.at(TokenKind::Let) {
+            true => {
+                self.advance();
+
+                let name = format!("{}", self.cur_token.value);
+                self.eat(TokenKind::Identifier);
+                self.eat(TokenKind::Assign);
+
+                Some(name)
+            }
+            false => None,
+        };
+
+        let test = match binding {
+            Some(_) => self.expr(),
+            None => {
+                let start = self.cur_token.start;
+                self.eat(TokenKind::LParen);
+                let test = self.expr();
+                self.eat_with_start(TokenKind::RParen, start);
+                test
+            }
         };
 
         let body = self.block_stmt();
+        let alternate = self.loop_else();
 
-        Statement::WhileStatement(uni_builder!(self, WhileStatement, start, [test, body]))
+        Statement::WhileStatement(uni_builder!(
+            self,
+            WhileStatement,
+            start,
+            [test, body, binding, alternate]
+        ))
     }
 
     // --------------- for statement ------------------
@@ -247,21 +383,37 @@ impl<'a> Parser<'a> {
         };
 
         let body = self.block_stmt();
+        let alternate = self.loop_else();
 
         Statement::ForStatement(Box::new(uni_builder!(
             self,
             ForStatement,
             start,
-            [init, test, update, body]
+            [init, test, update, body, alternate]
         )))
     }
 
+    /// Optional `else` block of `while` and `for`
+    fn loop_else(&mut self) -> BlockStatement {
+        match self.cur_kind() {
+            TokenKind::Else => {
+                self.advance();
+                self.block_stmt()
+            }
+            _ => vec![],
+        }
+    }
+
     // --------------- if statement -------------------
 
     fn if_stmt(&mut self) -> Statement {
         let start = self.cur_token.start;
 
-        self.eat(TokenKind::If);
+        // `elif` starts the `if` of an `else if`
+        match self.cur_kind() {
+            TokenKind::Elif => self.advance(),
+            _ => self.eat(TokenKind::If),
+        }
 
         let test = {
             let start = self.cur_token.start;
@@ -275,9 +427,13 @@ impl<'a> Parser<'a> {
 
         let mut alternate = vec![];
 
-        if self.cur_kind() == TokenKind::Else {
-            self.advance();
-            alternate = self.block_stmt();
+        match self.cur_kind() {
+            TokenKind::Else => {
+                self.advance();
+                alternate = self.block_stmt();
+            }
+            TokenKind::Elif => alternate = vec![self.if_stmt()],
+            _ => {}
         }
 
         Statement::IfStatement(uni_builder!(
@@ -302,6 +458,36 @@ impl<'a> Parser<'a> {
         word_stmt!(self, TokenKind::Throw, ThrowStatement)
     }
 
+    fn defer_stmt(&mut self) -> Statement {
+        let start = self.cur_token.start;
+        self.eat(TokenKind::Defer);
+
+        let body = self.block_stmt();
+
+        Statement::DeferStatement(uni_builder!(self, DeferStatement, start, [body]))
+    }
+
+    /// with expr as identifier { ... }
+    fn with_stmt(&mut self) -> Statement {
+        let start = self.cur_token.start;
+        self.eat(TokenKind::With);
+
+        let object = self.expr();
+        self.eat(TokenKind::As);
+
+        let name = format!("{}", self.cur_token.value);
+        self.eat(TokenKind::Identifier);
+
+        let body = self.block_stmt();
+
+        Statement::WithStatement(uni_builder!(
+            self,
+            WithStatement,
+            start,
+            [object, name, body]
+        ))
+    }
+
     fn block_decl(&mut self) -> Statement {
         self.advance();
 
@@ -312,20 +498,35 @@ impl<'a> Parser<'a> {
         let start = self.cur_token.start;
         self.eat(TokenKind::Continue);
 
-        Statement::ContinueStatement(Node::new(start, self.cur_token.end))
+        Statement::ContinueStatement(Node::new(start, self.prev_token_end))
     }
 
+    /// break | break expr
     fn break_stmt(&mut self) -> Statement {
         let start = self.cur_token.start;
         self.eat(TokenKind::Break);
 
-        Statement::BreakStatement(Node::new(start, self.cur_token.end))
+        let argument = match self.cur_kind() {
+            TokenKind::Semicolon | TokenKind::RAngle | TokenKind::Eof => None,
+            _ if self.implicit_semicolon() => None,
+            _ => Some(self.expr()),
+        };
+
+        // the `;` is not part of the statement
+        let stmt = uni_builder!(self, BreakStatement, start, [argument]);
+
+        if self.at(TokenKind::Semicolon) {
+            self.advance();
+        }
+
+        Statement::BreakStatement(stmt)
     }
 
     // --------------- function declaration -----------------
 
     fn fn_decl(&mut self) -> Statement {
         let start = self.cur_token.start;
+        let doc = self.doc.take();
 
         let is_async = {
             if self.cur_kind() == TokenKind::Async {
@@ -341,9 +542,57 @@ impl<'a> Parser<'a> {
         let id = format!("{}", self.cur_token.clone().value);
         self.eat(TokenKind::Identifier);
 
-        let params = {
-            let start = self.cur_token.start;
+        Statement::FunctionDeclaration(self.fn_rest(start, id, is_async, doc))
+    }
+
+    /// (At dot)+ fn_decl
+    fn decorated_fn_decl(&mut self) -> Statement {
+        let start = self.cur_token.start;
+        let doc = self.doc.take();
+
+        let mut decorators = vec![];
+        while self.at(TokenKind::At) {
+            self.advance();
+            decorators.push(self.dot());
+        }
+
+        if !matches!(self.cur_kind(), TokenKind::Function | TokenKind::Async) {
+            self.report_expected(start, "fn", self.cur_kind());
+            unreachable!("Report ends proccess");
+        }
+
+        let Statement::FunctionDeclaration(mut decl) = self.fn_decl() else {
+            unreachable!("fn_decl always returns a function declaration")
+        };
+
+        decl.node.start = start;
+        decl.decorators = decorators;
+        if doc.is_some() {
+            decl.doc = doc;
+        }
+
+        Statement::FunctionDeclaration(decl)
+    }
 
+    /// Function LSquare params RSquare block_stmt, an anonymous function in value position
+    fn fn_expr(&mut self) -> Expression {
+        let start = self.cur_token.start;
+        self.eat(TokenKind::Function);
+
+        let function = self.fn_rest(start, String::new(), false, None);
+        Expression::FunctionExpression(Box::new(function))
+    }
+
+    /// Parameters and body of a function, after its name
+    fn fn_rest(
+        &mut self,
+        start: usize,
+        id: String,
+        is_async: bool,
+        doc: Option<String>,
+    ) -> FunctionDeclarator {
+        let params_start = self.cur_token.start;
+        let params = {
             self.eat(TokenKind::LSquare);
 
             let params = self
@@ -352,18 +601,25 @@ impl<'a> Parser<'a> {
                 .map(|p| format!("{}", p.value))
                 .collect();
 
-            self.eat_with_start(TokenKind::RSquare, start);
             params
         };
 
+        let curried = self.at(TokenKind::Range);
+        if curried {
+            self.advance();
+        }
+
+        self.eat_with_start(TokenKind::RSquare, params_start);
+
         let body = self.block_stmt();
+        let decorators = vec![];
 
-        Statement::FunctionDeclaration(uni_builder!(
+        uni_builder!(
             self,
             FunctionDeclarator,
             start,
-            [id, params, body, is_async]
-        ))
+            [id, params, body, is_async, curried, decorators, doc]
+        )
     }
 
     fn parse_params(&mut self) -> Vec<Token> {
@@ -379,6 +635,12 @@ impl<'a> Parser<'a> {
 
         while self.cur_kind() == TokenKind::Comma {
             self.advance();
+
+            // trailing `..` of a curried function, the caller handles it
+            if self.at(TokenKind::Range) {
+                break;
+            }
+
             params.push(self.cur_token.clone());
             self.eat(TokenKind::Identifier);
         }
@@ -390,10 +652,27 @@ impl<'a> Parser<'a> {
 
     fn var_decl(&mut self, only_with_init: bool) -> Statement {
         let start = self.cur_token.start;
+        let doc = self.doc.take();
         self.advance();
 
-        let id = format!("{}", self.cur_token.clone().value);
-        self.eat(TokenKind::Identifier);
+        // `let [a, b] = ...` destructures a sequence
+        let (id, pattern) = if self.at(TokenKind::LSquare) {
+            let start = self.cur_token.start;
+            self.advance();
+
+            let names = self
+                .parse_params()
+                .into_iter()
+                .map(|p| format!("{}", p.value))
+                .collect();
+
+            self.eat_with_start(TokenKind::RSquare, start);
+            (String::new(), Some(names))
+        } else {
+            let id = format!("{}", self.cur_token.clone().value);
+            self.eat(TokenKind::Identifier);
+            (id, None)
+        };
 
         let mut is_formula = false;
 
@@ -405,13 +684,17 @@ impl<'a> Parser<'a> {
                     self.advance();
                     self.expr()
                 }
-                TokenKind::FormulaAssign => {
+                TokenKind::FormulaAssign if pattern.is_none() => {
                     is_formula = true;
                     self.advance();
                     self.expr()
                 }
+                _ if pattern.is_some() => {
+                    self.report_expected(start, "Assign", self.cur_kind());
+                    unreachable!("Report ends proccess");
+                }
                 _ if !only_with_init => Expression::None(None {
-                    node: Node::new(start, self.cur_token.end),
+                    node: Node::new(start, self.prev_token_end),
                 }),
                 _ => {
                     self.report_expected(start, "Assign or FormulaAssign", self.cur_kind());
@@ -426,13 +709,14 @@ impl<'a> Parser<'a> {
             self,
             VariableDeclarator,
             start,
-            [id, init, is_formula]
+            [id, pattern, init, is_formula, doc]
         ))
     }
 
     // ---------------- assign statement -------------------
 
-    ///ternary (Assign | PlusAssign | MinusAssign | MultiplyAssign | DivideAssign | PowerAssign | ModuloAssign) ternary
+    ///ternary (Assign | PlusAssign | MinusAssign | MultiplyAssign | DivideAssign | PowerAssign | ModuloAssign
+    /// | BitLeftShiftAssign | BitRightShiftAssign | BitAndAssign | BitOrAssign | BitXorAssign | DefaultAssign) ternary
     fn assign_statement(&mut self) -> Statement {
         let start = self.cur_token.start;
 
@@ -442,7 +726,7 @@ impl<'a> Parser<'a> {
         self.eat(TokenKind::Identifier);
 
         let left = Identifier {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             name: format!("{}", left.value),
         };
 
@@ -454,6 +738,12 @@ impl<'a> Parser<'a> {
             TokenKind::DivideAssign,
             TokenKind::PowerAssign,
             TokenKind::ModuloAssign,
+            TokenKind::BitLeftShiftAssign,
+            TokenKind::BitRightShiftAssign,
+            TokenKind::BitAndAssign,
+            TokenKind::BitOrAssign,
+            TokenKind::BitXorAssign,
+            TokenKind::DefaultAssign,
         ]
         .contains(&self.cur_token.kind)
         {
@@ -472,7 +762,11 @@ impl<'a> Parser<'a> {
                 [left, right, operator]
             ))
         } else {
-            self.report_expected(start, "= | += | -= | *= | /= | ^= | %=", self.cur_kind());
+            self.report_expected(
+                start,
+                "= | += | -= | *= | /= | ^= | %= | <<= | >>= | &= | |= | bxor= | ??=",
+                self.cur_kind(),
+            );
             unreachable!("Report ends proccess");
         }
     }
@@ -509,14 +803,14 @@ impl<'a> Parser<'a> {
         self.sequence_expression(start, nodes)
     }
 
-    /// range ? range : range | range
+    /// binary ? binary : binary | binary
     fn ternary(&mut self) -> Expression {
         let start = self.cur_token.start;
-        let mut node = self.range();
+        let mut node = self.binary(0);
 
         while self.cur_kind() == TokenKind::Question {
             self.advance();
-            let consequent = self.range();
+            let consequent = self.binary(0);
             self.eat(TokenKind::Colon);
 
             let alternate = self.expr();
@@ -527,93 +821,48 @@ impl<'a> Parser<'a> {
         node
     }
 
-    /// logical_or .. logical_or | logical_or
-    fn range(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::Range], logical_or)
-    }
-
-    /// logical_and || logical_and
-    fn logical_or(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::PipePipe], logical_and)
-    }
-
-    /// cmp && cmp
-    fn logical_and(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::AmpersandAmpersand], cmp)
-    }
-
-    /// bit_or (< | <= | > | >= | == | !=) bit_or
-    fn cmp(&mut self) -> Expression {
-        binary_left_associative!(
-            self,
-            [
-                TokenKind::Less,
-                TokenKind::LessEqual,
-                TokenKind::Greater,
-                TokenKind::GreaterEqual,
-                TokenKind::Equal,
-                TokenKind::NotEqual,
-            ],
-            bit_or
-        )
-    }
-
-    ///bit_xor | bit_xor
-    fn bit_or(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::Pipe], bit_xor)
-    }
-
-    /// bit_and bxor bit_and
-    fn bit_xor(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::BitXor], bit_and)
-    }
-
-    /// shift & shift
-    fn bit_and(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::Ampersand], shift)
-    }
-
-    /// add_sub (>> | <<) add_sub
-    fn shift(&mut self) -> Expression {
-        binary_left_associative!(
-            self,
-            [TokenKind::BitRightShift, TokenKind::BitLeftShift],
-            add_sub
-        )
-    }
-
-    /// term (Plus | Minus) term
-    fn add_sub(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::Plus, TokenKind::Minus], term)
-    }
-
-    /// (power (Star | Slash | Modulo) power)* | (power power)*
-    fn term(&mut self) -> Expression {
+    /// factor (operator factor)*
+    ///
+    /// Precedence climbing over [`BinaryOperator::precedence`].
+    /// Operand tokens directly after an expression (`2x`, `2(a + b)`) are implicit multiplication,
+    /// only before any explicit operator that binds as loose as `*`: `2x * 3` but not `2 * 3x`.
+    fn binary(&mut self, min_bp: u8) -> Expression {
         let start = self.cur_token.start;
-        let mut expr = self.power();
+        let mut node = self.factor();
+        let mut explicit_term = false;
 
-        while [TokenKind::Identifier, TokenKind::LParen, TokenKind::Number]
-            .contains(&self.cur_kind())
-        {
-            let right = self.power();
-            expr = self.binary_expression(start, expr, right, TokenKind::Star);
-        }
+        loop {
+            let (operator, implicit) = match self.cur_kind() {
+                // a line break in newlines mode starts a new statement instead
+                TokenKind::Identifier | TokenKind::LParen | TokenKind::Number
+                    if !self.newline_before =>
+                {
+                    (TokenKind::Star, true)
+                }
+                kind => (kind, false),
+            };
 
-        while [TokenKind::Star, TokenKind::Slash, TokenKind::Modulo].contains(&self.cur_kind()) {
-            let operator = self.cur_kind();
-            self.advance();
+            let (bp, right_associative) = match bin_op(operator) {
+                Some(op) if op.precedence() >= min_bp => {
+                    (op.precedence(), op.is_right_associative())
+                }
+                _ => break,
+            };
 
-            let right = self.power();
+            if implicit && explicit_term {
+                break;
+            }
 
-            expr = self.binary_expression(start, expr, right, operator);
-        }
+            if !implicit {
+                self.advance();
+                explicit_term |= bp <= BinaryOperator::Multiply.precedence();
+            }
 
-        expr
-    }
+            let right = self.binary(if right_associative { bp } else { bp + 1 });
+            node = self.binary_expression(start, node, right, operator);
+        }
 
-    /// factor (Power) factor
-    fn power(&mut self) -> Expression {
-        binary_left_associative!(self, [TokenKind::Caret], factor)
+        node
     }
 
     /// Number | LParen expr Rparen | Identifier | (! | ++ | -- | ~)factor
@@ -623,36 +872,55 @@ impl<'a> Parser<'a> {
         match token.kind {
             TokenKind::Number | TokenKind::Str => {
                 self.advance();
-                return Expression::Literal(Literal {
+                let literal = Expression::Literal(Literal {
                     node: Node::new(token.start, token.end),
                     value: token.value,
                 });
+                self.members(token.start, literal)
+            }
+
+            TokenKind::Template => {
+                let template = self.template(token.clone());
+                self.members(token.start, template)
             }
 
             TokenKind::True => {
                 self.advance();
-                return Expression::Literal(Literal {
+                Expression::Literal(Literal {
                     node: Node::new(token.start, token.end),
                     value: TokenValue::Bool(true),
-                });
+                })
             }
 
             TokenKind::False => {
                 self.advance();
-                return Expression::Literal(Literal {
+                Expression::Literal(Literal {
                     node: Node::new(token.start, token.end),
                     value: TokenValue::Bool(false),
-                });
+                })
+            }
+
+            TokenKind::None => {
+                self.advance();
+                Expression::None(None {
+                    node: Node::new(token.start, token.end),
+                })
             }
 
             TokenKind::LParen => {
                 self.advance();
                 let node = self.expr();
                 self.eat_with_start(TokenKind::RParen, token.start);
-                return node;
+                self.members(token.start, node)
             }
 
-            TokenKind::LSquare => self.read_seq_expr(token),
+            TokenKind::LSquare => {
+                let sequence = self.read_seq_expr(token.clone());
+                self.members(token.start, sequence)
+            }
+            TokenKind::LAngle => self.block_expr(),
+            TokenKind::Loop => Expression::LoopExpression(Box::new(self.loop_body())),
+            TokenKind::Function => self.fn_expr(),
 
             TokenKind::ExclamationMark
             | TokenKind::PlusPlus
@@ -662,22 +930,124 @@ impl<'a> Parser<'a> {
             | TokenKind::Plus => {
                 self.advance();
 
+                let before_number = token.kind == TokenKind::Minus && self.at(TokenKind::Number);
                 let right = self.factor();
-                return self.unary_expression(token.start, token.kind, right);
+
+                if before_number {
+                    if let Some(value) = negated_number(&right) {
+                        return Expression::Literal(Literal {
+                            node: Node::new(token.start, self.prev_token_end),
+                            value,
+                        });
+                    }
+                }
+
+                self.unary_expression(token.start, token.kind, right)
             }
-            _ => return self.await_expr(),
+            _ => self.await_expr(),
         }
     }
 
-    fn read_seq_expr(&mut self, token: Token) -> Expression {
+    /// `` `a${x}b` `` is `"a" + x.to_string[] + "b"`, the expressions are parsed in place
+    fn template(&mut self, token: Token) -> Expression {
+        let node = Node::new(token.start, token.end);
+        let contents_start = token.start + 1;
+
+        let parts = match &token.value {
+            TokenValue::Str(contents) => template_parts(contents).unwrap_or_default(),
+            _ => vec![],
+        };
+
+        let mut template: Option<Expression> = None;
+        for part in parts {
+            let part = match part {
+                TemplatePart::Text(text) => Expression::Literal(Literal {
+                    node,
+                    value: TokenValue::Str(text),
+                }),
+                TemplatePart::Expr(span) => {
+                    self.lexer.seek(contents_start + span.start);
+                    self.advance();
+
+                    let start = self.cur_token.start;
+                    let expression = self.expr();
+
+                    if self.cur_token.start != contents_start + span.end {
+                        self.report_expected(self.cur_token.start, "}", self.cur_kind());
+                    }
+
+                    let end = self.prev_token_end;
+                    let arguments = self.sequence_expression(end, vec![]);
+                    let to_string =
+                        self.call_expression(end, "to_string".to_owned(), arguments, vec![]);
+                    self.member_expression(start, expression, to_string, false)
+                }
+            };
+
+            template = Some(match template {
+                None => part,
+                Some(left) => Expression::BinaryExpression(Box::new(BinaryExpression {
+                    node,
+                    left,
+                    operator: BinaryOperator::Add,
+                    right: part,
+                })),
+            });
+        }
+
+        self.lexer.seek(token.end);
+        self.cur_token = token;
         self.advance();
 
-        match self.cur_kind() {
-            TokenKind::RSquare => {
-                self.advance();
-                return self.sequence_expression(token.start, vec![]);
+        template.unwrap_or(Expression::Literal(Literal {
+            node,
+            value: TokenValue::Str(String::new()),
+        }))
+    }
+
+    /// LAngle statement* expr? RAngle
+    fn block_expr(&mut self) -> Expression {
+        let start = self.cur_token.start;
+        self.eat(TokenKind::LAngle);
+
+        let mut body = vec![];
+        let mut value = Expression::None(None {
+            node: Node::new(self.cur_token.start, self.cur_token.start),
+        });
+
+        while !self.at(TokenKind::RAngle) && !self.at(TokenKind::Eof) {
+            if let Some(statement) = self.keyword_statement() {
+                body.push(statement);
+                continue;
             }
-            _ => {}
+
+            let expression = self.expr();
+
+            if self.at(TokenKind::RAngle) {
+                value = expression;
+                break;
+            }
+
+            self.eat(TokenKind::Semicolon);
+            body.push(Statement::ExpressionStatement(expression));
+        }
+
+        self.eat_with_start(TokenKind::RAngle, start);
+
+        Expression::BlockExpression(Box::new(uni_builder!(
+            self,
+            BlockExpression,
+            start,
+            [body, value]
+        )))
+    }
+
+    fn read_seq_expr(&mut self, token: Token) -> Expression {
+        self.advance();
+
+        if self.cur_kind() == TokenKind::RSquare {
+            self.advance();
+            return self.sequence_expression(token.start, vec![]);
         }
 
         let mut node = self.comma(true);
@@ -690,12 +1060,17 @@ impl<'a> Parser<'a> {
             _ => node = self.sequence_expression(token.start, vec![node]),
         }
 
-        return node;
+        node
     }
 
-    /// await delete_expr | delete_expr
+    /// await try_expr | try_expr
     fn await_expr(&mut self) -> Expression {
-        word_right_associative_expr!(self, TokenKind::Await, delete_expr, await_expr)
+        word_right_associative_expr!(self, TokenKind::Await, try_expr, await_expr)
+    }
+
+    /// try delete_expr | delete_expr
+    fn try_expr(&mut self) -> Expression {
+        word_right_associative_expr!(self, TokenKind::Try, delete_expr, try_expr)
     }
 
     /// delete new_expr | new_expr
@@ -708,7 +1083,19 @@ impl<'a> Parser<'a> {
         member_left_associative!(self, [TokenKind::Dot], call)
     }
 
-    /// identifier[expr] | identifier
+    /// (.call)* after a literal or parenthesised expression: `"a".upper[]`, `(x + y).to_string[]`
+    fn members(&mut self, start: usize, mut object: Expression) -> Expression {
+        while self.at(TokenKind::Dot) {
+            self.advance();
+
+            let (property, is_expr) = self.call();
+            object = self.member_expression(start, object, property, is_expr);
+        }
+
+        object
+    }
+
+    /// identifier[expr]([expr])* | identifier
     fn call(&mut self) -> (Expression, bool) {
         let token = self.cur_token.clone();
 
@@ -719,52 +1106,38 @@ impl<'a> Parser<'a> {
                 match self.cur_kind() {
                     TokenKind::LSquare => {
                         let sequence_start = self.cur_token.start;
+                        let arguments = self.call_arguments(token.start);
 
-                        self.advance();
-
-                        match self.cur_kind() {
-                            TokenKind::RSquare => {
-                                self.advance();
-
-                                let node = self.sequence_expression(sequence_start, vec![]);
-
-                                return (
-                                    self.call_expression(
-                                        sequence_start,
-                                        format!("{}", token.value),
-                                        node,
-                                    ),
-                                    false,
-                                );
-                            }
-                            _ => {}
+                        // `f[1][2]` calls the result of `f[1]`, a line break ends the call in newlines mode
+                        let mut chained = vec![];
+                        while self.at(TokenKind::LSquare) && !self.newline_before {
+                            chained.push(self.call_arguments(self.cur_token.start));
                         }
 
-                        let mut node = self.expr();
-                        self.eat_with_start(TokenKind::RSquare, token.start);
-
-                        match node {
-                            Expression::SequenceExpression(seq_exp) => {
-                                node =
-                                    self.sequence_expression(sequence_start, seq_exp.expressions);
+                        let start = match &arguments {
+                            Expression::SequenceExpression(seq) if seq.expressions.is_empty() => {
+                                sequence_start
                             }
-                            _ => node = self.sequence_expression(sequence_start, vec![node]),
-                        }
-
-                        return (
-                            self.call_expression(token.start, format!("{}", token.value), node),
-                            false,
-                        );
-                    }
-                    _ => {
-                        return (
-                            Expression::Identifier(Identifier {
-                                node: Node::new(token.start, token.end),
-                                name: format!("{}", token.value),
-                            }),
+                            _ => token.start,
+                        };
+
+                        (
+                            self.call_expression(
+                                start,
+                                format!("{}", token.value),
+                                arguments,
+                                chained,
+                            ),
                             false,
-                        );
+                        )
                     }
+                    _ => (
+                        Expression::Identifier(Identifier {
+                            node: Node::new(token.start, token.end),
+                            name: format!("{}", token.value),
+                        }),
+                        false,
+                    ),
                 }
             }
             TokenKind::LSquare => {
@@ -773,7 +1146,7 @@ impl<'a> Parser<'a> {
                 let node = self.expr();
                 self.eat_with_start(TokenKind::RSquare, token.start);
 
-                return (node, true);
+                (node, true)
             }
             got => {
                 self.report_expected(token.start, "Identifier or [", got);
@@ -782,6 +1155,49 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// `[expr, ...]` of a call, the current token is `[`
+    fn call_arguments(&mut self, start: usize) -> Expression {
+        let sequence_start = self.cur_token.start;
+
+        self.advance();
+
+        if self.cur_kind() == TokenKind::RSquare {
+            self.advance();
+
+            return self.sequence_expression(sequence_start, vec![]);
+        }
+
+        let mut arguments = vec![self.argument()];
+        while self.cur_kind() == TokenKind::Comma {
+            self.advance();
+            arguments.push(self.argument());
+        }
+
+        self.eat_with_start(TokenKind::RSquare, start);
+
+        self.sequence_expression(sequence_start, arguments)
+    }
+
+    /// name = ternary | ternary
+    fn argument(&mut self) -> Expression {
+        let start = self.cur_token.start;
+        let node = self.ternary();
+
+        match node {
+            Expression::Identifier(id) if self.at(TokenKind::Assign) => {
+                self.advance();
+                let value = self.ternary();
+
+                Expression::NamedArgument(Box::new(NamedArgument {
+                    node: Node::new(start, self.prev_token_end),
+                    name: id.name,
+                    value,
+                }))
+            }
+            _ => node,
+        }
+    }
+
     // ------------------------------ Expression builders ------------------------------
 
     fn call_expression(
@@ -789,11 +1205,13 @@ impl<'a> Parser<'a> {
         start: usize,
         callee: String,
         arguments: Expression,
+        chained: Vec<Expression>,
     ) -> Expression {
         Expression::CallExpression(Box::new(CallExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             callee,
             arguments,
+            chained,
         }))
     }
 
@@ -805,7 +1223,7 @@ impl<'a> Parser<'a> {
         is_expr: bool,
     ) -> Expression {
         Expression::MemberExpression(Box::new(MemberExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             object,
             property,
             is_expr,
@@ -814,7 +1232,7 @@ impl<'a> Parser<'a> {
 
     fn sequence_expression(&mut self, start: usize, expressions: Vec<Expression>) -> Expression {
         Expression::SequenceExpression(Box::new(SequenceExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             expressions,
         }))
     }
@@ -827,7 +1245,7 @@ impl<'a> Parser<'a> {
         alternate: Expression,
     ) -> Expression {
         Expression::ConditionalExpression(Box::new(ConditionalExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             test,
             consequent,
             alternate,
@@ -842,7 +1260,7 @@ impl<'a> Parser<'a> {
         operator: TokenKind,
     ) -> Expression {
         Expression::BinaryExpression(Box::new(BinaryExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             left,
             operator: self.kind_to_bin_op(operator),
             right,
@@ -856,7 +1274,7 @@ impl<'a> Parser<'a> {
         right: Expression,
     ) -> Expression {
         Expression::UnaryExpression(Box::new(UnaryExpression {
-            node: Node::new(start, self.cur_token.end),
+            node: Node::new(start, self.prev_token_end),
             operator: self.kind_to_un_op(operator),
             right,
         }))
@@ -868,6 +1286,7 @@ impl<'a> Parser<'a> {
         match kind {
             TokenKind::Await => WordOperator::Await,
             TokenKind::Delete => WordOperator::Delete,
+            TokenKind::Try => WordOperator::Try,
 
             got => unreachable!("This function can't be called for other tokens: ({})", got),
         }
@@ -896,40 +1315,21 @@ impl<'a> Parser<'a> {
             TokenKind::DivideAssign => AssignOperator::DivideAssign,
             TokenKind::PowerAssign => AssignOperator::PowerAssign,
             TokenKind::ModuloAssign => AssignOperator::ModuloAssign,
+            TokenKind::BitLeftShiftAssign => AssignOperator::BitLeftShiftAssign,
+            TokenKind::BitRightShiftAssign => AssignOperator::BitRightShiftAssign,
+            TokenKind::BitAndAssign => AssignOperator::BitAndAssign,
+            TokenKind::BitOrAssign => AssignOperator::BitOrAssign,
+            TokenKind::BitXorAssign => AssignOperator::BitXorAssign,
+            TokenKind::DefaultAssign => AssignOperator::DefaultAssign,
 
             got => unreachable!("This function can't be called for other tokens: ({})", got),
         }
     }
 
     fn kind_to_bin_op(&mut self, kind: TokenKind) -> BinaryOperator {
-        match kind {
-            TokenKind::Plus => BinaryOperator::Add,
-            TokenKind::Minus => BinaryOperator::Substract,
-            TokenKind::Star => BinaryOperator::Multiply,
-            TokenKind::Slash => BinaryOperator::Divide,
-            TokenKind::Caret => BinaryOperator::Power,
-            TokenKind::Range => BinaryOperator::Range,
-            TokenKind::Modulo => BinaryOperator::Modulo,
-
-            TokenKind::AmpersandAmpersand => BinaryOperator::And,
-            TokenKind::PipePipe => BinaryOperator::Or,
-            TokenKind::Xor => BinaryOperator::Xor,
-
-            TokenKind::Ampersand => BinaryOperator::BitAnd,
-            TokenKind::Pipe => BinaryOperator::BitOr,
-            TokenKind::BitXor => BinaryOperator::BitXor,
-
-            TokenKind::BitLeftShift => BinaryOperator::BitLeftShift,
-            TokenKind::BitRightShift => BinaryOperator::BitRightShift,
-
-            TokenKind::Equal => BinaryOperator::Equal,
-            TokenKind::NotEqual => BinaryOperator::NotEqual,
-            TokenKind::Less => BinaryOperator::Less,
-            TokenKind::LessEqual => BinaryOperator::LessEqual,
-            TokenKind::Greater => BinaryOperator::Greater,
-            TokenKind::GreaterEqual => BinaryOperator::GreaterEqual,
-            _ => unreachable!("This function can't be called for other tokens: ({kind})"),
-        }
+        bin_op(kind).unwrap_or_else(|| {
+            unreachable!("This function can't be called for other tokens: ({kind})")
+        })
     }
 
     fn eat(&mut self, kind: TokenKind) {
@@ -942,6 +1342,10 @@ impl<'a> Parser<'a> {
             return true;
         }
 
+        if kind == TokenKind::Semicolon && self.implicit_semicolon() {
+            return true;
+        }
+
         let val = self.cur_token.value.to_string();
 
         self.report_expected(
@@ -978,10 +1382,51 @@ impl<'a> Parser<'a> {
     /// Move to the next token
     fn advance(&mut self) {
         self.prev_token_end = self.cur_token.end;
-        let token = self.lexer.next_token();
+        let mut token = self.lexer.next_token();
+
+        // comments are only lexed for tools like the formatter, the parser skips them.
+        // `##` lines are always lexed, they document the declaration after them
+        self.newline_before = false;
+        let mut doc: Vec<String> = vec![];
+        let mut doc_end = 0;
+        loop {
+            // a blank line or another comment between `##` lines and the token detaches them
+            if !doc.is_empty() && !self.directly_above(doc_end, token.start) {
+                doc.clear();
+            }
+
+            match (&token.kind, &token.value) {
+                (TokenKind::Newline, _) => self.newline_before = true,
+                (TokenKind::DocComment, TokenValue::Str(text)) if text.starts_with("##") => {
+                    doc.push(text[2..].trim().to_owned());
+                    doc_end = token.end;
+                }
+                (TokenKind::Comment | TokenKind::DocComment, _) => {}
+                _ => break,
+            }
+            token = self.lexer.next_token();
+        }
+
+        self.doc = (!doc.is_empty()).then(|| doc.join("\n"));
         self.cur_token = token;
     }
 
+    /// Only whitespace and a single line break are between the line ending at `end` and `start`.
+    /// Comments skipped by the lexer are in the source too, so they count
+    fn directly_above(&self, end: usize, start: usize) -> bool {
+        let gap = &self.source[end..start];
+        let line_breaks =
+            gap.matches('\n').count() + usize::from(self.source[..end].ends_with('\n'));
+
+        gap.trim().is_empty() && line_breaks <= 1
+    }
+
+    /// In newlines mode a statement also ends at a line break, `}` or the end of file
+    fn implicit_semicolon(&self) -> bool {
+        self.newlines
+            && (self.newline_before || self.at(TokenKind::RAngle) || self.at(TokenKind::Eof))
+    }
+
     fn cur_kind(&self) -> TokenKind {
         self.cur_token.kind
     }
@@ -991,3 +1436,53 @@ impl<'a> Parser<'a> {
         self.cur_kind() == kind
     }
 }
+
+/// `-5` is a negative literal rather than a negation, `-5.abs[]` and `- -5` still negate
+fn negated_number(expression: &Expression) -> Option<TokenValue> {
+    match expression {
+        Expression::Literal(Literal {
+            value: TokenValue::Number(n),
+            ..
+        }) => Some(TokenValue::Number(-n)),
+        Expression::Literal(Literal {
+            value: TokenValue::Integer(n),
+            ..
+        }) => n.checked_neg().map(TokenValue::Integer),
+        _ => None,
+    }
+}
+
+/// Binary operator of a token, `None` for tokens that don't continue a binary expression
+pub fn bin_op(kind: TokenKind) -> Option<BinaryOperator> {
+    let operator = match kind {
+        TokenKind::Plus => BinaryOperator::Add,
+        TokenKind::Minus => BinaryOperator::Substract,
+        TokenKind::Star => BinaryOperator::Multiply,
+        TokenKind::Slash => BinaryOperator::Divide,
+        TokenKind::Caret => BinaryOperator::Power,
+        TokenKind::Range => BinaryOperator::Range,
+        TokenKind::Modulo => BinaryOperator::Modulo,
+
+        TokenKind::AmpersandAmpersand => BinaryOperator::And,
+        TokenKind::PipePipe => BinaryOperator::Or,
+        TokenKind::Xor => BinaryOperator::Xor,
+
+        TokenKind::Ampersand => BinaryOperator::BitAnd,
+        TokenKind::Pipe => BinaryOperator::BitOr,
+        TokenKind::BitXor => BinaryOperator::BitXor,
+
+        TokenKind::BitLeftShift => BinaryOperator::BitLeftShift,
+        TokenKind::BitRightShift => BinaryOperator::BitRightShift,
+
+        TokenKind::Equal => BinaryOperator::Equal,
+        TokenKind::NotEqual => BinaryOperator::NotEqual,
+        TokenKind::Is => BinaryOperator::Is,
+        TokenKind::Less => BinaryOperator::Less,
+        TokenKind::LessEqual => BinaryOperator::LessEqual,
+        TokenKind::Greater => BinaryOperator::Greater,
+        TokenKind::GreaterEqual => BinaryOperator::GreaterEqual,
+        _ => return None,
+    };
+
+    Some(operator)
+}
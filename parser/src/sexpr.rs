@@ -0,0 +1,266 @@
+use std::fmt::{self, Display, Formatter};
+
+use symboscript_types::parser::*;
+
+/// Displays the ast as s-expressions, one top-level statement per line: `(let x (+ 1 2))`.
+///
+/// For debugging the tree itself, unlike the `Display` of [`Ast`] the output doesn't look like source
+pub struct SExpr<'a>(pub &'a Ast);
+
+impl Display for SExpr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for statement in &self.0.program.body {
+            statement_sexpr(f, statement)?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn block_sexpr(f: &mut Formatter<'_>, block: &BlockStatement) -> fmt::Result {
+    write!(f, "(block")?;
+    for statement in block {
+        write!(f, " ")?;
+        statement_sexpr(f, statement)?;
+    }
+    write!(f, ")")
+}
+
+/// ` (block ...)` for a non-empty `else`
+fn alternate_sexpr(f: &mut Formatter<'_>, alternate: &BlockStatement) -> fmt::Result {
+    if alternate.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, " ")?;
+    block_sexpr(f, alternate)
+}
+
+/// `(name argument)`
+fn word_sexpr(f: &mut Formatter<'_>, name: &str, argument: &Expression) -> fmt::Result {
+    write!(f, "({name} ")?;
+    expression_sexpr(f, argument)?;
+    write!(f, ")")
+}
+
+fn statement_sexpr(f: &mut Formatter<'_>, statement: &Statement) -> fmt::Result {
+    match statement {
+        Statement::ExpressionStatement(expr) => expression_sexpr(f, expr),
+        Statement::ReturnStatement(stmt) => word_sexpr(f, "return", &stmt.argument),
+        Statement::ThrowStatement(stmt) => word_sexpr(f, "throw", &stmt.argument),
+        Statement::YieldStatement(stmt) => word_sexpr(f, "yield", &stmt.argument),
+        Statement::DeferStatement(stmt) => {
+            write!(f, "(defer ")?;
+            block_sexpr(f, &stmt.body)?;
+            write!(f, ")")
+        }
+        Statement::WithStatement(stmt) => {
+            write!(f, "(with {} ", stmt.name)?;
+            expression_sexpr(f, &stmt.object)?;
+            write!(f, " ")?;
+            block_sexpr(f, &stmt.body)?;
+            write!(f, ")")
+        }
+        Statement::ContinueStatement(_) => write!(f, "(continue)"),
+        Statement::BreakStatement(stmt) => match &stmt.argument {
+            Some(argument) => word_sexpr(f, "break", argument),
+            None => write!(f, "(break)"),
+        },
+        Statement::VariableDeclaration(decl) => {
+            let keyword = if decl.is_formula { "formula" } else { "let" };
+
+            match &decl.pattern {
+                Some(names) => write!(f, "({keyword} [{}] ", names.join(" "))?,
+                None => write!(f, "({keyword} {} ", decl.id)?,
+            }
+            expression_sexpr(f, &decl.init)?;
+            write!(f, ")")
+        }
+        Statement::FunctionDeclaration(decl) => {
+            let keyword = if decl.is_async { "async-fn" } else { "fn" };
+            let curried = if decl.curried { " .." } else { "" };
+
+            // `@a @b fn f` is `(@ a (@ b (fn f ...)))`
+            for decorator in &decl.decorators {
+                write!(f, "(@ ")?;
+                expression_sexpr(f, decorator)?;
+                write!(f, " ")?;
+            }
+
+            write!(
+                f,
+                "({keyword} {} ({}{curried}) ",
+                decl.id,
+                decl.params.join(" ")
+            )?;
+            block_sexpr(f, &decl.body)?;
+            write!(f, "{}", ")".repeat(decl.decorators.len() + 1))
+        }
+        Statement::ScopeDeclaration(decl) => {
+            write!(f, "(scope {} ", decl.id)?;
+            if let Some(base) = &decl.base {
+                write!(f, "(: {base}) ")?;
+            }
+            block_sexpr(f, &decl.body)?;
+            write!(f, ")")
+        }
+        Statement::ContextDeclaration(decl) => {
+            write!(f, "(context {} ", decl.id)?;
+            block_sexpr(f, &decl.body)?;
+            write!(f, ")")
+        }
+        Statement::IfStatement(stmt) => {
+            write!(f, "(if ")?;
+            expression_sexpr(f, &stmt.test)?;
+            write!(f, " ")?;
+            block_sexpr(f, &stmt.consequent)?;
+            alternate_sexpr(f, &stmt.alternate)?;
+            write!(f, ")")
+        }
+        Statement::ForStatement(stmt) => {
+            write!(f, "(for ")?;
+            statement_sexpr(f, &stmt.init)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &stmt.test)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &stmt.update)?;
+            write!(f, " ")?;
+            block_sexpr(f, &stmt.body)?;
+            alternate_sexpr(f, &stmt.alternate)?;
+            write!(f, ")")
+        }
+        Statement::WhileStatement(stmt) => {
+            match &stmt.binding {
+                Some(name) => write!(f, "(while-let {name} ")?,
+                None => write!(f, "(while ")?,
+            }
+            expression_sexpr(f, &stmt.test)?;
+            write!(f, " ")?;
+            block_sexpr(f, &stmt.body)?;
+            alternate_sexpr(f, &stmt.alternate)?;
+            write!(f, ")")
+        }
+        Statement::LoopStatement(stmt) => {
+            write!(f, "(loop ")?;
+            block_sexpr(f, &stmt.body)?;
+            write!(f, ")")
+        }
+        Statement::RepeatStatement(stmt) => {
+            write!(f, "(repeat ")?;
+            expression_sexpr(f, &stmt.count)?;
+            write!(f, " ")?;
+            block_sexpr(f, &stmt.body)?;
+            write!(f, ")")
+        }
+        Statement::BlockStatement(block) => block_sexpr(f, block),
+        Statement::AssignStatement(stmt) => {
+            write!(f, "({} {} ", stmt.operator, stmt.left.name)?;
+            expression_sexpr(f, &stmt.right)?;
+            write!(f, ")")
+        }
+        Statement::ImportStatement(stmt) => {
+            write!(f, "(import {} {})", stmt.source.name, stmt.as_name.name)
+        }
+    }
+}
+
+/// The expressions of an argument list or sequence, each after a space
+fn items_sexpr(f: &mut Formatter<'_>, expression: &Expression) -> fmt::Result {
+    match expression {
+        Expression::SequenceExpression(seq) => {
+            for item in &seq.expressions {
+                write!(f, " ")?;
+                expression_sexpr(f, item)?;
+            }
+            Ok(())
+        }
+        _ => {
+            write!(f, " ")?;
+            expression_sexpr(f, expression)
+        }
+    }
+}
+
+fn expression_sexpr(f: &mut Formatter<'_>, expression: &Expression) -> fmt::Result {
+    match expression {
+        Expression::BinaryExpression(expr) => {
+            write!(f, "({} ", expr.operator)?;
+            expression_sexpr(f, &expr.left)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &expr.right)?;
+            write!(f, ")")
+        }
+        Expression::UnaryExpression(expr) => {
+            write!(f, "({} ", expr.operator)?;
+            expression_sexpr(f, &expr.right)?;
+            write!(f, ")")
+        }
+        Expression::ConditionalExpression(expr) => {
+            write!(f, "(? ")?;
+            expression_sexpr(f, &expr.test)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &expr.consequent)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &expr.alternate)?;
+            write!(f, ")")
+        }
+        Expression::CallExpression(expr) => {
+            // `f[1][2]` is `(call (call f 1) 2)`
+            for _ in &expr.chained {
+                write!(f, "(call ")?;
+            }
+            write!(f, "(call {}", expr.callee)?;
+            items_sexpr(f, &expr.arguments)?;
+            write!(f, ")")?;
+
+            for arguments in &expr.chained {
+                items_sexpr(f, arguments)?;
+                write!(f, ")")?;
+            }
+            Ok(())
+        }
+        Expression::MemberExpression(expr) => {
+            write!(f, "(. ")?;
+            expression_sexpr(f, &expr.object)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &expr.property)?;
+            write!(f, ")")
+        }
+        Expression::SequenceExpression(_) => {
+            write!(f, "(seq")?;
+            items_sexpr(f, expression)?;
+            write!(f, ")")
+        }
+        Expression::WordExpression(expr) => {
+            word_sexpr(f, &expr.operator.to_string(), &expr.argument)
+        }
+        Expression::BlockExpression(expr) => {
+            write!(f, "(block-expr ")?;
+            block_sexpr(f, &expr.body)?;
+            write!(f, " ")?;
+            expression_sexpr(f, &expr.value)?;
+            write!(f, ")")
+        }
+        Expression::LoopExpression(expr) => {
+            write!(f, "(loop ")?;
+            block_sexpr(f, &expr.body)?;
+            write!(f, ")")
+        }
+        Expression::FunctionExpression(expr) => {
+            let curried = if expr.curried { " .." } else { "" };
+
+            write!(f, "(fn ({}{curried}) ", expr.params.join(" "))?;
+            block_sexpr(f, &expr.body)?;
+            write!(f, ")")
+        }
+        Expression::NamedArgument(arg) => {
+            write!(f, "(= {} ", arg.name)?;
+            expression_sexpr(f, &arg.value)?;
+            write!(f, ")")
+        }
+        Expression::Literal(literal) => write!(f, "{}", literal.value),
+        Expression::Identifier(id) => write!(f, "{}", id.name),
+        Expression::None(_) => write!(f, "none"),
+    }
+}
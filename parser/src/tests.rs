@@ -32,6 +32,27 @@ pub mod expr_tests {
         assert_parser!("1>2;", "(1>2)");
         assert_parser!("1<=2;", "(1<=2)");
         assert_parser!("1>=2;", "(1>=2)");
+        assert_parser!("a is b==c;", "((a is b)==c)");
+    }
+
+    #[test]
+    fn expression_only() {
+        use symboscript_types::parser::{BinaryOperator, Expression};
+
+        let expression = Parser::new("test", "1 + 2 * 3").parse_expression();
+
+        let Expression::BinaryExpression(add) = &expression else {
+            panic!("expected a binary expression, got {expression}");
+        };
+        assert_eq!(add.operator, BinaryOperator::Add);
+        assert_eq!(add.left.to_string(), "1");
+
+        let Expression::BinaryExpression(multiply) = &add.right else {
+            panic!("expected a binary expression, got {}", add.right);
+        };
+        assert_eq!(multiply.operator, BinaryOperator::Multiply);
+        assert_eq!(multiply.to_string(), "2*3");
+        assert_eq!((add.node.start, add.node.end), (0, 9));
     }
 
     #[test]
@@ -43,6 +64,26 @@ pub mod expr_tests {
         assert_parser!("--1;", "(--1)");
     }
 
+    #[test]
+    fn minus_and_decrement() {
+        // `--` is one token (pre-decrement), separated minuses are negations
+        assert_parser!("a - -b;", "(a-(-b))");
+        assert_parser!("a - - b;", "(a-(-b))");
+        assert_parser!("a+-b;", "(a+(-b))");
+        assert_parser!("--x;", "(--x)");
+        assert_parser!("- -x;", "(-(-x))");
+        assert_parser!("- - 5;", "(-(-5))");
+        assert_parser!("-(-5);", "(-(-5))");
+        assert_parser!("a - --b;", "(a-(--b))");
+    }
+
+    #[test]
+    fn loop_expression() {
+        assert_parser!("let x = loop { break 5; };", "let x = loop {\nbreak 5;\n}");
+        assert_parser!("let x = loop { break };", "let x = loop {\nbreak;\n}");
+        assert_parser!("let x = loop { break; };", "let x = loop {\nbreak;\n}");
+    }
+
     #[test]
     fn ternary_op() {
         assert_parser!("a ? b : c;", "(a ? b : c)");
@@ -54,4 +95,865 @@ pub mod expr_tests {
 
         assert_parser!("(a ? b : c) ? d : e;", "((a ? b : c) ? d : e)");
     }
+
+    #[test]
+    fn precedence() {
+        assert_parser!("1+2*3;", "(1+(2*3))");
+        assert_parser!("1*2+3;", "((1*2)+3)");
+        assert_parser!("1-2-3;", "((1-2)-3)");
+        assert_parser!("a-b+c-d;", "(((a-b)+c)-d)");
+        assert_parser!("2^3^2;", "((2^3)^2)");
+        assert_parser!("-2^2;", "((-2)^2)");
+        assert_parser!("1+2^3*4;", "(1+((2^3)*4))");
+        assert_parser!("a*b%c/d;", "(((a*b)%c)/d)");
+        assert_parser!("1<<2+3;", "(1<<(2+3))");
+        assert_parser!("a&b bxor c|d;", "(((a&b) bxor c)|d)");
+        assert_parser!("a|b&c;", "(a|(b&c))");
+        assert_parser!("a==b&&c!=d||e;", "(((a==b)&&(c!=d))||e)");
+        assert_parser!("a&&b||c&&d;", "((a&&b)||(c&&d))");
+        assert_parser!("a<b==c>=d;", "(((a<b)==c)>=d)");
+        assert_parser!("1..2+3;", "(1..(2+3))");
+        assert_parser!("!a+b;", "((!a)+b)");
+        assert_parser!("(1+2)*3;", "((1+2)*3)");
+        assert_parser!("x.y+1;", "((x.y)+1)");
+        assert_parser!("a ? b+1 : c*2;", "(a ? (b+1) : (c*2))");
+    }
+
+    #[test]
+    fn operator_precedence() {
+        use symboscript_types::parser::BinaryOperator;
+
+        assert!(BinaryOperator::Power.precedence() > BinaryOperator::Multiply.precedence());
+        assert!(BinaryOperator::Multiply.precedence() > BinaryOperator::Add.precedence());
+        assert_eq!(
+            BinaryOperator::Modulo.precedence(),
+            BinaryOperator::Divide.precedence()
+        );
+        assert!(!BinaryOperator::Power.is_right_associative());
+    }
+
+    #[test]
+    fn operator_display() {
+        use symboscript_types::parser::BinaryOperator;
+
+        assert_eq!(BinaryOperator::Power.to_string(), "^");
+        assert_eq!(BinaryOperator::Xor.to_string(), "xor");
+        assert_eq!(BinaryOperator::BitXor.to_string(), "bxor");
+        assert_eq!(BinaryOperator::Range.to_string(), "..");
+    }
+
+    #[test]
+    fn round_trip() {
+        for source in [
+            "a+b-c*d/e%f^g;",
+            "a..b;",
+            "a&&b||c xor d;",
+            "a&b|c bxor d<<e>>f;",
+            "a==b!=c<d<=e>f>=g;",
+            "-a+!b*~c;",
+        ] {
+            let printed = format!("{}", Parser::new("test", source).parse());
+            let reparsed = format!("{}", Parser::new("test", &printed).parse());
+
+            assert_eq!(printed, reparsed, "{source}");
+        }
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        assert_parser!("2x;", "(2*x)");
+        assert_parser!("2x^2;", "(2*(x^2))");
+        assert_parser!("2(a+b);", "(2*(a+b))");
+        assert_parser!("2 3 4;", "((2*3)*4)");
+        assert_parser!("2x*3;", "((2*x)*3)");
+        assert_parser!("a^b c;", "((a^b)*c)");
+        assert_parser!("1+2x;", "(1+(2*x))");
+        assert_parser!("2^3x;", "((2^3)*x)");
+    }
+
+    #[test]
+    fn no_implicit_multiplication_after_explicit_term() {
+        // syntax errors end the process, so each source is parsed by a child test process
+        if let Ok(source) = std::env::var("SYMBOSCRIPT_PARSE_SOURCE") {
+            Parser::new("test", &source).parse();
+            return;
+        }
+
+        for source in ["let r = 2*3x;", "let r = 1+2*3x;", "let r = 2x*3y;"] {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "tests::expr_tests::no_implicit_multiplication_after_explicit_term",
+                    "--nocapture",
+                ])
+                .env("SYMBOSCRIPT_PARSE_SOURCE", source)
+                .output()
+                .unwrap();
+
+            assert!(!output.status.success(), "{source}");
+            assert!(
+                String::from_utf8_lossy(&output.stdout).contains("Expected ; but got Identifier"),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn call_arguments() {
+        assert_parser!("f[1, 2];", "(f[[1, 2]])");
+        assert_parser!("f[[1, 2]];", "(f[[[1, 2]]])");
+        assert_parser!("f[];", "(f[[]])");
+    }
+}
+
+pub mod newline_tests {
+    use crate::parser::Parser;
+    use symboscript_lexer::LexerOptions;
+
+    fn parse_newlines(source: &str) -> String {
+        let options = LexerOptions {
+            newlines: true,
+            ..Default::default()
+        };
+
+        format!(
+            "{}",
+            Parser::with_lexer_options("test", source, options).parse()
+        )
+    }
+
+    fn parse(source: &str) -> String {
+        format!("{}", Parser::new("test", source).parse())
+    }
+
+    #[test]
+    fn semicolon_free_program() {
+        assert_eq!(
+            parse_newlines("let a = 1\nlet b = a + 2\nmut b = b * 2\nprintln[a, b]\n"),
+            parse("let a = 1; let b = a + 2; mut b = b * 2; println[a, b];")
+        );
+    }
+
+    #[test]
+    fn blocks_and_comments() {
+        assert_eq!(
+            parse_newlines("fn f[x] {\n  # comment\n  return x + 1\n}\nwhile (true) { break }\n"),
+            parse("fn f[x] { return x + 1; } while (true) { break }")
+        );
+    }
+
+    #[test]
+    fn semicolons_still_allowed() {
+        assert_eq!(
+            parse_newlines("let a = 1; let b = 2\nlet c = 3;"),
+            parse("let a = 1; let b = 2; let c = 3;")
+        );
+    }
+
+    #[test]
+    fn expression_continues_after_operator() {
+        assert_eq!(
+            parse_newlines("let a = 1 +\n  2\n2x"),
+            parse("let a = 1 + 2; 2x;")
+        );
+    }
+
+    #[test]
+    fn line_break_is_not_implicit_multiplication() {
+        assert_eq!(parse_newlines("a\nb"), parse("a; b;"));
+    }
+}
+
+pub mod comment_tests {
+    use crate::parser::Parser;
+    use symboscript_lexer::LexerOptions;
+    use symboscript_types::parser::Statement;
+
+    fn parse_with_comments(source: &str, newlines: bool) -> String {
+        let options = LexerOptions {
+            comment: true,
+            newlines,
+            ..Default::default()
+        };
+
+        format!(
+            "{}",
+            Parser::with_lexer_options("test", source, options).parse()
+        )
+    }
+
+    #[test]
+    fn comment_tokens_are_skipped() {
+        assert_eq!(
+            parse_with_comments("let x = 1; # note", false),
+            "let x = 1;\n"
+        );
+        assert_eq!(
+            parse_with_comments("#/ doc /# let x = 1 #/ inline /# + 2; # note\nx;", false),
+            "let x = (1+2);\nx;\n"
+        );
+        assert_eq!(
+            parse_with_comments("let x = 1 # note\nx", true),
+            "let x = 1;\nx;\n"
+        );
+    }
+
+    #[test]
+    fn doc_comments() {
+        let source = "## Adds two numbers.\n##   Curried.\nfn add[a, b, ..] { return a + b; }
+# plain comment
+let x = 1;
+## The origin
+scope origin { }
+## doc\n\nlet y = 2;
+## doc
+# note
+let z = 3;
+## doc
+let w = 4;";
+
+        let ast = Parser::new("test", source).parse();
+        let docs: Vec<Option<&str>> = ast
+            .program
+            .body
+            .iter()
+            .map(|statement| match statement {
+                Statement::FunctionDeclaration(decl) => decl.doc.as_deref(),
+                Statement::VariableDeclaration(decl) => decl.doc.as_deref(),
+                Statement::ScopeDeclaration(decl) => decl.doc.as_deref(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(
+            docs,
+            [
+                Some("Adds two numbers.\nCurried."),
+                None,
+                Some("The origin"),
+                None,
+                None,
+                Some("doc")
+            ]
+        );
+
+        // the formatter's comment tokens don't change what's attached
+        let options = LexerOptions {
+            comment: true,
+            newlines: true,
+            ..Default::default()
+        };
+        let ast = Parser::with_lexer_options("test", "## doc\nlet x = 1", options).parse();
+        let Statement::VariableDeclaration(decl) = &ast.program.body[0] else {
+            unreachable!()
+        };
+        assert_eq!(decl.doc.as_deref(), Some("doc"));
+    }
+}
+
+pub mod diagnostic_tests {
+    use crate::parser::Parser;
+    use symboscript_lexer::LexerOptions;
+    use symboscript_types::diagnostic::{Diagnostic, Note, Severity};
+
+    /// Diagnostics of the program. Sources mark variables as used with statements like `x;`,
+    /// so `unused_expression` is allowed, it is tested on its own
+    fn warnings(source: &str) -> Vec<Diagnostic> {
+        let mut parser = Parser::new("test", source);
+        parser.allow_lint("unused_expression");
+        parser.parse();
+        parser.diagnostics().iter().cloned().collect()
+    }
+
+    #[test]
+    fn unused_let_is_a_warning() {
+        let mut parser = Parser::new("test", "let x = 1; let y = 2; println[y];");
+        let ast = parser.parse();
+
+        assert_eq!(
+            format!("{ast}"),
+            "let x = 1;\nlet y = 2;\n(println[[y]]);\n"
+        );
+        assert_eq!(
+            parser.diagnostics().iter().collect::<Vec<_>>(),
+            vec![&Diagnostic {
+                severity: Severity::Warning,
+                code: "unused_variable",
+                message: "Variable `x` is never used".to_owned(),
+                span: 0..10,
+                note: None,
+            }]
+        );
+        assert!(!parser.diagnostics().has_errors());
+    }
+
+    #[test]
+    fn used_variables_are_not_reported() {
+        assert!(warnings("let f = 1; f[]; let a = 2; mut a += 1; let _b = 3;").is_empty());
+        assert!(warnings("let s = 1; fn g[] { return s; }").is_empty());
+        assert_eq!(warnings("let [a, b] = [1, 2]; a;").len(), 1);
+    }
+
+    #[test]
+    fn unused_expression() {
+        let unused = |source| {
+            let mut parser = Parser::new("test", source);
+            parser.parse();
+            parser
+                .diagnostics()
+                .iter()
+                .filter(|d| d.code == "unused_expression")
+                .map(|d| (d.message.clone(), &source[d.span.clone()]))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            unused("1 + 2;"),
+            [("Value of the expression is never used".to_owned(), "1 + 2")]
+        );
+        assert_eq!(unused("let x = 1; fn f[] { x; -x ? 1 : none; }").len(), 2);
+
+        // calls and updates may have side effects
+        assert!(unused("print[1];").is_empty());
+        assert!(unused("let x = 1; ++x; --x; 1 + f[x]; x.len[]; delete x;").is_empty());
+    }
+
+    #[test]
+    fn unreachable_after_return() {
+        let source = "fn f[x] { return x; x + 1; x; }";
+
+        assert_eq!(
+            warnings(source),
+            vec![Diagnostic {
+                severity: Severity::Warning,
+                code: "unreachable_code",
+                message: "Unreachable statement after `return`".to_owned(),
+                span: 20..25,
+                note: None,
+            }]
+        );
+        assert_eq!(&source[20..25], "x + 1");
+
+        let messages = |source| {
+            warnings(source)
+                .into_iter()
+                .map(|d| d.message)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            messages("while (true) { break; f[]; } loop { if (true) { continue } else { throw 1; f[]; } }"),
+            vec![
+                "Unreachable statement after `break`",
+                "Unreachable statement after `throw`"
+            ]
+        );
+        assert!(messages("fn f[] { if (true) { return 1; } f[]; }").is_empty());
+    }
+
+    #[test]
+    fn shadowed_variable() {
+        let source = "let x = 1; if (x) { let x = 2; x; }";
+        let shadowing = |source| {
+            warnings(source)
+                .into_iter()
+                .filter(|d| d.code == "shadowed_variable")
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            shadowing(source),
+            vec![Diagnostic {
+                severity: Severity::Warning,
+                code: "shadowed_variable",
+                message: "Variable `x` shadows a variable from an outer scope".to_owned(),
+                span: 20..30,
+                note: Some(Note {
+                    message: "`x` is declared here".to_owned(),
+                    span: 0..10,
+                }),
+            }]
+        );
+        assert_eq!(&source[20..30], "let x = 2;");
+
+        // redeclaring in the same block and declaring in sibling blocks is fine
+        assert!(shadowing("let x = 1; let x = 2; x;").is_empty());
+        assert!(shadowing("if (1) { let a = 1; a; } else { let a = 2; a; }").is_empty());
+        assert!(shadowing("let y = { let y = 1; y }; y;").is_empty());
+
+        let mut parser = Parser::new("test", source);
+        parser.allow_lint("shadowed_variable");
+        parser.allow_lint("unused_expression");
+        parser.parse();
+        assert!(parser.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn call_arity() {
+        let source = "fn add[a, b] { return a + b; } add[1];";
+
+        assert_eq!(
+            warnings(source),
+            vec![Diagnostic {
+                severity: Severity::Error,
+                code: "call_arity",
+                message: "Function `add` takes 2 arguments, got 1".to_owned(),
+                span: 31..37,
+                note: None,
+            }]
+        );
+        assert_eq!(&source[31..37], "add[1]");
+
+        let errors = |source| {
+            let mut parser = Parser::new("test", source);
+            parser.parse();
+            parser.diagnostics().has_errors()
+        };
+
+        assert!(errors("fn f[] { 1; } if (1) { f[2, b = 1]; } else {}"));
+        assert!(!errors("fn add[a, b] { a + b; } add[1, b = 2];"));
+        // curried and redeclared functions are not checked
+        assert!(!errors("fn add[a, b, ..] { a + b; } add[1];"));
+        assert!(!errors("fn g[a] { a; } fn f[g] { g[]; } f[1];"));
+    }
+
+    #[test]
+    fn update_of_non_variable() {
+        let source = "let x = 1; ++x; --(x + 1); ++5;";
+
+        assert_eq!(
+            warnings(source),
+            vec![
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: "invalid_update",
+                    message: "`--` can only update a variable".to_owned(),
+                    span: 19..24,
+                    note: None,
+                },
+                Diagnostic {
+                    severity: Severity::Error,
+                    code: "invalid_update",
+                    message: "`++` can only update a variable".to_owned(),
+                    span: 29..30,
+                    note: None,
+                }
+            ]
+        );
+        assert_eq!(&source[19..24], "x + 1");
+        assert_eq!(&source[29..30], "5");
+    }
+
+    #[test]
+    fn break_outside_of_loop() {
+        let source = "let _x = 1; break;";
+
+        assert_eq!(
+            warnings(source),
+            vec![Diagnostic {
+                severity: Severity::Error,
+                code: "misplaced_statement",
+                message: "`break` outside of a loop".to_owned(),
+                span: 12..17,
+                note: None,
+            }]
+        );
+
+        let errors = |source| {
+            let mut parser = Parser::new("test", source);
+            parser.parse();
+            parser.diagnostics().has_errors()
+        };
+
+        assert!(!errors(
+            "while (true) { if (1) { break; } else { continue } }"
+        ));
+        assert!(!errors("let v = loop { break 1; };"));
+        assert!(errors("while (true) { fn f[] { continue } f[]; }"));
+        assert!(errors("while (true) {} else { break; }"));
+    }
+
+    #[test]
+    fn return_outside_of_function() {
+        let source = "return 1;";
+
+        assert_eq!(
+            warnings(source),
+            vec![Diagnostic {
+                severity: Severity::Error,
+                code: "misplaced_statement",
+                message: "`return` outside of a function".to_owned(),
+                span: 0..9,
+                note: None,
+            }]
+        );
+
+        let mut parser = Parser::new("test", "fn f[] { while (true) { return 1; } } f[];");
+        parser.parse();
+        assert!(!parser.diagnostics().has_errors());
+    }
+
+    #[test]
+    fn lexer_limits() {
+        let limited = |max_source_len, max_tokens| {
+            let options = LexerOptions {
+                recover: true,
+                max_source_len,
+                max_tokens,
+                ..Default::default()
+            };
+
+            let mut parser = Parser::with_lexer_options("test", "let x = 1; x;", options);
+            parser.allow_lint("unused_expression");
+            let ast = parser.parse();
+            let codes: Vec<_> = parser.diagnostics().iter().map(|d| d.code).collect();
+            (ast.program.body.len(), codes)
+        };
+
+        assert_eq!(limited(None, None), (2, vec![]));
+        assert_eq!(limited(Some(5), None), (0, vec!["source_too_large"]));
+        // `x;` is cut off, so `x` is unused
+        assert_eq!(
+            limited(None, Some(5)),
+            (1, vec!["too_many_tokens", "unused_variable"])
+        );
+    }
+}
+
+pub mod block_tests {
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> String {
+        format!("{}", Parser::new("test", source).parse())
+    }
+
+    #[test]
+    fn block_expression() {
+        assert_eq!(
+            parse("let x = { let a = 1; a + 1 };"),
+            "let x = {\nlet a = 1;\n(a+1)\n};\n"
+        );
+        assert_eq!(parse("let x = { 1 };"), "let x = {\n1\n};\n");
+        assert_eq!(parse("let x = {};"), "let x = {\nNone\n};\n");
+    }
+
+    #[test]
+    fn block_without_value() {
+        assert_eq!(
+            parse("let x = { f[]; };"),
+            "let x = {\n(f[[]]);\nNone\n};\n"
+        );
+    }
+
+    #[test]
+    fn block_statement_is_unchanged() {
+        assert_eq!(parse("{ let a = 1; }"), "{\nlet a = 1;\n}\n");
+    }
+}
+
+pub mod stmt_tests {
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> String {
+        format!("{}", Parser::new("test", source).parse())
+    }
+
+    #[test]
+    fn defer_statement() {
+        assert_eq!(
+            parse("defer f[]; defer { a; }"),
+            "defer {\n(f[[]]);\n}\ndefer {\na;\n}\n"
+        );
+    }
+
+    #[test]
+    fn curried_function_and_chained_call() {
+        assert_eq!(
+            parse("fn add[a, b, ..] return a + b; add[2][3];"),
+            "fn add(a, b, ..) {\nreturn (a+b);\n}\n(add[[2]][[3]]);\n"
+        );
+    }
+
+    #[test]
+    fn named_arguments() {
+        assert_eq!(parse("f[1, b = 2 + 3];"), "(f[[1, b = (2+3)]]);\n");
+        assert_eq!(parse("f[a == 1];"), "(f[[(a==1)]]);\n");
+    }
+
+    #[test]
+    fn while_let() {
+        assert_eq!(
+            parse("while let x = next[] { f[x]; }"),
+            "while let x = (next[[]]) {\n(f[[x]]);\n}\n"
+        );
+    }
+
+    #[test]
+    fn destructuring_declaration() {
+        assert_eq!(parse("let [a, b] = [1, 2];"), "let [a, b] = [1, 2];\n");
+        assert_eq!(parse("let [a] = f[];"), "let [a] = (f[[]]);\n");
+    }
+
+    #[test]
+    fn scope_extension() {
+        assert_eq!(
+            parse("scope B : A { let a = 1; }"),
+            "scope B : A {\nlet a = 1;\n}\n"
+        );
+        assert_eq!(parse("scope B { }"), "scope B {\n\n}\n");
+    }
+
+    #[test]
+    fn bitwise_assign() {
+        assert_eq!(parse("mut x <<= 2;"), "x <<= 2\n");
+        assert_eq!(parse("mut x >>= 2;"), "x >>= 2\n");
+        assert_eq!(parse("mut x &= 6;"), "x &= 6\n");
+        assert_eq!(parse("mut x |= 6;"), "x |= 6\n");
+        assert_eq!(parse("mut x bxor= 6;"), "x bxor= 6\n");
+        assert_eq!(parse("mut x ^= 2;"), "x ^= 2\n");
+        assert_eq!(parse("a bxor b==c;"), "((a bxor b)==c);\n");
+        assert_eq!(parse("x<<y>>z&&a||b;"), "((((x<<y)>>z)&&a)||b);\n");
+    }
+
+    #[test]
+    fn elif() {
+        assert_eq!(
+            parse("if (a) { b; } elif (c) { d; } elif (e) f; else { g; }"),
+            parse("if (a) { b; } else if (c) { d; } else if (e) f; else { g; }")
+        );
+        assert_eq!(
+            parse("if (a) b; elif (c) d;"),
+            "if a {\nb;\n} else {\nif c {\nd;\n} else {\n\n}\n}\n"
+        );
+    }
+
+    #[test]
+    fn none_literal() {
+        assert_eq!(parse("let x = none;"), "let x = None;\n");
+        assert_eq!(parse("x == None;"), "(x==None);\n");
+    }
+
+    #[test]
+    fn double_star_power() {
+        assert_eq!(parse("2 ** 3;"), parse("2 ^ 3;"));
+        assert_eq!(parse("mut x **= 2;"), "x ^= 2\n");
+        assert_eq!(parse("mut x *= 2;"), "x *= 2\n");
+    }
+
+    #[test]
+    fn repeat_statement() {
+        assert_eq!(parse("repeat (3) { a; }"), "repeat (3) {\na;\n}\n");
+        assert_eq!(parse("repeat (n + 1) a;"), "repeat ((n+1)) {\na;\n}\n");
+    }
+
+    #[test]
+    fn loop_else() {
+        assert_eq!(
+            parse("while (a) { b; } else { c; }"),
+            "while (a) {\nb;\n} else {\nc;\n}\n"
+        );
+        assert_eq!(parse("while (a) b;"), "while (a) {\nb;\n}\n");
+        assert_eq!(
+            parse("for (let i = 0; i < 3; ++i) b; else c;"),
+            "for (let i = 0; (i<3); (++i)) {\nb;\n} else {\nc;\n}\n"
+        );
+    }
+    #[test]
+    fn empty_programs() {
+        for source in ["", "   \n", "# just a comment", "#/ doc /#\n"] {
+            let mut parser = Parser::new("test", source);
+
+            assert!(parser.parse().program.body.is_empty(), "{source:?}");
+            assert!(parser.diagnostics().is_empty());
+        }
+    }
+}
+
+pub mod visitor_tests {
+    use crate::{parser::Parser, visitor::*};
+    use symboscript_types::parser::*;
+
+    #[derive(Default)]
+    struct BinaryCounter {
+        count: usize,
+    }
+
+    impl Visitor for BinaryCounter {
+        fn visit_expression(&mut self, expression: &Expression) {
+            if let Expression::BinaryExpression(_) = expression {
+                self.count += 1;
+            }
+
+            walk_expression(self, expression);
+        }
+    }
+
+    fn count_binary(source: &str) -> usize {
+        let mut counter = BinaryCounter::default();
+        walk_ast(&mut counter, &Parser::new("test", source).parse());
+
+        counter.count
+    }
+
+    #[test]
+    fn counts_binary_expressions() {
+        assert_eq!(count_binary("1 + 2 * 3;"), 2);
+        assert_eq!(
+            count_binary("fn f[a] { return a - 1; } let x = f[2 ^ 2] + { let y = 1 + 1; y };"),
+            4
+        );
+        assert_eq!(
+            count_binary("while (i < 3) { if (a == b) { print[a.b]; } } else { c; }"),
+            2
+        );
+        assert_eq!(count_binary("let x = f[[1, 2]];"), 0);
+    }
+}
+
+pub mod sexpr_tests {
+    use crate::{parser::Parser, SExpr};
+
+    fn sexpr(source: &str) -> String {
+        SExpr(&Parser::new("test", source).parse()).to_string()
+    }
+
+    #[test]
+    fn small_program() {
+        assert_eq!(
+            sexpr(
+                r#"let x = 1 + 2 * 3;
+fn add[a, b] { return a + b; }
+if (x > 2) { println[add[x, 1], "big"]; } else { mut x -= 1; }
+let s = point.x;
+let g = f[1][2];"#
+            ),
+            r#"(let x (+ 1 (* 2 3)))
+(fn add (a b) (block (return (+ a b))))
+(if (> x 2) (block (call println (call add x 1) "big")) (block (-= x 1)))
+(let s (. point x))
+(let g (call (call f 1) 2))
+"#
+        );
+    }
+
+    #[test]
+    fn with_statement() {
+        assert_eq!(
+            sexpr("with open[path] as file { read[file]; }"),
+            "(with file (call open path) (block (call read file)))\n"
+        );
+    }
+
+    #[test]
+    fn try_expression() {
+        assert_eq!(
+            sexpr("let x = try f[] + 1;"),
+            "(let x (+ (try (call f)) 1))\n"
+        );
+    }
+
+    #[test]
+    fn decorators() {
+        assert_eq!(
+            sexpr("@log\n@retry[3] fn f[x] { x; }"),
+            "(@ log (@ (call retry 3) (fn f (x) (block x))))\n"
+        );
+    }
+
+    #[test]
+    fn negative_literals() {
+        assert_eq!(
+            sexpr("-5; a-5; a - -2.5; -10i; -(5); -5.abs[]; - -5;"),
+            "-5\n(- a 5)\n(- a -2.5)\n-10i\n(- 5)\n(- (. 5 (call abs)))\n(- -5)\n"
+        );
+    }
+
+    #[test]
+    fn function_expression() {
+        assert_eq!(
+            sexpr("let inc = fn[x] { return x + 1; }; xs.map[fn[a, b, ..] { a; }];"),
+            r#"(let inc (fn (x) (block (return (+ x 1)))))
+(. xs (call map (fn (a b ..) (block a))))
+"#
+        );
+    }
+
+    #[test]
+    fn chained_members() {
+        assert_eq!(
+            sexpr(r#"a.b[x].c[y]; "s".upper[].len[];"#),
+            r#"(. (. a (call b x)) (call c y))
+(. (. "s" (call upper)) (call len))
+"#
+        );
+    }
+}
+
+pub mod span_tests {
+    use crate::parser::Parser;
+    use symboscript_types::parser::Statement;
+
+    /// Source text covered by the first statement of the program
+    fn first_span(source: &str) -> &str {
+        let ast = Parser::new("test", source).parse();
+        let node = ast.program.body[0].node().unwrap();
+
+        &source[node.start..node.end]
+    }
+
+    fn first_expression_span(source: &str) -> &str {
+        let ast = Parser::new("test", source).parse();
+        let Statement::ExpressionStatement(expression) = &ast.program.body[0] else {
+            panic!("expected an expression statement");
+        };
+        let node = expression.node();
+
+        &source[node.start..node.end]
+    }
+
+    #[test]
+    fn statements_end_at_closing_brace() {
+        assert_eq!(first_span("if (a) { b; } c;"), "if (a) { b; }");
+        assert_eq!(
+            first_span("if (a) { b; } else { c; } d;"),
+            "if (a) { b; } else { c; }"
+        );
+        assert_eq!(first_span("while (a) { b; } c;"), "while (a) { b; }");
+        assert_eq!(
+            first_span("for (let i = 0; i < 3; ++i) { b; } c;"),
+            "for (let i = 0; i < 3; ++i) { b; }"
+        );
+        assert_eq!(first_span("loop { break; } c;"), "loop { break; }");
+    }
+
+    #[test]
+    fn expressions_end_at_last_token() {
+        assert_eq!(first_expression_span("f[1, 2];"), "f[1, 2]");
+        assert_eq!(first_expression_span("a + b * c;"), "a + b * c");
+        assert_eq!(first_expression_span("a.b;"), "a.b");
+        assert_eq!(first_expression_span("-a;"), "-a");
+    }
+
+    /// Source text covered by the first statement in the body of a leading `while`
+    fn loop_body_span(source: &str) -> &str {
+        let ast = Parser::new("test", source).parse();
+        let Statement::WhileStatement(stmt) = &ast.program.body[0] else {
+            panic!("expected a while statement");
+        };
+        let node = stmt.body[0].node().unwrap();
+
+        &source[node.start..node.end]
+    }
+
+    #[test]
+    fn continue_ends_at_keyword() {
+        assert_eq!(loop_body_span("while (a) { continue } b;"), "continue");
+    }
+
+    #[test]
+    fn break_ends_before_semicolon() {
+        assert_eq!(loop_body_span("while (a) { break; } b;"), "break");
+        assert_eq!(loop_body_span("while (a) { break  ; b; }"), "break");
+        assert_eq!(loop_body_span("while (a) { break x + 1; }"), "break x + 1");
+    }
 }
@@ -0,0 +1,122 @@
+use symboscript_types::parser::*;
+
+/// Read-only traversal of the ast.
+///
+/// Every method walks into the children by default, override the ones you need
+/// and call the matching `walk_*` function to keep descending.
+pub trait Visitor {
+    fn visit_block(&mut self, block: &BlockStatement) {
+        walk_block(self, block);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_ast<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast) {
+    visitor.visit_block(&ast.program.body);
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &BlockStatement) {
+    for statement in block {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::ExpressionStatement(expr) => visitor.visit_expression(expr),
+        Statement::ReturnStatement(stmt) => visitor.visit_expression(&stmt.argument),
+        Statement::ThrowStatement(stmt) => visitor.visit_expression(&stmt.argument),
+        Statement::YieldStatement(stmt) => visitor.visit_expression(&stmt.argument),
+        Statement::DeferStatement(stmt) => visitor.visit_block(&stmt.body),
+        Statement::WithStatement(stmt) => {
+            visitor.visit_expression(&stmt.object);
+            visitor.visit_block(&stmt.body);
+        }
+        Statement::ContinueStatement(_) => {}
+        Statement::BreakStatement(stmt) => {
+            if let Some(argument) = &stmt.argument {
+                visitor.visit_expression(argument);
+            }
+        }
+        Statement::VariableDeclaration(decl) => visitor.visit_expression(&decl.init),
+        Statement::FunctionDeclaration(decl) => {
+            for decorator in &decl.decorators {
+                visitor.visit_expression(decorator);
+            }
+            visitor.visit_block(&decl.body);
+        }
+        Statement::ScopeDeclaration(decl) => visitor.visit_block(&decl.body),
+        Statement::ContextDeclaration(decl) => visitor.visit_block(&decl.body),
+        Statement::IfStatement(stmt) => {
+            visitor.visit_expression(&stmt.test);
+            visitor.visit_block(&stmt.consequent);
+            visitor.visit_block(&stmt.alternate);
+        }
+        Statement::ForStatement(stmt) => {
+            visitor.visit_statement(&stmt.init);
+            visitor.visit_expression(&stmt.test);
+            visitor.visit_expression(&stmt.update);
+            visitor.visit_block(&stmt.body);
+            visitor.visit_block(&stmt.alternate);
+        }
+        Statement::WhileStatement(stmt) => {
+            visitor.visit_expression(&stmt.test);
+            visitor.visit_block(&stmt.body);
+            visitor.visit_block(&stmt.alternate);
+        }
+        Statement::LoopStatement(stmt) => visitor.visit_block(&stmt.body),
+        Statement::RepeatStatement(stmt) => {
+            visitor.visit_expression(&stmt.count);
+            visitor.visit_block(&stmt.body);
+        }
+        Statement::BlockStatement(block) => visitor.visit_block(block),
+        Statement::AssignStatement(stmt) => visitor.visit_expression(&stmt.right),
+        Statement::ImportStatement(_) => {}
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &Expression) {
+    match expression {
+        Expression::BinaryExpression(expr) => {
+            visitor.visit_expression(&expr.left);
+            visitor.visit_expression(&expr.right);
+        }
+        Expression::UnaryExpression(expr) => visitor.visit_expression(&expr.right),
+        Expression::ConditionalExpression(expr) => {
+            visitor.visit_expression(&expr.test);
+            visitor.visit_expression(&expr.consequent);
+            visitor.visit_expression(&expr.alternate);
+        }
+        Expression::CallExpression(expr) => {
+            visitor.visit_expression(&expr.arguments);
+            for arguments in &expr.chained {
+                visitor.visit_expression(arguments);
+            }
+        }
+        Expression::MemberExpression(expr) => {
+            visitor.visit_expression(&expr.object);
+            visitor.visit_expression(&expr.property);
+        }
+        Expression::SequenceExpression(expr) => {
+            for expression in &expr.expressions {
+                visitor.visit_expression(expression);
+            }
+        }
+        Expression::WordExpression(expr) => visitor.visit_expression(&expr.argument),
+        Expression::NamedArgument(arg) => visitor.visit_expression(&arg.value),
+        Expression::BlockExpression(expr) => {
+            visitor.visit_block(&expr.body);
+            visitor.visit_expression(&expr.value);
+        }
+        Expression::LoopExpression(expr) => visitor.visit_block(&expr.body),
+        Expression::FunctionExpression(expr) => visitor.visit_block(&expr.body),
+        Expression::Literal(_) | Expression::Identifier(_) | Expression::None(_) => {}
+    }
+}
@@ -0,0 +1,95 @@
+use core::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A message about the source that doesn't have to stop the compilation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Stable name of the check, e.g. `unused_variable`
+    pub code: &'static str,
+
+    pub message: String,
+
+    /// Byte range in the source
+    pub span: Range<usize>,
+
+    /// Second place in the source the message refers to
+    pub note: Option<Note>,
+}
+
+/// Labeled span attached to a diagnostic, e.g. where a shadowed variable is declared
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub message: String,
+    pub span: Range<usize>,
+}
+
+/// Diagnostics collected while lexing, parsing or running a program
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.items.push(diagnostic);
+    }
+
+    pub fn warning(&mut self, code: &'static str, message: String, span: Range<usize>) {
+        self.push(Diagnostic {
+            severity: Severity::Warning,
+            code,
+            message,
+            span,
+            note: None,
+        });
+    }
+
+    pub fn error(&mut self, code: &'static str, message: String, span: Range<usize>) {
+        self.push(Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+            note: None,
+        });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter()
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
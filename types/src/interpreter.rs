@@ -1,31 +1,117 @@
 use crate::parser::*;
 use core::fmt;
-use std::{collections::HashMap, ops};
+use indexmap::IndexMap;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    mem, ops,
+    rc::Rc,
+};
 
 pub type Vault = HashMap<String, ScopeValue>;
 
-pub type Scope = HashMap<String, Value>;
+/// Members keep their insertion order, so enumerating a scope or hashmap is reproducible
+pub type Scope = IndexMap<String, Value>;
+
+/// Sequences are shared by reference, mutations are visible through every copy of the value
+pub type Sequence = Rc<RefCell<SequenceValue>>;
+
 #[derive(Clone, Debug)]
 pub enum Value {
     None,
     Number(f64),
+
+    /// Whole number from an integer literal (`1i`), arithmetic between integers stays exact
+    Integer(i64),
     Bool(bool),
     Str(String),
-    Sequence(Vec<Value>),
+    Sequence(Sequence),
+    Range(RangeValue),
+    Bytes(Vec<u8>),
 
+    /// Expression of a `:=` formula, `f.eval[]` computes its value
     Ast(Expression),
     ScopeRef(String),
 
     NativeFunction(NativeFunction),
     Function(FunctionDeclarator),
+    PartialFunction(Box<PartialFunction>),
+
+    /// Native method with the value it was looked up on: `"abc".upper`
+    BoundMethod(Box<BoundMethod>),
+
+    Err(Box<ErrorValue>),
+}
+
+/// Curried function with its first arguments bound
+#[derive(Clone, Debug)]
+pub struct PartialFunction {
+    pub function: FunctionDeclarator,
+    pub args: Vec<Value>,
+}
+
+/// Method of a value, called with the receiver before the arguments
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub function: NativeFunction,
+}
+
+/// `start..end..step`, both ends included. Items are computed when iterated, not stored
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub struct RangeValue {
+    pub start: i64,
+    pub end: i64,
+
+    /// Never zero, negative steps count down
+    pub step: i64,
+}
+
+impl RangeValue {
+    pub fn len(&self) -> usize {
+        let distance = (self.end as i128 - self.start as i128) / self.step as i128;
+
+        if distance < 0 {
+            0
+        } else {
+            distance as usize + 1
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-    Err(String),
+    pub fn iter(&self) -> impl Iterator<Item = Value> {
+        let range = *self;
+
+        (0..range.len()).map(move |i| Value::Number((range.start + i as i64 * range.step) as f64))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SequenceValue {
+    pub items: Vec<Value>,
+
+    /// Frozen sequences can't be changed
+    pub frozen: bool,
+}
+
+/// Thrown error, `message` holds the thrown value when it isn't an `Error[...]` object
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct ErrorValue {
+    pub message: Value,
+    pub code: Value,
+
+    /// Source span the error was raised at, set for runtime errors like type errors
+    pub origin: Option<Node>,
 }
 
 #[derive(Clone, Debug)]
 pub enum ControlFlow {
     Continue,
-    Break,
+    Break(Value),
     Return(Value),
     Yield(Value),
     Throw(Value),
@@ -36,22 +122,42 @@ pub enum ControlFlow {
 pub struct ScopeValue {
     pub values: Scope,
     pub named_scope_refs: Vec<String>,
+
+    /// Deferred blocks, run in reverse order when the scope exits
+    pub defers: Vec<BlockStatement>,
+
+    /// Scope this one extends, its members are visible through member access
+    pub base: Option<String>,
+
+    /// Frozen scopes (hashmaps) can't be changed
+    pub frozen: bool,
+}
+
+impl Default for ScopeValue {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ScopeValue {
     pub fn new() -> Self {
         Self {
-            values: HashMap::new(),
+            values: IndexMap::new(),
             named_scope_refs: vec![],
+            defers: vec![],
+            base: None,
+            frozen: false,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum NativeFunction {
     // io
     IOPrint,
     IOPrintln,
+    IOEprint,
+    IOEprintln,
 
     //HashMap
     HMNew,
@@ -67,39 +173,360 @@ pub enum NativeFunction {
     // conversion methods
     ToString,
     IsError,
+
+    // formulas
+    FormulaEval,
+
+    // errors
+    ErrorNew,
+
+    // deep copy and immutability
+    Clone,
+    Freeze,
+
+    // debugging
+    SizeOf,
+    Dbg,
+
+    // json
+    JsonParse,
+    JsonStringify,
+
+    // file system
+    FsReadFile,
+    FsWriteFile,
+
+    // assertions
+    Assert,
+    AssertEq,
+    AssertNe,
+
+    // string
+    StrUpper,
+    StrLower,
+    StrTrim,
+    StrSplit,
+    StrReplace,
+    StrContains,
+
+    // array
+    ArrMap,
+    ArrFilter,
+    ArrReduce,
+    ArrPush,
+    ArrPop,
+    ArrLen,
+
+    // bytes
+    BytesNew,
+    BytesGet,
+    BytesLen,
+    BytesFromBase64,
+    BytesToBase64,
+
+    /// Registered by the embedding program, looked up by name
+    Host(String),
+
+    /// Called with the value before the dot as the first argument: `"a".upper[]`
+    Method(Box<NativeFunction>),
+}
+
+// ----------------- Equality -----------------
+
+/// Structural equality: sequences and errors are compared element by element,
+/// a range equals the sequence of its items,
+/// numbers follow IEEE rules (`NaN != NaN`, `0 == -0`),
+/// functions are equal only to themselves (the same declaration),
+/// unevaluated `Ast` values are never equal.
+///
+/// `Eq` is not implemented because of `NaN`.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::None, Value::None) => true,
+            (Value::Number(n1), Value::Number(n2)) => n1 == n2,
+            (Value::Integer(n1), Value::Integer(n2)) => n1 == n2,
+            (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
+            (Value::Str(s1), Value::Str(s2)) => s1 == s2,
+            (Value::Sequence(s1), Value::Sequence(s2)) => {
+                Rc::ptr_eq(s1, s2) || s1.borrow().items == s2.borrow().items
+            }
+            (Value::Range(r1), Value::Range(r2)) => r1 == r2 || r1.iter().eq(r2.iter()),
+            (Value::Range(range), Value::Sequence(seq))
+            | (Value::Sequence(seq), Value::Range(range)) => {
+                range.iter().eq(seq.borrow().items.iter().cloned())
+            }
+            (Value::Bytes(b1), Value::Bytes(b2)) => b1 == b2,
+            (Value::ScopeRef(r1), Value::ScopeRef(r2)) => r1 == r2,
+            (Value::NativeFunction(f1), Value::NativeFunction(f2)) => f1 == f2,
+            (Value::Function(f1), Value::Function(f2)) => f1.node == f2.node && f1.id == f2.id,
+            (Value::PartialFunction(p1), Value::PartialFunction(p2)) => {
+                let (f1, f2) = (&p1.function, &p2.function);
+                f1.node == f2.node && f1.id == f2.id && p1.args == p2.args
+            }
+            (Value::BoundMethod(m1), Value::BoundMethod(m2)) => m1 == m2,
+            (Value::Err(e1), Value::Err(e2)) => e1 == e2,
+            _ => false,
+        }
+    }
+}
+
+/// Consistent with `PartialEq`: values that are equal have equal hashes
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // a range equals the sequence of its items, so it hashes like one
+        if let Value::Range(range) = self {
+            return Value::new_sequence(range.iter().collect()).hash(state);
+        }
+
+        mem::discriminant(self).hash(state);
+
+        match self {
+            Value::None | Value::Ast(_) => {}
+            // `0` and `-0` are equal, so they must hash the same
+            Value::Number(n) => (if *n == 0.0 { 0.0 } else { *n }).to_bits().hash(state),
+            Value::Integer(n) => n.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::Str(s) => s.hash(state),
+            Value::Sequence(seq) => seq.borrow().items.hash(state),
+            Value::Range(_) => unreachable!("ranges are hashed as sequences above"),
+            Value::Bytes(bytes) => bytes.hash(state),
+            Value::ScopeRef(r) => r.hash(state),
+            Value::NativeFunction(f) => f.hash(state),
+            Value::Function(f) => {
+                f.node.start.hash(state);
+                f.node.end.hash(state);
+                f.id.hash(state);
+            }
+            Value::PartialFunction(p) => {
+                p.function.node.start.hash(state);
+                p.function.node.end.hash(state);
+                p.function.id.hash(state);
+                p.args.hash(state);
+            }
+            Value::BoundMethod(m) => m.hash(state),
+            Value::Err(e) => e.hash(state),
+        }
+    }
 }
 
 // Display
 
+/// The name scripts call the function by, `array.map` for functions of a std scope
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NativeFunction::IOPrint => "print",
+            NativeFunction::IOPrintln => "println",
+            NativeFunction::IOEprint => "eprint",
+            NativeFunction::IOEprintln => "eprintln",
+
+            NativeFunction::HMNew => "hashmap.new",
+            NativeFunction::HMSet => "hashmap.set",
+            NativeFunction::HMGet => "hashmap.get",
+            NativeFunction::HMDelete => "hashmap.del",
+            NativeFunction::HMHas => "hashmap.has",
+            NativeFunction::HMLen => "hashmap.len",
+            NativeFunction::HMKeys => "hashmap.keys",
+            NativeFunction::HMValues => "hashmap.values",
+            NativeFunction::HMClear => "hashmap.clear",
+
+            NativeFunction::ToString => "to_string",
+            NativeFunction::IsError => "is_err",
+            NativeFunction::FormulaEval => "eval",
+            NativeFunction::ErrorNew => "Error",
+            NativeFunction::Clone => "clone",
+            NativeFunction::Freeze => "freeze",
+            NativeFunction::SizeOf => "sizeof",
+            NativeFunction::Dbg => "dbg",
+
+            NativeFunction::JsonParse => "json.parse",
+            NativeFunction::JsonStringify => "json.stringify",
+
+            NativeFunction::FsReadFile => "read_file",
+            NativeFunction::FsWriteFile => "write_file",
+
+            NativeFunction::Assert => "assert",
+            NativeFunction::AssertEq => "assert_eq",
+            NativeFunction::AssertNe => "assert_ne",
+
+            NativeFunction::StrUpper => "string.upper",
+            NativeFunction::StrLower => "string.lower",
+            NativeFunction::StrTrim => "string.trim",
+            NativeFunction::StrSplit => "string.split",
+            NativeFunction::StrReplace => "string.replace",
+            NativeFunction::StrContains => "string.contains",
+
+            NativeFunction::ArrMap => "array.map",
+            NativeFunction::ArrFilter => "array.filter",
+            NativeFunction::ArrReduce => "array.reduce",
+            NativeFunction::ArrPush => "array.push",
+            NativeFunction::ArrPop => "array.pop",
+            NativeFunction::ArrLen => "array.len",
+
+            NativeFunction::BytesNew => "bytes.new",
+            NativeFunction::BytesGet => "bytes.get",
+            NativeFunction::BytesLen => "bytes.len",
+            NativeFunction::BytesFromBase64 => "bytes.from_base64",
+            NativeFunction::BytesToBase64 => "bytes.to_base64",
+
+            NativeFunction::Host(name) => name,
+            NativeFunction::Method(function) => return write!(f, "{}", function),
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Value::None => write!(f, "None"),
             Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(n) => write!(f, "{}", n),
             Value::Bool(b) => write!(f, "{}", b),
             Value::Str(s) => write!(f, "{}", s),
-            Value::Sequence(_) => todo!(),
-            Value::Ast(_) => todo!(),
+            Value::Sequence(seq) => write!(
+                f,
+                "[{}]",
+                seq.borrow()
+                    .items
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Value::Range(range) if range.step == 1 => write!(f, "{}..{}", range.start, range.end),
+            Value::Range(range) => write!(f, "{}..{}..{}", range.start, range.end, range.step),
+            Value::Bytes(bytes) => write!(
+                f,
+                "bytes[{}]",
+                bytes
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Value::Ast(expression) => write!(f, "{}", expression),
             Value::ScopeRef(r) => write!(f, "{}", r),
-            Value::NativeFunction(_) => todo!(),
-            Value::Function(_) => todo!(),
+            Value::NativeFunction(function) => write!(f, "{}", function),
+            Value::Function(function) => write!(f, "{}", function),
+            Value::PartialFunction(p) => write!(
+                f,
+                "{}[{}, ..]",
+                p.function.id,
+                p.args
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Value::BoundMethod(m) => write!(f, "{}", m.function),
             Value::Err(e) => write!(f, "{}", e),
         }
     }
 }
 
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.code {
+            Value::None => write!(f, "{}", self.message),
+            _ => write!(f, "{} (code {})", self.message, self.code),
+        }
+    }
+}
+
 // ----------------- Math -----------------
 
 impl Value {
-    pub fn as_bool(&self) -> bool {
+    pub fn new_sequence(values: Vec<Value>) -> Value {
+        Value::Sequence(Rc::new(RefCell::new(SequenceValue {
+            items: values,
+            frozen: false,
+        })))
+    }
+
+    /// Wraps a thrown value into an error, `Error[...]` objects are kept as is
+    pub fn new_error(thrown: Value) -> Value {
+        match thrown {
+            Value::Err(_) => thrown,
+            _ => Value::Err(Box::new(ErrorValue {
+                message: thrown,
+                code: Value::None,
+                origin: None,
+            })),
+        }
+    }
+
+    /// Runtime error raised by the interpreter at `origin`
+    pub fn new_runtime_error(message: String, origin: Node) -> Value {
+        Value::Err(Box::new(ErrorValue {
+            message: Value::Str(message),
+            code: Value::None,
+            origin: Some(origin),
+        }))
+    }
+
+    /// Type name used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none",
+            Value::Number(_) => "number",
+            Value::Integer(_) => "int",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "str",
+            Value::Sequence(_) => "sequence",
+            Value::Range(_) => "range",
+            Value::Bytes(_) => "bytes",
+            Value::Ast(_) => "ast",
+            Value::ScopeRef(_) => "scope",
+            Value::NativeFunction(_)
+            | Value::Function(_)
+            | Value::PartialFunction(_)
+            | Value::BoundMethod(_) => "function",
+            Value::Err(_) => "err",
+        }
+    }
+
+    /// Whether conditions treat the value as true: everything except `none`, `false` and zero
+    pub fn is_truthy(&self) -> bool {
         match self {
             Value::None => false,
             Value::Number(n) => *n != 0.0,
+            Value::Integer(n) => *n != 0,
             Value::Bool(b) => *b,
             _ => true,
         }
     }
 
+    /// The number of a number or integer value, for host functions: `args[0].as_number()?`
+    pub fn as_number(&self) -> Result<f64, String> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Integer(n) => Ok(*n as f64),
+            got => Err(got.type_mismatch("number")),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<&str, String> {
+        match self {
+            Value::Str(s) => Ok(s),
+            got => Err(got.type_mismatch("str")),
+        }
+    }
+
+    /// Only `true` and `false`, unlike [`Value::is_truthy`]
+    pub fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            got => Err(got.type_mismatch("bool")),
+        }
+    }
+
+    fn type_mismatch(&self, expected: &str) -> String {
+        format!("Expected {expected}, got {}", self.type_name())
+    }
+
     pub fn and(&self, other: &Value) -> Value {
         Value::Bool(match (self, other) {
             (Value::Bool(b1), Value::Bool(b2)) => *b1 && *b2,
@@ -162,70 +589,97 @@ impl Value {
 
     pub fn pow(&self, other: &Value) -> Value {
         match (self, other) {
-            (Value::Number(left), Value::Number(right)) => Value::Number(left.powf(*right)),
+            (Value::Number(left), Value::Number(right)) => Value::Number(number_pow(*left, *right)),
             _ => Value::None,
         }
     }
 
+    /// `start..end` counts by one, `start..end..step` sets the step of a range
     pub fn range(&self, other: &Value) -> Value {
         match (self, other) {
-            (Value::Number(left), Value::Number(right)) => {
-                let left = left.round() as usize;
-                let right = right.round() as usize;
-
-                let val = (left..=right).collect::<Vec<usize>>();
-                Value::Sequence(val.into_iter().map(|p| Value::Number(p as f64)).collect())
+            (Value::Number(start), Value::Number(end)) => Value::Range(RangeValue {
+                start: start.round() as i64,
+                end: end.round() as i64,
+                step: 1,
+            }),
+            (Value::Range(range), Value::Number(step)) if step.round() != 0.0 => {
+                Value::Range(RangeValue {
+                    step: step.round() as i64,
+                    ..*range
+                })
             }
             _ => Value::None,
         }
     }
 
     pub fn equal(&self, other: &Value) -> Value {
-        Value::Bool(match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => n1 == n2,
-            (Value::Bool(b1), Value::Bool(b2)) => b1 == b2,
-            (Value::Str(s1), Value::Str(s2)) => s1 == s2,
-            // (Value::Ast(e1), Value::Ast(e2)) => e1 == e2,
-            (Value::ScopeRef(s1), Value::ScopeRef(s2)) => s1 == s2,
-            // (Value::Function(f1), Value::Function(f2)) => f1 == f2,
-            // (Value::NativeFunction(n1), Value::NativeFunction(n2)) => n1 == n2,
-            (Value::None, Value::None) => true,
-            // (Value::Sequence(s1), Value::Sequence(s2)) => s1 == s2,
-            _ => false,
-        })
+        Value::Bool(self == other)
     }
 
     pub fn not_equal(&self, other: &Value) -> Value {
-        return !self.equal(other);
+        !self.equal(other)
     }
 
+    /// `a is b`: arrays are the same only when both values reference one array.
+    /// Hashmaps, scopes and functions already compare by identity, other values by `==`
+    pub fn identical(&self, other: &Value) -> Value {
+        match (self, other) {
+            (Value::Sequence(s1), Value::Sequence(s2)) => Value::Bool(Rc::ptr_eq(s1, s2)),
+            _ => self.equal(other),
+        }
+    }
+
+    /// Ordering is defined for two numbers, two integers and two strings (by code points),
+    /// other operands give `None` and the interpreter reports a type error
     pub fn greater(&self, other: &Value) -> Value {
-        Value::Bool(match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => n1 > n2,
-            _ => false,
-        })
+        match (self, other) {
+            (Value::Number(n1), Value::Number(n2)) => Value::Bool(n1 > n2),
+            (Value::Integer(n1), Value::Integer(n2)) => Value::Bool(n1 > n2),
+            (Value::Str(s1), Value::Str(s2)) => Value::Bool(s1 > s2),
+            _ => Value::None,
+        }
     }
 
     pub fn greater_equal(&self, other: &Value) -> Value {
-        Value::Bool(match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => n1 >= n2,
-            _ => false,
-        })
+        match (self, other) {
+            (Value::Number(n1), Value::Number(n2)) => Value::Bool(n1 >= n2),
+            (Value::Integer(n1), Value::Integer(n2)) => Value::Bool(n1 >= n2),
+            (Value::Str(s1), Value::Str(s2)) => Value::Bool(s1 >= s2),
+            _ => Value::None,
+        }
     }
 
     pub fn less(&self, other: &Value) -> Value {
-        Value::Bool(match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => n1 < n2,
-            _ => false,
-        })
+        match (self, other) {
+            (Value::Number(n1), Value::Number(n2)) => Value::Bool(n1 < n2),
+            (Value::Integer(n1), Value::Integer(n2)) => Value::Bool(n1 < n2),
+            (Value::Str(s1), Value::Str(s2)) => Value::Bool(s1 < s2),
+            _ => Value::None,
+        }
     }
 
     pub fn less_equal(&self, other: &Value) -> Value {
-        Value::Bool(match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => n1 <= n2,
-            _ => false,
-        })
+        match (self, other) {
+            (Value::Number(n1), Value::Number(n2)) => Value::Bool(n1 <= n2),
+            (Value::Integer(n1), Value::Integer(n2)) => Value::Bool(n1 <= n2),
+            (Value::Str(s1), Value::Str(s2)) => Value::Bool(s1 <= s2),
+            _ => Value::None,
+        }
+    }
+}
+
+/// `base ^ exponent`, non-negative integers that don't overflow `u64` are raised exactly
+/// (`powf` is off by a few units for results like `10^18`), other numbers use `powf`
+pub fn number_pow(base: f64, exponent: f64) -> f64 {
+    let is_int = |n: f64| n >= 0.0 && n.fract() == 0.0;
+
+    if is_int(base) && is_int(exponent) && base <= u64::MAX as f64 && exponent <= u32::MAX as f64 {
+        if let Some(result) = (base as u64).checked_pow(exponent as u32) {
+            return result as f64;
+        }
     }
+
+    base.powf(exponent)
 }
 
 impl ops::Add for Value {
@@ -236,6 +690,8 @@ impl ops::Add for Value {
             (Value::Number(n1), Value::Number(n2)) => Value::Number(n1 + n2),
             (Value::Number(n), Value::Str(str)) => Value::Str(format!("{}{}", n, str)),
             (Value::Str(str), Value::Number(n)) => Value::Str(format!("{}{}", str, n)),
+            (Value::Integer(n), Value::Str(str)) => Value::Str(format!("{}{}", n, str)),
+            (Value::Str(str), Value::Integer(n)) => Value::Str(format!("{}{}", str, n)),
             (Value::Str(str1), Value::Str(str2)) => Value::Str(str1 + &str2),
             (Value::Bool(b1), Value::Bool(b2)) => Value::Bool(b1 || b2),
 
@@ -310,7 +766,8 @@ impl ops::Not for Value {
     fn not(self) -> Self::Output {
         match self {
             Value::None => Value::None,
-            Value::Number(n) => Value::Bool(if n == 0.0 { true } else { false }),
+            Value::Number(n) => Value::Bool(n == 0.0),
+            Value::Integer(n) => Value::Bool(n == 0),
             Value::Bool(b) => Value::Bool(!b),
             _ => Value::Bool(false),
         }
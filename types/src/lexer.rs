@@ -36,7 +36,9 @@ impl Default for Token {
     }
 }
 
+/// Guaranteed to be one byte, kinds are compared and copied everywhere
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
 pub enum TokenKind {
     Eof, // end of file
     DocComment,
@@ -45,11 +47,16 @@ pub enum TokenKind {
     Skip,
     Start,
 
+    /// Line break, only emitted in the lexer newlines mode
+    Newline,
     Semicolon,
     Comma,
     Colon,
     Dot,
 
+    /// `@` before a decorator
+    At,
+
     // Operators
     Plus,
     Minus,
@@ -79,6 +86,9 @@ pub enum TokenKind {
     PipePipe,
     Xor,
 
+    /// `is`, reference identity
+    Is,
+
     // Unary logic operators
     ExclamationMark,
 
@@ -91,6 +101,12 @@ pub enum TokenKind {
     DivideAssign,
     PowerAssign,
     ModuloAssign,
+    BitLeftShiftAssign,
+    BitRightShiftAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    DefaultAssign,
 
     // Comparison operators
     Equal,
@@ -114,6 +130,8 @@ pub enum TokenKind {
     // Literals
     Number,
     Str,
+    /// `` `a${x}b` ``, the value holds the contents between the backticks
+    Template,
 
     // --- Keywords ---
 
@@ -125,9 +143,12 @@ pub enum TokenKind {
     // Keywords
     If,
     Else,
+    /// `elif`, same as `else if`
+    Elif,
     While,
     For,
     Loop,
+    Repeat,
     Let,
     Scope,   // Scope declaration
     Context, // Context scope declaration
@@ -140,6 +161,9 @@ pub enum TokenKind {
     Of,
     Delete,
     Throw,
+    Defer,
+    /// `with expr as name { ... }`
+    With,
 
     Mut,
 
@@ -151,6 +175,9 @@ pub enum TokenKind {
     Async,
     Await,
 
+    /// `try expr`, returns errors from the enclosing function
+    Try,
+
     Block,
 }
 
@@ -164,10 +191,12 @@ impl fmt::Display for TokenKind {
             TokenKind::Skip => write!(f, "Skip"),
             TokenKind::Start => write!(f, "Start"),
 
+            TokenKind::Newline => write!(f, "Newline"),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Dot => write!(f, "."),
+            TokenKind::At => write!(f, "@"),
 
             TokenKind::Plus => write!(f, "+"),
             TokenKind::Minus => write!(f, "-"),
@@ -180,7 +209,7 @@ impl fmt::Display for TokenKind {
             TokenKind::Ampersand => write!(f, "&"),
             TokenKind::Pipe => write!(f, "|"),
             TokenKind::Tilde => write!(f, "~"),
-            TokenKind::BitXor => write!(f, "^"),
+            TokenKind::BitXor => write!(f, "bxor"),
             TokenKind::BitLeftShift => write!(f, "<<"),
             TokenKind::BitRightShift => write!(f, ">>"),
 
@@ -191,6 +220,7 @@ impl fmt::Display for TokenKind {
             TokenKind::AmpersandAmpersand => write!(f, "&&"),
             TokenKind::PipePipe => write!(f, "||"),
             TokenKind::Xor => write!(f, "xor"),
+            TokenKind::Is => write!(f, "is"),
             TokenKind::ExclamationMark => write!(f, "!"),
 
             TokenKind::Assign => write!(f, "="),
@@ -201,6 +231,12 @@ impl fmt::Display for TokenKind {
             TokenKind::DivideAssign => write!(f, "/="),
             TokenKind::PowerAssign => write!(f, "^="),
             TokenKind::ModuloAssign => write!(f, "%="),
+            TokenKind::BitLeftShiftAssign => write!(f, "<<="),
+            TokenKind::BitRightShiftAssign => write!(f, ">>="),
+            TokenKind::BitAndAssign => write!(f, "&="),
+            TokenKind::BitOrAssign => write!(f, "|="),
+            TokenKind::BitXorAssign => write!(f, "bxor="),
+            TokenKind::DefaultAssign => write!(f, "??="),
 
             TokenKind::Equal => write!(f, "=="),
             TokenKind::NotEqual => write!(f, "!="),
@@ -220,6 +256,7 @@ impl fmt::Display for TokenKind {
 
             TokenKind::Number => write!(f, "Number"),
             TokenKind::Str => write!(f, "String"),
+            TokenKind::Template => write!(f, "Template string"),
 
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
@@ -227,9 +264,11 @@ impl fmt::Display for TokenKind {
 
             TokenKind::If => write!(f, "if"),
             TokenKind::Else => write!(f, "else"),
+            TokenKind::Elif => write!(f, "elif"),
             TokenKind::While => write!(f, "while"),
             TokenKind::For => write!(f, "for"),
             TokenKind::Loop => write!(f, "loop"),
+            TokenKind::Repeat => write!(f, "repeat"),
             TokenKind::Let => write!(f, "let"),
             TokenKind::Scope => write!(f, "scope"),
             TokenKind::Context => write!(f, "context"),
@@ -242,12 +281,15 @@ impl fmt::Display for TokenKind {
             TokenKind::Of => write!(f, "of"),
             TokenKind::Delete => write!(f, "delete"),
             TokenKind::Throw => write!(f, "throw"),
+            TokenKind::Defer => write!(f, "defer"),
+            TokenKind::With => write!(f, "with"),
 
             TokenKind::Import => write!(f, "import"),
             TokenKind::As => write!(f, "as"),
 
             TokenKind::Await => write!(f, "await"),
             TokenKind::Async => write!(f, "async"),
+            TokenKind::Try => write!(f, "try"),
 
             TokenKind::Block => write!(f, "block"),
 
@@ -260,6 +302,8 @@ impl fmt::Display for TokenKind {
 pub enum TokenValue {
     None,
     Number(f64),
+    /// `10i`, numbers without a suffix and with `f` are `Number`
+    Integer(i64),
     Str(String),
     Identifier(String),
     Bool(bool),
@@ -279,7 +323,7 @@ mod math {
                         kind: self.kind,
                         start: self.start,
                         end: rhs.end,
-                        value: self.value + rhs.value,
+                        value: <TokenValue as $Op>::$fn(self.value, rhs.value),
                     }
                 }
             }
@@ -292,6 +336,7 @@ impl fmt::Display for TokenValue {
         match self {
             TokenValue::None => write!(f, ""),
             TokenValue::Number(s) => write!(f, "{}", s),
+            TokenValue::Integer(n) => write!(f, "{}i", n),
             TokenValue::Str(s) => write!(f, "\"{}\"", s),
             TokenValue::Identifier(s) => write!(f, "{}", s),
             TokenValue::Bool(b) => write!(f, "{}", b),
@@ -325,6 +370,8 @@ impl ops::Add for TokenValue {
 
             (TokenValue::Bool(_), _) | (_, TokenValue::Bool(_)) => TokenValue::None,
 
+            (TokenValue::Integer(_), _) | (_, TokenValue::Integer(_)) => TokenValue::None,
+
             (TokenValue::None, _) | (_, TokenValue::None) => TokenValue::None,
             (TokenValue::Identifier(_), _) | (_, TokenValue::Identifier(_)) => {
                 panic!("Identifiers can't be added")
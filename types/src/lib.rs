@@ -1,3 +1,5 @@
+pub mod diagnostic;
+
 #[cfg(any(feature = "lexer", feature = "parser", feature = "interpreter"))]
 pub mod lexer;
 
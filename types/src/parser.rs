@@ -15,7 +15,7 @@ pub struct Program {
     pub body: BlockStatement,
 }
 
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Node {
     /// Start offset in source
     pub start: usize,
@@ -42,8 +42,10 @@ pub enum Statement {
     ExpressionStatement(Expression),
     ReturnStatement(ReturnStatement),
     ThrowStatement(ThrowStatement),
+    DeferStatement(DeferStatement),
+    WithStatement(WithStatement),
     ContinueStatement(Node),
-    BreakStatement(Node),
+    BreakStatement(BreakStatement),
     YieldStatement(YieldStatement),
     VariableDeclaration(VariableDeclarator),
     FunctionDeclaration(FunctionDeclarator),
@@ -53,6 +55,7 @@ pub enum Statement {
     ForStatement(Box<ForStatement>),
     WhileStatement(WhileStatement),
     LoopStatement(LoopStatement),
+    RepeatStatement(RepeatStatement),
     BlockStatement(BlockStatement),
     AssignStatement(AssignStatement),
     ImportStatement(ImportStatement),
@@ -79,11 +82,25 @@ pub struct LoopStatement {
     pub body: BlockStatement,
 }
 
+/// `repeat (count) { ... }`, runs the body `count` times
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepeatStatement {
+    pub node: Node,
+    pub count: Expression,
+    pub body: BlockStatement,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WhileStatement {
     pub node: Node,
     pub test: Expression,
     pub body: BlockStatement,
+
+    /// `while let x = test`, the value of `test` is bound to `x` in every iteration
+    pub binding: Option<String>,
+
+    /// `else` block, runs when the loop ends without `break`
+    pub alternate: BlockStatement,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -93,6 +110,17 @@ pub struct ForStatement {
     pub test: Expression,
     pub update: Expression,
     pub body: BlockStatement,
+
+    /// `else` block, runs when the loop ends without `break`
+    pub alternate: BlockStatement,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BreakStatement {
+    pub node: Node,
+
+    /// `break value;` gives the result of a `loop` expression
+    pub argument: Option<Expression>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -107,6 +135,22 @@ pub struct ThrowStatement {
     pub argument: Expression,
 }
 
+/// Runs `body` when the enclosing scope exits
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeferStatement {
+    pub node: Node,
+    pub body: BlockStatement,
+}
+
+/// `with object as name { ... }`, calls `name.close[]` when the body ends, also when it throws
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WithStatement {
+    pub node: Node,
+    pub object: Expression,
+    pub name: String,
+    pub body: BlockStatement,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct YieldStatement {
     pub node: Node,
@@ -117,8 +161,15 @@ pub struct YieldStatement {
 pub struct VariableDeclarator {
     pub node: Node,
     pub id: String,
+
+    /// Names bound by `let [a, b] = ...`, `id` is empty then
+    pub pattern: Option<Vec<String>>,
+
     pub init: Expression,
     pub is_formula: bool,
+
+    /// Text of the `##` comment lines right above the declaration, one line per comment
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -128,13 +179,30 @@ pub struct FunctionDeclarator {
     pub params: Vec<String>,
     pub body: BlockStatement,
     pub is_async: bool,
+
+    /// Declared with a trailing `..` (`fn add[a, b, ..]`), calls with fewer arguments
+    /// bind them and return the rest of the function
+    pub curried: bool,
+
+    /// `@log fn f[] {}`, each one is called with the function and returns its replacement.
+    /// The decorator nearest to `fn` is applied first
+    pub decorators: Vec<Expression>,
+
+    /// Text of the `##` comment lines right above the declaration, one line per comment
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScopeDeclarator {
     pub node: Node,
     pub id: String,
+
+    /// Extended scope: `scope B : A { ... }`
+    pub base: Option<String>,
     pub body: BlockStatement,
+
+    /// Text of the `##` comment lines right above the declaration, one line per comment
+    pub doc: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -161,11 +229,25 @@ pub enum Expression {
     MemberExpression(Box<MemberExpression>),
     SequenceExpression(Box<SequenceExpression>),
     WordExpression(Box<WordExpression>),
+    BlockExpression(Box<BlockExpression>),
+    /// `loop` in value position, the value comes from `break value;`
+    LoopExpression(Box<LoopStatement>),
+    /// `fn[a, b] { ... }` in value position, an anonymous function with an empty `id`
+    FunctionExpression(Box<FunctionDeclarator>),
+    NamedArgument(Box<NamedArgument>),
     Literal(Literal),
     Identifier(Identifier),
     None(None),
 }
 
+/// `name = value` in the arguments of a call
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NamedArgument {
+    pub node: Node,
+    pub name: String,
+    pub value: Expression,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct None {
     pub node: Node,
@@ -211,6 +293,9 @@ pub struct CallExpression {
     pub node: Node,
     pub callee: String,
     pub arguments: Expression,
+
+    /// Argument lists of calls on the result, `f[1][2]`
+    pub chained: Vec<Expression>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -227,6 +312,14 @@ pub struct SequenceExpression {
     pub expressions: Vec<Expression>,
 }
 
+/// `{ statements; value }`, evaluates to the trailing expression without a semicolon
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockExpression {
+    pub node: Node,
+    pub body: BlockStatement,
+    pub value: Expression,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WordExpression {
     pub node: Node,
@@ -234,13 +327,20 @@ pub struct WordExpression {
     pub operator: WordOperator,
 }
 
+/// `await x` and `delete x`
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum WordOperator {
     Await,
-    New,
     Delete,
+    /// Throws an error value out of the enclosing function, other values pass through
+    Try,
 }
 
+/// Operator of a binary expression, shared by the parser and the interpreter.
+///
+/// The parser maps operator tokens to it in `bin_op`, compound assignments through
+/// `AssignOperator::binary_operator`, and the interpreter evaluates it in `binary_operation`.
+/// A new operator needs all three, the interpreter tests check every one of them.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum BinaryOperator {
     // Binary
@@ -265,6 +365,8 @@ pub enum BinaryOperator {
 
     Equal,
     NotEqual,
+    /// Same array, hashmap or function, other values are compared like `==`
+    Is,
     Less,
     LessEqual,
     Greater,
@@ -292,11 +394,71 @@ pub enum AssignOperator {
     DivideAssign,
     PowerAssign,
     ModuloAssign,
+    BitLeftShiftAssign,
+    BitRightShiftAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    /// Assigns only when the variable is `none`
+    DefaultAssign,
+}
+
+impl AssignOperator {
+    /// Binary operator a compound assignment applies, `x += 1` is `x = x + 1`.
+    /// `None` for `=` and `??=`
+    pub fn binary_operator(&self) -> Option<BinaryOperator> {
+        Some(match self {
+            AssignOperator::Assign | AssignOperator::DefaultAssign => return None,
+            AssignOperator::PlusAssign => BinaryOperator::Add,
+            AssignOperator::MinusAssign => BinaryOperator::Substract,
+            AssignOperator::MultiplyAssign => BinaryOperator::Multiply,
+            AssignOperator::DivideAssign => BinaryOperator::Divide,
+            AssignOperator::PowerAssign => BinaryOperator::Power,
+            AssignOperator::ModuloAssign => BinaryOperator::Modulo,
+            AssignOperator::BitLeftShiftAssign => BinaryOperator::BitLeftShift,
+            AssignOperator::BitRightShiftAssign => BinaryOperator::BitRightShift,
+            AssignOperator::BitAndAssign => BinaryOperator::BitAnd,
+            AssignOperator::BitOrAssign => BinaryOperator::BitOr,
+            AssignOperator::BitXorAssign => BinaryOperator::BitXor,
+        })
+    }
+}
+
+impl BinaryOperator {
+    /// Binding power shared by the parser and tools deciding on parentheses, higher binds tighter
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Range => 1,
+            BinaryOperator::Or | BinaryOperator::Xor => 2,
+            BinaryOperator::And => 3,
+
+            BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual
+            | BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Is => 4,
+
+            BinaryOperator::BitOr => 5,
+            BinaryOperator::BitXor => 6,
+            BinaryOperator::BitAnd => 7,
+            BinaryOperator::BitLeftShift | BinaryOperator::BitRightShift => 8,
+            BinaryOperator::Add | BinaryOperator::Substract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 10,
+            BinaryOperator::Power => 11,
+        }
+    }
+
+    /// Every operator is left associative for now, even `2^3^2` is `(2^3)^2`
+    pub fn is_right_associative(&self) -> bool {
+        false
+    }
 }
 
 //----------Display------------
 
-fn format_vec<T: fmt::Display>(vec: &Vec<T>, separator: &str) -> String {
+fn format_vec<T: fmt::Display>(vec: &[T], separator: &str) -> String {
     vec.iter()
         .map(|x| format!("{}", x))
         .collect::<Vec<String>>()
@@ -328,13 +490,19 @@ impl fmt::Display for Statement {
             Statement::ScopeDeclaration(expr) => write!(f, "{}", expr),
             Statement::ReturnStatement(expr) => write!(f, "{}", expr),
             Statement::ThrowStatement(expr) => write!(f, "{}", expr),
+            Statement::DeferStatement(expr) => write!(f, "{}", expr),
+            Statement::WithStatement(expr) => write!(f, "{}", expr),
             Statement::ContinueStatement(_) => write!(f, "continue;"),
-            Statement::BreakStatement(_) => write!(f, "break;"),
+            Statement::BreakStatement(stmt) => match &stmt.argument {
+                Some(argument) => write!(f, "break {};", argument),
+                None => write!(f, "break;"),
+            },
             Statement::YieldStatement(expr) => write!(f, "{}", expr),
             Statement::IfStatement(expr) => write!(f, "{}", expr),
             Statement::ForStatement(expr) => write!(f, "{}", expr),
             Statement::WhileStatement(expr) => write!(f, "{}", expr),
             Statement::LoopStatement(expr) => write!(f, "{}", expr),
+            Statement::RepeatStatement(expr) => write!(f, "{}", expr),
             Statement::BlockStatement(expr) => write!(f, "{{\n{}\n}}", format_vec(expr, "\n")),
             Statement::AssignStatement(expr) => write!(f, "{}", expr),
             Statement::ImportStatement(expr) => write!(f, "{}", expr),
@@ -351,7 +519,7 @@ impl fmt::Display for ImportStatement {
 
 impl fmt::Display for AssignStatement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} = {}", self.left, self.right)
+        write!(f, "{} {} {}", self.left, self.operator, self.right)
     }
 }
 
@@ -361,6 +529,24 @@ impl fmt::Display for ThrowStatement {
     }
 }
 
+impl fmt::Display for WithStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "with {} as {} {{\n{}\n}}",
+            self.object,
+            self.name,
+            format_vec(&self.body, "\n")
+        )
+    }
+}
+
+impl fmt::Display for DeferStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "defer {{\n{}\n}}", format_vec(&self.body, "\n"))
+    }
+}
+
 impl fmt::Display for ForStatement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -370,16 +556,39 @@ impl fmt::Display for ForStatement {
             self.test,
             self.update,
             format_vec(&self.body, "\n")
-        )
+        )?;
+
+        write_loop_else(f, &self.alternate)
     }
 }
 
 impl fmt::Display for WhileStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.binding {
+            Some(name) => write!(f, "while let {} = {} ", name, self.test)?,
+            None => write!(f, "while ({}) ", self.test)?,
+        }
+
+        write!(f, "{{\n{}\n}}", format_vec(&self.body, "\n"))?;
+
+        write_loop_else(f, &self.alternate)
+    }
+}
+
+fn write_loop_else(f: &mut fmt::Formatter<'_>, alternate: &BlockStatement) -> fmt::Result {
+    if alternate.is_empty() {
+        return Ok(());
+    }
+
+    write!(f, " else {{\n{}\n}}", format_vec(alternate, "\n"))
+}
+
+impl fmt::Display for RepeatStatement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "while ({}) {{\n{}\n}}",
-            self.test,
+            "repeat ({}) {{\n{}\n}}",
+            self.count,
             format_vec(&self.body, "\n")
         )
     }
@@ -405,11 +614,16 @@ impl fmt::Display for YieldStatement {
 
 impl fmt::Display for VariableDeclarator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = match &self.pattern {
+            Some(names) => format!("[{}]", names.join(", ")),
+            None => self.id.clone(),
+        };
+
         write!(
             f,
             "let {} {} {}",
-            self.id,
-            if self.is_formula == true { ":=" } else { "=" },
+            id,
+            if self.is_formula { ":=" } else { "=" },
             self.init
         )
     }
@@ -417,12 +631,17 @@ impl fmt::Display for VariableDeclarator {
 
 impl fmt::Display for FunctionDeclarator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for decorator in &self.decorators {
+            writeln!(f, "@{}", decorator)?;
+        }
+
         write!(
             f,
-            "{}fn {}({}) {{\n{}\n}}",
+            "{}fn {}({}{}) {{\n{}\n}}",
             if self.is_async { "async " } else { "" },
             self.id,
             format_vec(&self.params, ", "),
+            if self.curried { ", .." } else { "" },
             format_vec(&self.body, "\n")
         )
     }
@@ -430,12 +649,13 @@ impl fmt::Display for FunctionDeclarator {
 
 impl fmt::Display for ScopeDeclarator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "scope {} {{\n{}\n}}",
-            self.id,
-            format_vec(&self.body, "\n")
-        )
+        write!(f, "scope {}", self.id)?;
+
+        if let Some(base) = &self.base {
+            write!(f, " : {}", base)?;
+        }
+
+        write!(f, " {{\n{}\n}}", format_vec(&self.body, "\n"))
     }
 }
 
@@ -462,10 +682,69 @@ impl fmt::Display for IfStatement {
     }
 }
 
+impl Statement {
+    /// Source span of the statement, `None` for an empty block
+    pub fn node(&self) -> Option<Node> {
+        Some(match self {
+            Statement::ExpressionStatement(expr) => expr.node(),
+            Statement::ReturnStatement(stmt) => stmt.node,
+            Statement::ThrowStatement(stmt) => stmt.node,
+            Statement::DeferStatement(stmt) => stmt.node,
+            Statement::WithStatement(stmt) => stmt.node,
+            Statement::ContinueStatement(node) => *node,
+            Statement::BreakStatement(stmt) => stmt.node,
+            Statement::YieldStatement(stmt) => stmt.node,
+            Statement::VariableDeclaration(decl) => decl.node,
+            Statement::FunctionDeclaration(decl) => decl.node,
+            Statement::ScopeDeclaration(decl) => decl.node,
+            Statement::ContextDeclaration(decl) => decl.node,
+            Statement::IfStatement(stmt) => stmt.node,
+            Statement::ForStatement(stmt) => stmt.node,
+            Statement::WhileStatement(stmt) => stmt.node,
+            Statement::LoopStatement(stmt) => stmt.node,
+            Statement::RepeatStatement(stmt) => stmt.node,
+            Statement::AssignStatement(stmt) => stmt.node,
+            Statement::ImportStatement(stmt) => stmt.node,
+            Statement::BlockStatement(block) => {
+                Node::new(block.first()?.node()?.start, block.last()?.node()?.end)
+            }
+        })
+    }
+}
+
+impl Expression {
+    /// Source span of the expression
+    pub fn node(&self) -> Node {
+        match self {
+            Expression::BinaryExpression(expr) => expr.node,
+            Expression::UnaryExpression(expr) => expr.node,
+            Expression::ConditionalExpression(expr) => expr.node,
+            Expression::CallExpression(expr) => expr.node,
+            Expression::MemberExpression(expr) => expr.node,
+            Expression::SequenceExpression(expr) => expr.node,
+            Expression::WordExpression(expr) => expr.node,
+            Expression::BlockExpression(expr) => expr.node,
+            Expression::LoopExpression(expr) => expr.node,
+            Expression::FunctionExpression(expr) => expr.node,
+            Expression::NamedArgument(arg) => arg.node,
+            Expression::Literal(literal) => literal.node,
+            Expression::Identifier(id) => id.node,
+            Expression::None(none) => none.node,
+        }
+    }
+}
+
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expression::BinaryExpression(expr) => write!(f, "({})", expr),
+            // parenthesised like a negation, so `a - -5` doesn't print as `a--5`
+            Expression::Literal(literal)
+                if matches!(literal.value, TokenValue::Number(n) if n.is_sign_negative())
+                    || matches!(literal.value, TokenValue::Integer(n) if n < 0) =>
+            {
+                write!(f, "({})", literal)
+            }
             Expression::Literal(token) => write!(f, "{}", token),
             Expression::Identifier(token) => write!(f, "{}", token),
 
@@ -474,6 +753,10 @@ impl fmt::Display for Expression {
             Expression::CallExpression(expr) => write!(f, "({})", expr),
             Expression::MemberExpression(expr) => write!(f, "({})", expr),
             Expression::WordExpression(expr) => write!(f, "({})", expr),
+            Expression::BlockExpression(expr) => write!(f, "{}", expr),
+            Expression::LoopExpression(expr) => write!(f, "{}", expr),
+            Expression::FunctionExpression(expr) => write!(f, "{}", expr),
+            Expression::NamedArgument(arg) => write!(f, "{} = {}", arg.name, arg.value),
             Expression::SequenceExpression(expr) => {
                 let len = expr.expressions.len();
                 let mut k = 0;
@@ -492,6 +775,21 @@ impl fmt::Display for Expression {
     }
 }
 
+impl fmt::Display for BlockExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.body.is_empty() {
+            write!(f, "{{\n{}\n}}", self.value)
+        } else {
+            write!(
+                f,
+                "{{\n{}\n{}\n}}",
+                format_vec(&self.body, "\n"),
+                self.value
+            )
+        }
+    }
+}
+
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.value)
@@ -506,7 +804,13 @@ impl fmt::Display for Identifier {
 
 impl fmt::Display for BinaryExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}{}", self.left, self.operator, self.right)
+        match self.operator {
+            // keyword operators need spaces to be parsed back
+            BinaryOperator::Xor | BinaryOperator::BitXor | BinaryOperator::Is => {
+                write!(f, "{} {} {}", self.left, self.operator, self.right)
+            }
+            _ => write!(f, "{}{}{}", self.left, self.operator, self.right),
+        }
     }
 }
 
@@ -528,7 +832,13 @@ impl fmt::Display for ConditionalExpression {
 
 impl fmt::Display for CallExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}[{}]", self.callee, self.arguments)
+        write!(f, "{}[{}]", self.callee, self.arguments)?;
+
+        for arguments in &self.chained {
+            write!(f, "[{}]", arguments)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -561,8 +871,8 @@ impl fmt::Display for WordOperator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             WordOperator::Await => write!(f, "await"),
-            WordOperator::New => write!(f, "new"),
             WordOperator::Delete => write!(f, "delete"),
+            WordOperator::Try => write!(f, "try"),
         }
     }
 }
@@ -579,7 +889,7 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::Modulo => write!(f, "%"),
             BinaryOperator::And => write!(f, "&&"),
             BinaryOperator::Or => write!(f, "||"),
-            BinaryOperator::Xor => write!(f, "^"),
+            BinaryOperator::Xor => write!(f, "xor"),
             BinaryOperator::BitAnd => write!(f, "&"),
             BinaryOperator::BitOr => write!(f, "|"),
             BinaryOperator::BitXor => write!(f, "bxor"),
@@ -587,6 +897,7 @@ impl fmt::Display for BinaryOperator {
             BinaryOperator::BitRightShift => write!(f, ">>"),
             BinaryOperator::Equal => write!(f, "=="),
             BinaryOperator::NotEqual => write!(f, "!="),
+            BinaryOperator::Is => write!(f, "is"),
             BinaryOperator::Less => write!(f, "<"),
             BinaryOperator::LessEqual => write!(f, "<="),
             BinaryOperator::Greater => write!(f, ">"),
@@ -605,6 +916,12 @@ impl fmt::Display for AssignOperator {
             AssignOperator::DivideAssign => write!(f, "/="),
             AssignOperator::PowerAssign => write!(f, "^="),
             AssignOperator::ModuloAssign => write!(f, "%="),
+            AssignOperator::BitLeftShiftAssign => write!(f, "<<="),
+            AssignOperator::BitRightShiftAssign => write!(f, ">>="),
+            AssignOperator::BitAndAssign => write!(f, "&="),
+            AssignOperator::BitOrAssign => write!(f, "|="),
+            AssignOperator::BitXorAssign => write!(f, "bxor="),
+            AssignOperator::DefaultAssign => write!(f, "??="),
         }
     }
 }
@@ -1,7 +1,13 @@
-use std::cmp::max;
+use std::{
+    cmp::max,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use colored::Colorize;
-use symboscript_types::lexer::{Token, TokenKind::*};
+use colored::{Color, Colorize};
+use symboscript_types::{
+    diagnostic::{Diagnostic, Severity},
+    lexer::{Token, TokenKind::*},
+};
 
 pub fn output_tokens_colored(text: &str, tokens: &Vec<Token>, show_tokens: Option<bool>) {
     let show_tokens = show_tokens.unwrap_or(false);
@@ -10,13 +16,13 @@ pub fn output_tokens_colored(text: &str, tokens: &Vec<Token>, show_tokens: Optio
 
     for token in tokens {
         last_start = token.start;
-        print!("{}", text[last_end..last_start].to_string());
+        print!("{}", &text[last_end..last_start]);
         last_end = token.end;
 
         let s = if show_tokens {
-            format!("<{}>", text[token.start..token.end].to_string())
+            format!("<{}>", &text[token.start..token.end])
         } else {
-            format!("{}", text[token.start..token.end].to_string())
+            text[token.start..token.end].to_string()
         };
 
         print!("{}", {
@@ -31,10 +37,10 @@ pub fn output_tokens_colored(text: &str, tokens: &Vec<Token>, show_tokens: Optio
 
                 LParen | RParen | LAngle | RAngle => s.cyan(),
 
-                If | Else | While | For | Loop | Let | Return | Break | Continue | Function
-                | True | False | In => s.magenta(),
+                If | Else | Elif | While | For | Loop | Let | Return | Break | Continue
+                | Function | True | False | In => s.magenta(),
 
-                Str => s.truecolor(206, 145, 120),
+                Str | Template => s.truecolor(206, 145, 120),
 
                 DocComment | Comment => s.green(),
 
@@ -43,34 +49,104 @@ pub fn output_tokens_colored(text: &str, tokens: &Vec<Token>, show_tokens: Optio
         });
     }
 
-    print!("{}", text[last_end..].to_string());
+    print!("{}", &text[last_end..]);
 
     println!();
 }
 
+/// Columns a tab advances to the next multiple of, in reported positions and printed lines
+static TAB_WIDTH: AtomicUsize = AtomicUsize::new(1);
+
+/// Sets the tab width used in reports so columns match the user's editor, 1 by default
+pub fn set_tab_width(width: usize) {
+    TAB_WIDTH.store(width.max(1), Ordering::Relaxed);
+}
+
+/// 1-based column of the byte `offset` in `source`, tabs advance to the next multiple of `tab_width`
+pub fn column(source: &str, offset: usize, tab_width: usize) -> usize {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+
+    source[line_start..offset]
+        .chars()
+        .fold(0, |column, c| match c {
+            '\t' => (column / tab_width + 1) * tab_width,
+            _ => column + 1,
+        })
+        + 1
+}
+
+/// Replaces tabs with spaces up to the next tab stop, so pointers under the line stay aligned
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let width = expanded.chars().count();
+                expanded.push_str(&" ".repeat((width / tab_width + 1) * tab_width - width));
+            }
+            _ => expanded.push(c),
+        }
+    }
+
+    expanded
+}
+
 pub fn report_error(path: &str, source: &str, error: &str, start: usize, end: usize) {
+    print_at(path, source, error, start, end, Color::Red);
+
+    std::process::exit(1);
+}
+
+/// Prints a diagnostic at its span, unlike `report_error` this doesn't end the process
+pub fn report_diagnostic(path: &str, source: &str, diagnostic: &Diagnostic) {
+    let color = match diagnostic.severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::Yellow,
+    };
+
+    print_at(
+        path,
+        source,
+        &format!("{diagnostic}"),
+        diagnostic.span.start,
+        diagnostic.span.end,
+        color,
+    );
+
+    if let Some(note) = &diagnostic.note {
+        print_at(
+            path,
+            source,
+            &format!("note: {}", note.message),
+            note.span.start,
+            note.span.end,
+            Color::Blue,
+        );
+    }
+}
+
+fn print_at(path: &str, source: &str, error: &str, start: usize, end: usize, color: Color) {
     let line_start = max(source[..start].lines().count(), 1);
     let line_end = max(source[..end].lines().count(), 1);
 
-    let mut column_start = start - source[..start].rfind('\n').unwrap_or(0);
-    let mut column_end = end - source[..end].rfind('\n').unwrap_or(0);
+    let tab_width = TAB_WIDTH.load(Ordering::Relaxed);
 
-    if line_start == 1 || line_end == 1 {
-        column_end += 1;
-        column_start += 1;
-    }
+    let column_start = column(source, start, tab_width);
+    let mut column_end = column(source, end, tab_width);
 
     if column_end < column_start {
         column_end = source[..end].rfind('\n').unwrap_or(0);
     }
 
-    let near_text = source.lines().nth(line_end - 1).unwrap_or("").trim_end();
+    let near_text = expand_tabs(source.lines().nth(line_end - 1).unwrap_or(""), tab_width);
+    let near_text = near_text.trim_end();
 
     let line_n = format!("{line_end} |");
 
     let error_pointer = (" ".repeat(column_start + line_n.len())
         + "^".repeat(column_end - column_start).as_str())
-    .red()
+    .color(color)
     .bold();
 
     let error_pointer_text = error
@@ -81,7 +157,7 @@ pub fn report_error(path: &str, source: &str, error: &str, start: usize, end: us
                 " ".repeat(column_end - column_start) + &" ".repeat(column_start + line_n.len())
             ),
         )
-        .red()
+        .color(color)
         .bold();
 
     let file_src = format!(
@@ -95,7 +171,7 @@ pub fn report_error(path: &str, source: &str, error: &str, start: usize, end: us
         println!(
             "{} {}",
             format!("{} |", i).blue().bold(),
-            source.lines().nth(i - 1).unwrap_or("")
+            expand_tabs(source.lines().nth(i - 1).unwrap_or(""), tab_width)
         );
     }
 
@@ -103,6 +179,7 @@ pub fn report_error(path: &str, source: &str, error: &str, start: usize, end: us
         "{} {near_text}\n{error_pointer} {error_pointer_text}",
         line_n.to_string().blue().bold(),
     );
-
-    std::process::exit(1);
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,13 @@
+use crate::column;
+
+#[test]
+fn column_after_tab() {
+    let source = "let a = 1;\n\tb;";
+    let b = source.find('b').unwrap();
+
+    assert_eq!(column(source, 0, 1), 1);
+    assert_eq!(column(source, b, 1), 2);
+    assert_eq!(column(source, b, 4), 5);
+    assert_eq!(column("a\tb", 2, 4), 5);
+    assert_eq!(column("abcd\tb", 5, 4), 9);
+}